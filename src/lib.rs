@@ -52,7 +52,13 @@ pub use toql_core::query;
 pub use toql_core::query_parser;
 pub use toql_core::sql_builder;
 pub use toql_core::sql_builder_result;
+pub use toql_core::query_plan_cache;
 pub use toql_core::sql_mapper;
+pub use toql_core::mapper_registry;
+pub use toql_core::sql_mapper_schema;
+#[cfg(feature = "config")]
+pub use toql_core::sql_mapper_config;
+pub use toql_core::role_expr;
 pub use toql_core::fields_type;
 pub use toql_core::merge;
 pub use toql_core::indelup;
@@ -6,6 +6,7 @@
 use crate::query_parser::Rule;
 use std::fmt;
 use crate::sql_builder::SqlBuilderError;
+use crate::sql_mapper::SqlMapperError;
 
 use pest::error::Error as PestError;
 
@@ -27,8 +28,12 @@ use mysql::error::Error;
     MapperMissing(String),
     /// Unable to put database result into struct. Contains field name.
     ValueMissing(String),
+    /// Unable to put database result into a JSON object, because a field has the name of a path. Contains field name.
+    ValueClash(String),
     /// SQL Builder failed to turn Toql query into SQL query.
     SqlBuilderError(SqlBuilderError),
+    /// SQL Mapper failed to change a mapping.
+    SqlMapperError(SqlMapperError),
     #[cfg(feature = "mysqldb")]
     /// MySQL failed to run the SQL query. For feature `mysql`
     MySqlError(Error)
@@ -44,6 +49,12 @@ impl From<SqlBuilderError> for ToqlError {
     }
 }
 
+impl From<SqlMapperError> for ToqlError {
+        fn from(err: SqlMapperError) -> ToqlError {
+        ToqlError::SqlMapperError(err)
+    }
+}
+
 #[cfg(feature = "mysqldb")]
 impl From<Error> for ToqlError {
         fn from(err: Error) -> ToqlError {
@@ -68,9 +79,12 @@ impl fmt::Display for ToqlError {
                 write!(f, "no mapper found for `{}`", s),
             ToqlError::ValueMissing(ref s) =>
                 write!(f, "no value found for `{}`", s),
+            ToqlError::ValueClash(ref s) =>
+                write!(f, "value for `{}` clashes with a path of the same name", s),
             #[cfg(feature = "mysqldb")]
             ToqlError::MySqlError (ref e) => e.fmt(f),
             ToqlError::SqlBuilderError (ref e) => e.fmt(f),
+            ToqlError::SqlMapperError (ref e) => e.fmt(f),
             ToqlError::EncodingError (ref e) => e.fmt(f),
             ToqlError::QueryParserError (ref e) => e.fmt(f),
         }
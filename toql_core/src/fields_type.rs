@@ -5,4 +5,7 @@
 /// Used by code produced from Toql derive.
 pub trait FieldsType {
     type FieldsType;
+    /// Returns the fields of the struct, that start at a path.
+    /// Fields of joined type parameters are built with it, so manual implementations must provide it too.
+    fn fields_from_path(path: String) -> Self::FieldsType;
 }
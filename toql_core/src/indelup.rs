@@ -25,6 +25,7 @@
 //! 
 //! Note that operations are not cascaded. If you insert a struct `Foo` that contains another struct `Bar` only `Foo will be inserted.
 //! To deal with those dependencies, you are expected to make multiple calls. 
+//! Only the rows in link tables of merges with `through` are maintained, see [insert_links_sql](trait.Indelup.html#method.insert_links_sql).
 //! 
 //! If you *update* a struct, fields of type `Option<>` with value `None` are skipped. Read the guide for details!
 //! 
@@ -45,4 +46,11 @@ pub trait Indelup<'a, T: 'a> {
     fn update_one_sql (entity: & T) -> Result<(String, Vec<String>)>;
     /// Update many structs, returns tuple with SQL statement and SQL params or error.
     fn update_many_sql<I> (entities: I) -> Result<(String, Vec<String>)> where I: IntoIterator<Item = &'a T> + 'a + Clone;
+    /// Insert the link rows of merges through link tables, returns SQL statements with their SQL params or error.
+    /// Run them after the struct is inserted. Structs with an auto generated key have no link statements,
+    /// insert their links with the link functions of the merge, once the key is known.
+    fn insert_links_sql (_entity: & T) -> Result<Vec<(String, Vec<String>)>> { Ok(Vec::new()) }
+    /// Delete the link rows of merges through link tables, returns SQL statements with their SQL params or error.
+    /// Run them before the struct is deleted. To update the links, delete them and insert them again.
+    fn delete_links_sql (_entity: & T) -> Result<Vec<(String, Vec<String>)>> { Ok(Vec::new()) }
 }
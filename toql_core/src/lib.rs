@@ -8,7 +8,13 @@ pub mod query_parser;
 pub mod fields_type;
 pub mod sql_builder;
 pub mod sql_builder_result;
+pub mod query_plan_cache;
 pub mod sql_mapper;
+pub mod mapper_registry;
+pub mod sql_mapper_schema;
+#[cfg(feature = "config")]
+pub mod sql_mapper_config;
+pub mod role_expr;
 pub mod error;
 pub mod indelup;
 pub mod merge;
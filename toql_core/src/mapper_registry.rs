@@ -0,0 +1,162 @@
+//!
+//! The Mapper Registry holds all [SQL Mappers](../sql_mapper/struct.SqlMapper.html) of an application.
+//!
+//! Mappers are registered by name, usually the name of the mapped struct.
+//! Registering a struct with [register()](struct.MapperRegistry.html#method.register) also registers
+//! the mappers of all its joined and merged structs, so no mapper is missing at load time.
+//!
+//! ## Example
+//! ``` ignore
+//! let mut registry = MapperRegistry::new();
+//! registry.register::<User>();  // Registers `User`, and dependencies like `Phone`
+//! let registry = Arc::new(registry);
+//! ```
+//!
+//! ### Overlays
+//! The registry is `Send` and `Sync` and can be shared behind an `Arc`.
+//! To customize mappers, for example per tenant, create an overlay with [with_base()](struct.MapperRegistry.html#method.with_base).
+//! The overlay looks up mappers in its base registry, unless it has its own mapper with that name.
+//! [get_mut()](struct.MapperRegistry.html#method.get_mut) copies a mapper from the base into the overlay, so changes never affect the base.
+//!
+//! ### Merge depth
+//! Loading a struct also loads its merged structs, their merged structs and so on.
+//! The [maximum merge depth](struct.MapperRegistry.html#method.set_max_merge_depth) limits how deep the merges are loaded,
+//! for example to stop self referencing structs. An overlay uses the depth of its base, unless it sets its own.
+//!
+//! ### Migration
+//! [SqlMapperCache](../sql_mapper/type.SqlMapperCache.html) used to be a `HashMap<String, SqlMapper>`, now it is an alias of the registry.
+//! The registry keeps `new()`, `insert()`, `get()`, `get_mut()`, `remove()`, `contains_key()`, `keys()`, `iter()`, `len()` and `is_empty()`.
+//! Other `HashMap` methods, such as `entry()`, `values()` or `into_iter()`, are gone, use the methods above instead.
+//!
+//! Manual implementations of [Mapped](../sql_mapper/trait.Mapped.html) must add `type_name()`, the name of the mapper in the registry.
+//! `register_dependencies()` and `table_name()` have defaults. Derived structs need no changes.
+//!
+
+use crate::sql_mapper::validate_cache;
+use crate::sql_mapper::Mapped;
+use crate::sql_mapper::SqlMapper;
+use crate::sql_mapper::SqlMapperError;
+use std::collections::HashMap;
+use std::sync::Arc;
+
+/// Default for the [maximum merge depth](struct.MapperRegistry.html#method.max_merge_depth).
+pub const DEFAULT_MAX_MERGE_DEPTH: usize = 8;
+
+/// A registry that holds mappers by name.
+#[derive(Debug, Default)]
+pub struct MapperRegistry {
+    mappers: HashMap<String, SqlMapper>,
+    base: Option<Arc<MapperRegistry>>,
+    max_merge_depth: Option<usize>,
+}
+
+impl MapperRegistry {
+    /// Creates an empty registry.
+    pub fn new() -> Self {
+        MapperRegistry {
+            mappers: HashMap::new(),
+            base: None,
+            max_merge_depth: None,
+        }
+    }
+    /// Creates an empty overlay for a base registry.
+    pub fn with_base(base: Arc<MapperRegistry>) -> Self {
+        MapperRegistry {
+            mappers: HashMap::new(),
+            base: Some(base),
+            max_merge_depth: None,
+        }
+    }
+    /// Returns the base registry of an overlay.
+    pub fn base(&self) -> Option<&Arc<MapperRegistry>> {
+        self.base.as_ref()
+    }
+    /// Returns how many levels of merges are loaded. See [set_max_merge_depth()](struct.MapperRegistry.html#method.set_max_merge_depth).
+    pub fn max_merge_depth(&self) -> usize {
+        match self.max_merge_depth {
+            Some(d) => d,
+            None => self.base.as_ref().map_or(DEFAULT_MAX_MERGE_DEPTH, |b| b.max_merge_depth()),
+        }
+    }
+    /// Sets how many levels of merges are loaded. With 1 only the merges of the loaded struct are loaded,
+    /// with 0 no merges at all. The default is [DEFAULT_MAX_MERGE_DEPTH](constant.DEFAULT_MAX_MERGE_DEPTH.html).
+    pub fn set_max_merge_depth(&mut self, depth: usize) -> &mut Self {
+        self.max_merge_depth = Some(depth);
+        self
+    }
+    /// Registers the mapper for a struct and the mappers of its dependencies, unless they are already registered.
+    /// Returns a mutable reference to the mapper. Use it for configuration.
+    pub fn register<T: Mapped>(&mut self) -> &mut SqlMapper {
+        let name = T::type_name();
+        if !self.contains_key(&name) {
+            T::insert_new_mapper(self);
+        }
+        self.get_mut(&name).unwrap()
+    }
+    /// Inserts a mapper. Returns the mapper that was previously registered in this registry under the same name.
+    pub fn insert(&mut self, name: String, mapper: SqlMapper) -> Option<SqlMapper> {
+        self.mappers.insert(name, mapper)
+    }
+    /// Removes a mapper from this registry. Mappers of the base registry are not affected.
+    pub fn remove(&mut self, name: &str) -> Option<SqlMapper> {
+        self.mappers.remove(name)
+    }
+    /// Returns the mapper for a name. Looks in the base registry, if the mapper is missing.
+    pub fn get(&self, name: &str) -> Option<&SqlMapper> {
+        match self.mappers.get(name) {
+            Some(m) => Some(m),
+            None => self.base.as_ref().and_then(|b| b.get(name)),
+        }
+    }
+    /// Returns the mapper for a name to change it. A mapper from the base registry is copied into this registry first.
+    pub fn get_mut(&mut self, name: &str) -> Option<&mut SqlMapper> {
+        if !self.mappers.contains_key(name) {
+            let base_mapper = self.base.as_ref().and_then(|b| b.get(name)).cloned();
+            if let Some(m) = base_mapper {
+                self.mappers.insert(name.to_owned(), m);
+            }
+        }
+        self.mappers.get_mut(name)
+    }
+    /// Returns the mapper for a struct.
+    pub fn get_for<T: Mapped>(&self) -> Option<&SqlMapper> {
+        self.get(&T::type_name())
+    }
+    /// Returns the mapper for a struct to change it. See [get_mut()](struct.MapperRegistry.html#method.get_mut).
+    pub fn get_mut_for<T: Mapped>(&mut self) -> Option<&mut SqlMapper> {
+        self.get_mut(&T::type_name())
+    }
+    /// Returns true, if the registry or its base has a mapper with that name.
+    pub fn contains_key(&self, name: &str) -> bool {
+        self.get(name).is_some()
+    }
+    /// Returns the names of all mappers, including the base registry, sorted by name.
+    pub fn keys(&self) -> Vec<&String> {
+        self.iter().map(|(k, _)| k).collect()
+    }
+    /// Returns all mappers with their names, including the base registry, sorted by name.
+    pub fn iter(&self) -> std::vec::IntoIter<(&String, &SqlMapper)> {
+        let mut mappers: Vec<(&String, &SqlMapper)> = self.mappers.iter().collect();
+        if let Some(base) = &self.base {
+            for (name, mapper) in base.iter() {
+                if !self.mappers.contains_key(name) {
+                    mappers.push((name, mapper));
+                }
+            }
+        }
+        mappers.sort_by(|a, b| a.0.cmp(b.0));
+        mappers.into_iter()
+    }
+    /// Returns the number of mappers, including the base registry.
+    pub fn len(&self) -> usize {
+        self.iter().len()
+    }
+    /// Returns true, if neither the registry nor its base have mappers.
+    pub fn is_empty(&self) -> bool {
+        self.mappers.is_empty() && self.base.as_ref().is_none_or(|b| b.is_empty())
+    }
+    /// Validates all mappers. See [validate_cache()](../sql_mapper/fn.validate_cache.html).
+    pub fn validate(&self) -> Result<(), Vec<(String, SqlMapperError)>> {
+        validate_cache(self)
+    }
+}
@@ -1,9 +1,60 @@
 //! Generic merge function called by code from Toql derive.
 //! Used to merge a collection of structs into another collection of structs by equal keys
 
+use std::collections::BTreeMap;
+use std::collections::BTreeSet;
 use std::collections::HashMap;
+use std::collections::HashSet;
+use std::collections::LinkedList;
+use std::collections::VecDeque;
+
+/// A collection that merged structs are put into.
+///
+/// Maps take the key from a field of the merged struct, other collections get `()` as key.
+/// Implement it for own collection types to use them for merged fields.
+pub trait MergeTarget<K, O> {
+    /// Puts a merged struct into the collection.
+    fn merge_item(&mut self, key: K, item: O);
+}
+
+impl<O> MergeTarget<(), O> for Vec<O> {
+    fn merge_item(&mut self, _key: (), item: O) {
+        self.push(item);
+    }
+}
+impl<O> MergeTarget<(), O> for VecDeque<O> {
+    fn merge_item(&mut self, _key: (), item: O) {
+        self.push_back(item);
+    }
+}
+impl<O> MergeTarget<(), O> for LinkedList<O> {
+    fn merge_item(&mut self, _key: (), item: O) {
+        self.push_back(item);
+    }
+}
+impl<O: Eq + std::hash::Hash> MergeTarget<(), O> for HashSet<O> {
+    fn merge_item(&mut self, _key: (), item: O) {
+        self.insert(item);
+    }
+}
+impl<O: Ord> MergeTarget<(), O> for BTreeSet<O> {
+    fn merge_item(&mut self, _key: (), item: O) {
+        self.insert(item);
+    }
+}
+impl<K: Eq + std::hash::Hash, O> MergeTarget<K, O> for HashMap<K, O> {
+    fn merge_item(&mut self, key: K, item: O) {
+        self.insert(key, item);
+    }
+}
+impl<K: Ord, O> MergeTarget<K, O> for BTreeMap<K, O> {
+    fn merge_item(&mut self, key: K, item: O) {
+        self.insert(key, item);
+    }
+}
+
 pub fn merge<T, O, K, F, X, Y>(
-    this: &mut std::vec::Vec<T>,
+    this: &mut [T],
     mut other: Vec<O>,
     tkey: X,
     okey: Y,
@@ -30,25 +81,76 @@ pub fn merge<T, O, K, F, X, Y>(
 
     // Consume all authors and distribute
     for a in other.drain(..) {
-        // Get all books for author id
-        match &okey(&a) {
-            Some(ok) => {
-                let vbi = index.get(ok).unwrap();
-
-                // Clone author for second to last books
-                for bi in vbi.iter().skip(1) {
-                    if let Some(mut b) = this.get_mut(*bi) {
-                        assign(&mut b, a.clone());
-                    }
-                }
-
-                // Assign drained author for first book
-                let fbi = vbi.get(0).unwrap();
-                if let Some(mut b) = this.get_mut(*fbi) {
-                    assign(&mut b, a.clone());
-                }
+        // Get all books for author id, skip authors without books
+        let vbi = match okey(&a).and_then(|ok| index.get(&ok)) {
+            Some(vbi) => vbi,
+            None => continue,
+        };
+
+        // Clone author for second to last books
+        for bi in vbi.iter().skip(1) {
+            if let Some(b) = this.get_mut(*bi) {
+                assign(b, a.clone());
             }
-            None => {}
         }
+
+        // Assign drained author for first book
+        let fbi = vbi.first().unwrap();
+        if let Some(b) = this.get_mut(*fbi) {
+            assign(b, a);
+        }
+    }
+}
+
+/// Merges the nodes of a tree below their parents.
+///
+/// `this` are the top nodes, `nodes` all nodes below them in any order.
+/// A node is cloned for every parent with its key, so top nodes that are also below other top nodes get their children too.
+/// Nodes without parent among the top nodes or other nodes are dropped, and so are cycles.
+pub fn merge_tree<T, K, F, X, Y>(this: &mut [T], nodes: Vec<T>, key: X, parent: Y, assign: F)
+where
+    T: Clone,
+    K: Eq + std::hash::Hash,
+    F: Fn(&mut T, T),
+    X: Fn(&T) -> Option<K>,
+    Y: Fn(&T) -> Option<K>,
+{
+    // Build index to lookup all children by parent key
+    let mut index: HashMap<K, Vec<T>> = HashMap::new();
+    for n in nodes {
+        if let Some(p) = parent(&n) {
+            index.entry(p).or_default().push(n);
+        }
+    }
+
+    let mut ancestors = Vec::new();
+    for t in this.iter_mut() {
+        merge_children(t, &index, &mut ancestors, &key, &assign);
+    }
+}
+
+fn merge_children<T, K, F, X>(t: &mut T, index: &HashMap<K, Vec<T>>, ancestors: &mut Vec<K>, key: &X, assign: &F)
+where
+    T: Clone,
+    K: Eq + std::hash::Hash,
+    F: Fn(&mut T, T),
+    X: Fn(&T) -> Option<K>,
+{
+    let k = match key(t) {
+        Some(k) => k,
+        None => return,
+    };
+    if let Some(children) = index.get(&k) {
+        ancestors.push(k);
+        for c in children {
+            // Skip cycles, children must not be their own ancestors
+            if key(c).is_some_and(|ck| ancestors.contains(&ck)) {
+                continue;
+            }
+            let mut c = c.clone();
+            merge_children(&mut c, index, ancestors, key, assign);
+            assign(t, c);
+        }
+        ancestors.pop();
     }
 }
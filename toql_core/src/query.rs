@@ -19,6 +19,7 @@
 //! Read the guide for more information on the query syntax.
 //!
 use std::collections::BTreeSet;
+use std::collections::HashMap;
 use std::fmt;
 
 /// A trait to convert a simple datatype into a filter argument. Used by builder functions. Not very interesting ;)
@@ -164,6 +165,37 @@ impl Wildcard {
     }
 }
 
+/// A selection selects a named set of fields, that is predefined on the [SQL Mapper](../sql_mapper/struct.SqlMapper.html#method.selection).
+///
+/// Example
+/// ```ignore
+///
+///  let q = Query::new().and(Selection::from("summary"));
+///
+///  assert_eq!("$summary", q.to_string());
+/// ```
+#[derive(Clone, Debug)]
+pub struct Selection {
+    pub(crate) concatenation: Concatenation,
+    pub(crate) name: String,
+    pub(crate) position: Option<usize>, // Position in parsed query string
+}
+
+impl Selection {
+    /// Creates a new selection. A leading `$` is optional.
+    pub fn from<T>(name: T) -> Self
+    where
+        T: Into<String>,
+    {
+        let name = name.into();
+        Selection {
+            concatenation: Concatenation::And,
+            name: name.trim_start_matches('$').to_string(),
+            position: None,
+        }
+    }
+}
+
 /// A Toql field can select, filter and order a database column or expression
 /// A field can be created from a field name and filtered, sorted with its methods.
 /// However the Toql derive creates fields structs for a derived struct, so instead of
@@ -183,6 +215,7 @@ pub struct Field {
     pub(crate) order: Option<FieldOrder>,
     pub(crate) filter: Option<FieldFilter>,
     pub(crate) aggregation: bool,
+    pub(crate) position: Option<usize>, // Position in parsed query string
 }
 
 impl Field {
@@ -207,6 +240,7 @@ impl Field {
             order: None,
             filter: None,
             aggregation: false,
+            position: None,
         }
     }
     /// Hide field. Useful if a field should not be selected, but be used for filtering.
@@ -444,6 +478,28 @@ pub enum FieldFilter {
     //  Sc(String),
     Fn(String, Vec<String>), // Function name, args
 }
+
+impl FieldFilter {
+    /// Returns the name of the filter operation, e.g. `EQ`.
+    pub fn name(&self) -> &'static str {
+        match self {
+            FieldFilter::Eq(_) => "EQ",
+            FieldFilter::Eqn => "EQN",
+            FieldFilter::Ne(_) => "NE",
+            FieldFilter::Nen => "NEN",
+            FieldFilter::Gt(_) => "GT",
+            FieldFilter::Ge(_) => "GE",
+            FieldFilter::Lt(_) => "LT",
+            FieldFilter::Le(_) => "LE",
+            FieldFilter::Lk(_) => "LK",
+            FieldFilter::Bw(_, _) => "BW",
+            FieldFilter::In(_) => "IN",
+            FieldFilter::Out(_) => "OUT",
+            FieldFilter::Re(_) => "RE",
+            FieldFilter::Fn(_, _) => "FN",
+        }
+    }
+}
 #[derive(Clone, Debug)]
 pub(crate) enum FieldOrder {
     Asc(u8),
@@ -456,6 +512,7 @@ pub(crate) enum QueryToken {
     RightBracket,
     Wildcard(Wildcard),
     DoubleWildcard(Concatenation),
+    Selection(Selection),
     Field(Field),
 }
 
@@ -465,12 +522,25 @@ impl From<&str> for QueryToken {
             QueryToken::DoubleWildcard(Concatenation::And)
         } else if s.ends_with("*") {
             QueryToken::Wildcard(Wildcard::from(s))
+        } else if s.starts_with("$") {
+            QueryToken::Selection(Selection::from(s))
         } else {
             QueryToken::Field(Field::from(s))
         }
     }
 }
 
+impl QueryToken {
+    // Position in the parsed query string
+    pub(crate) fn position(&self) -> Option<usize> {
+        match self {
+            QueryToken::Field(field) => field.position,
+            QueryToken::Selection(selection) => selection.position,
+            _ => None,
+        }
+    }
+}
+
 impl ToString for QueryToken {
     fn to_string(&self) -> String {
         let s = match self {
@@ -484,6 +554,7 @@ impl ToString for QueryToken {
             ) => field.to_string(),
             QueryToken::Wildcard(wildcard) => format!("{}*", wildcard.path),
             QueryToken::DoubleWildcard(_) => String::from("**"),
+            QueryToken::Selection(selection) => format!("${}", selection.name),
         };
         s
     }
@@ -513,6 +584,9 @@ pub struct Query {
     /// Roles a query has to access fields.
     /// See [MapperOption](../sql_mapper/struct.MapperOptions.html#method.restrict_roles) for explanation.
     pub roles: BTreeSet<String>,
+    /// Context values, such as the tenant or user id.
+    /// [Row filters](../sql_mapper/trait.RowFilter.html) use them to restrict the rows a query can access.
+    pub context: HashMap<String, String>,
 }
 
 impl Query {
@@ -522,6 +596,7 @@ impl Query {
             tokens: vec![],
             distinct: false,
             roles: BTreeSet::new(),
+            context: HashMap::new(),
         }
     }
     /// Create a new query that select all top fields.
@@ -530,6 +605,7 @@ impl Query {
             tokens: vec![QueryToken::Wildcard(Wildcard::new())],
             distinct: false,
             roles: BTreeSet::new(),
+            context: HashMap::new(),
         }
     }
     /// Create a new query that select all top fields and all dependend fields. This is the best :)
@@ -538,6 +614,7 @@ impl Query {
             tokens: vec![QueryToken::DoubleWildcard(Concatenation::And)],
             distinct: false,
             roles: BTreeSet::new(),
+            context: HashMap::new(),
         }
     }
     /// Wrap query with parentheses.
@@ -573,11 +650,28 @@ impl Query {
             wildcard.concatenation = Concatenation::Or;
         } else if let QueryToken::DoubleWildcard(w) = query.tokens.get_mut(0).unwrap() {
             *w = Concatenation::Or;
+        } else if let QueryToken::Selection(selection) = query.tokens.get_mut(0).unwrap() {
+            selection.concatenation = Concatenation::Or;
         }
         
         self.tokens.append(&mut query.tokens);
         self
     }
+    /// Returns true, if the query may select or filter fields from a path, such as `user_orders`.
+    /// Double wildcards and selections may contain fields from any path.
+    pub fn contains_path(&self, path: &str) -> bool {
+        let prefix = if path.ends_with('_') {
+            path.to_owned()
+        } else {
+            format!("{}_", path)
+        };
+        self.tokens.iter().any(|t| match t {
+            QueryToken::DoubleWildcard(_) | QueryToken::Selection(_) => true,
+            QueryToken::Wildcard(wildcard) => wildcard.path.starts_with(&prefix),
+            QueryToken::Field(field) => field.name.starts_with(&prefix),
+            _ => false,
+        })
+    }
     // Not sure if needed
     /* pub fn prepend<T>(mut self, query: T) -> Self
     where
@@ -615,6 +709,9 @@ impl fmt::Display for Query {
                     QueryToken::Wildcard(wildcard) => {
                         s.push(get_concatenation(&wildcard.concatenation))
                     }
+                    QueryToken::Selection(selection) => {
+                        s.push(get_concatenation(&selection.concatenation))
+                    }
                     QueryToken::Field(field) => s.push(get_concatenation(&field.concatenation)),
                     _ => {}
                 }
@@ -625,6 +722,7 @@ impl fmt::Display for Query {
                 QueryToken::Field(..) => concatenation_needed = true,
                 QueryToken::Wildcard(..) => concatenation_needed = true,
                 QueryToken::DoubleWildcard(..) => concatenation_needed = true,
+                QueryToken::Selection(..) => concatenation_needed = true,
                 _ => {}
             }
         }
@@ -655,6 +753,14 @@ impl From<Wildcard> for Query {
     }
 }
 
+impl From<Selection> for Query {
+    fn from(selection: Selection) -> Query {
+        let mut q = Query::new();
+        q.tokens.push(QueryToken::Selection(selection));
+        q
+    }
+}
+
 impl From<&str> for Query {
     fn from(string: &str) -> Query {
         let mut q = Query::new();
@@ -663,6 +769,8 @@ impl From<&str> for Query {
             QueryToken::DoubleWildcard(Concatenation::And)
         } else if string.ends_with("*") {
             QueryToken::Wildcard(Wildcard::from(string))
+        } else if string.starts_with("$") {
+            QueryToken::Selection(Selection::from(string))
         } else {
             QueryToken::Field(Field::from(string))
         });
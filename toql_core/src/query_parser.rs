@@ -16,6 +16,7 @@
 use crate::query::Concatenation;
 use crate::query::Field;
 use crate::query::Wildcard;
+use crate::query::Selection;
 use crate::query::FieldFilter;
 use crate::query::FieldOrder;
 use crate::query::Query;
@@ -57,6 +58,7 @@ impl QueryParser {
                         order: None,
                         aggregation: false,
                         filter: None,
+                        position: Some(span.start()),
                     }));
                 }
                 Rule::sort => {
@@ -156,6 +158,14 @@ impl QueryParser {
                             path: String::from("")
                         }));
                 }
+                Rule::selection => {
+                    query.tokens.push(QueryToken::Selection(
+                        Selection {
+                            concatenation: con.clone(),
+                            name: span.as_str().trim_start_matches('$').to_string(),
+                            position: Some(span.start()),
+                        }));
+                }
                 Rule::rpar => {
                     query.tokens.push(QueryToken::RightBracket);
                 }
@@ -0,0 +1,238 @@
+//!
+//! The Query Plan Cache keeps the SQL of built queries, so that queries with the same shape are built only once.
+//!
+//! Two queries have the same shape, if they only differ in their filter values.
+//! For example `id eq 5, title lk 'a%'` and `id eq 7, title lk 'b%'` share a plan,
+//! while `id eq 5; title lk 'a%'` or `id in 5 7` get their own plan.
+//! Roles and the settings of the SQL Builder are part of the shape too.
+//!
+//! On a cache hit the SQL Builder only validates the filter values and fills in the parameters.
+//! Row filters are applied on every build, because they depend on the query context.
+//!
+//! ## Example
+//! ``` ignore
+//! let cache = Arc::new(QueryPlanCache::new(1000));
+//! let result = SqlBuilder::new().plan_cache(Arc::clone(&cache)).build(&mapper, &query)?;
+//! println!("{} hits", cache.metrics().hits);
+//! ```
+//!
+//! Plans belong to a version of a mapper, every change of the mapper starts a new version.
+//! Plans of old versions are never used again and make room for new ones, once they are least recently used.
+//! A cache can therefore be shared among builds with different mappers, for example from mapper overlays.
+//! Field handlers and filter functions must not put filter values into their SQL, use `?` instead.
+//!
+
+use crate::query::Concatenation;
+use crate::query::FieldFilter;
+use crate::query::FieldOrder;
+use crate::query::Query;
+use crate::query::QueryToken;
+use crate::sql_builder_result::SqlBuilderResult;
+use std::collections::HashMap;
+use std::sync::atomic::AtomicU64;
+use std::sync::atomic::Ordering;
+use std::sync::Arc;
+use std::sync::Mutex;
+
+/// A filtered query field, whose parameters must be filled in on a cache hit.
+#[derive(Debug, Clone)]
+pub(crate) struct ParamSlot {
+    pub(crate) token: usize,      // Index of the query token
+    pub(crate) toql_field: String, // Mapper field without subpath
+    pub(crate) having: bool,      // Parameters belong to the having clause
+}
+
+/// A built query without parameters and row filters.
+pub(crate) struct QueryPlan {
+    pub(crate) result: SqlBuilderResult,
+    pub(crate) slots: Vec<ParamSlot>,
+}
+
+struct CachedPlan {
+    plan: Arc<QueryPlan>,
+    last_used: u64,
+}
+
+#[derive(Default)]
+struct Plans {
+    plans: HashMap<String, CachedPlan>,
+    clock: u64,
+}
+
+/// Hit and miss counters of a [Query Plan Cache](struct.QueryPlanCache.html).
+#[derive(Debug, Clone, Copy, PartialEq, Default)]
+pub struct PlanCacheMetrics {
+    /// Builds that used a cached plan.
+    pub hits: u64,
+    /// Builds that had to build a new plan.
+    pub misses: u64,
+    /// Plans that were removed to make room for new ones.
+    pub evictions: u64,
+    /// Plans currently in the cache.
+    pub len: usize,
+}
+
+/// A cache for query plans with a fixed capacity. The least recently used plan is removed, if the cache is full.
+/// The cache is `Send` and `Sync` and can be shared among SQL Builders behind an `Arc`.
+pub struct QueryPlanCache {
+    capacity: usize,
+    plans: Mutex<Plans>,
+    hits: AtomicU64,
+    misses: AtomicU64,
+    evictions: AtomicU64,
+}
+
+impl QueryPlanCache {
+    /// Creates a cache that holds up to `capacity` plans. A capacity of 0 disables caching.
+    pub fn new(capacity: usize) -> Self {
+        QueryPlanCache {
+            capacity,
+            plans: Mutex::new(Plans::default()),
+            hits: AtomicU64::new(0),
+            misses: AtomicU64::new(0),
+            evictions: AtomicU64::new(0),
+        }
+    }
+    /// Returns the maximum number of plans.
+    pub fn capacity(&self) -> usize {
+        self.capacity
+    }
+    /// Returns the number of cached plans.
+    pub fn len(&self) -> usize {
+        self.lock().plans.len()
+    }
+    /// Returns true, if no plan is cached.
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+    /// Removes all plans. The counters are kept.
+    pub fn clear(&self) {
+        self.lock().plans.clear();
+    }
+    /// Returns the hit and miss counters.
+    pub fn metrics(&self) -> PlanCacheMetrics {
+        PlanCacheMetrics {
+            hits: self.hits.load(Ordering::Relaxed),
+            misses: self.misses.load(Ordering::Relaxed),
+            evictions: self.evictions.load(Ordering::Relaxed),
+            len: self.len(),
+        }
+    }
+
+    pub(crate) fn get(&self, key: &str) -> Option<Arc<QueryPlan>> {
+        let mut plans = self.lock();
+        plans.clock += 1;
+        let clock = plans.clock;
+        match plans.plans.get_mut(key) {
+            Some(cached) => {
+                cached.last_used = clock;
+                self.hits.fetch_add(1, Ordering::Relaxed);
+                Some(Arc::clone(&cached.plan))
+            }
+            None => {
+                self.misses.fetch_add(1, Ordering::Relaxed);
+                None
+            }
+        }
+    }
+
+    pub(crate) fn insert(&self, key: String, plan: QueryPlan) {
+        if self.capacity == 0 {
+            return;
+        }
+        let mut plans = self.lock();
+        if plans.plans.len() >= self.capacity && !plans.plans.contains_key(&key) {
+            let oldest = plans
+                .plans
+                .iter()
+                .min_by_key(|(_, c)| c.last_used)
+                .map(|(k, _)| k.to_owned());
+            if let Some(k) = oldest {
+                plans.plans.remove(&k);
+                self.evictions.fetch_add(1, Ordering::Relaxed);
+            }
+        }
+        plans.clock += 1;
+        let last_used = plans.clock;
+        plans.plans.insert(
+            key,
+            CachedPlan {
+                plan: Arc::new(plan),
+                last_used,
+            },
+        );
+    }
+
+    fn lock(&self) -> std::sync::MutexGuard<'_, Plans> {
+        // A panic while holding the lock cannot leave the plans inconsistent
+        self.plans.lock().unwrap_or_else(|e| e.into_inner())
+    }
+}
+
+/// Appends the shape of a query to a key: tokens with filter operations, but without filter values, roles and distinct.
+pub(crate) fn push_query_shape(key: &mut String, query: &Query) {
+    if query.distinct {
+        key.push_str("DISTINCT ");
+    }
+    for t in &query.tokens {
+        match t {
+            QueryToken::LeftBracket(c) => {
+                push_concatenation(key, c);
+                key.push('(');
+            }
+            QueryToken::RightBracket => key.push(')'),
+            QueryToken::Wildcard(wildcard) => {
+                push_concatenation(key, &wildcard.concatenation);
+                key.push_str(&wildcard.path);
+                key.push('*');
+            }
+            QueryToken::DoubleWildcard(c) => {
+                push_concatenation(key, c);
+                key.push_str("**");
+            }
+            QueryToken::Selection(selection) => {
+                push_concatenation(key, &selection.concatenation);
+                key.push('$');
+                key.push_str(&selection.name);
+            }
+            QueryToken::Field(field) => {
+                push_concatenation(key, &field.concatenation);
+                match field.order {
+                    Some(FieldOrder::Asc(n)) => key.push_str(&format!("+{}", n)),
+                    Some(FieldOrder::Desc(n)) => key.push_str(&format!("-{}", n)),
+                    None => {}
+                }
+                if field.hidden {
+                    key.push('.');
+                }
+                key.push_str(&field.name);
+                if field.aggregation {
+                    key.push('!');
+                }
+                if let Some(f) = &field.filter {
+                    key.push(' ');
+                    key.push_str(f.name());
+                    // Argument lists change the number of placeholders
+                    match f {
+                        FieldFilter::In(args) | FieldFilter::Out(args) => {
+                            key.push_str(&format!("/{}", args.len()))
+                        }
+                        FieldFilter::Fn(name, args) => {
+                            key.push_str(&format!(" {}/{}", name.to_uppercase(), args.len()))
+                        }
+                        _ => {}
+                    }
+                }
+            }
+        }
+    }
+    key.push_str(" ROLES ");
+    key.push_str(&query.roles.iter().cloned().collect::<Vec<String>>().join(","));
+}
+
+fn push_concatenation(key: &mut String, concatenation: &Concatenation) {
+    key.push(match concatenation {
+        Concatenation::And => ',',
+        Concatenation::Or => ';',
+    });
+}
@@ -0,0 +1,203 @@
+//!
+//! A role expression restricts access to fields, joins and merges.
+//!
+//! The syntax follows the Toql query language:
+//!  - `,` combines roles with AND
+//!  - `;` combines roles with OR. AND binds stronger than OR.
+//!  - `!` negates a role or a parenthesized expression.
+//!
+//! ## Example
+//! ``` ignore
+//! let expr = RoleExpr::parse("admin;owner,!guest").unwrap();  // admin OR (owner AND NOT guest)
+//! let roles = ["owner"].iter().map(|r| r.to_string()).collect();
+//! assert!(expr.is_valid(&roles));
+//! ```
+//!
+//! Use role expressions with [restrict_role_expr()](../sql_mapper/struct.MapperOptions.html#method.restrict_role_expr).
+//!
+use std::collections::BTreeSet;
+use std::fmt;
+
+/// A boolean expression over roles.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum RoleExpr {
+    /// The query must have this role.
+    Role(String),
+    /// All expressions must be valid.
+    All(Vec<RoleExpr>),
+    /// Any expression must be valid.
+    Any(Vec<RoleExpr>),
+    /// The expression must not be valid.
+    Not(Box<RoleExpr>),
+}
+
+/// Represents all errors from parsing a role expression.
+#[derive(Debug, PartialEq)]
+pub enum RoleExprError {
+    /// Unexpected character. Contains position and character.
+    UnexpectedChar(usize, char),
+    /// The expression ended unexpectedly, e.g. after `!` or with an open parenthesis.
+    UnexpectedEnd,
+}
+
+impl fmt::Display for RoleExprError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            RoleExprError::UnexpectedChar(ref p, ref c) =>
+                write!(f, "unexpected character `{}` at position {}", c, p),
+            RoleExprError::UnexpectedEnd =>
+                write!(f, "unexpected end of role expression"),
+        }
+    }
+}
+
+impl RoleExpr {
+    /// Creates an expression for a single role.
+    pub fn role<T: Into<String>>(role: T) -> Self {
+        RoleExpr::Role(role.into())
+    }
+    /// Creates an expression that requires all roles.
+    pub fn all_of<I: IntoIterator<Item = String>>(roles: I) -> Self {
+        RoleExpr::All(roles.into_iter().map(RoleExpr::Role).collect())
+    }
+    /// Creates an expression that requires any of the roles.
+    pub fn any_of<I: IntoIterator<Item = String>>(roles: I) -> Self {
+        RoleExpr::Any(roles.into_iter().map(RoleExpr::Role).collect())
+    }
+
+    /// Returns true, if the roles satisfy the expression.
+    pub fn is_valid(&self, roles: &BTreeSet<String>) -> bool {
+        match self {
+            RoleExpr::Role(r) => roles.contains(r),
+            RoleExpr::All(exprs) => exprs.iter().all(|e| e.is_valid(roles)),
+            RoleExpr::Any(exprs) => exprs.iter().any(|e| e.is_valid(roles)),
+            RoleExpr::Not(expr) => !expr.is_valid(roles),
+        }
+    }
+
+    /// Returns all roles that appear in the expression.
+    pub fn roles(&self) -> BTreeSet<String> {
+        let mut roles = BTreeSet::new();
+        self.collect_roles(&mut roles);
+        roles
+    }
+
+    fn collect_roles(&self, roles: &mut BTreeSet<String>) {
+        match self {
+            RoleExpr::Role(r) => {
+                roles.insert(r.to_owned());
+            }
+            RoleExpr::All(exprs) | RoleExpr::Any(exprs) => {
+                for e in exprs {
+                    e.collect_roles(roles);
+                }
+            }
+            RoleExpr::Not(expr) => expr.collect_roles(roles),
+        }
+    }
+
+    /// Parses a role expression, e.g. `admin;owner,!guest`.
+    /// Role names may contain alphanumeric characters and underscores. Whitespace is ignored.
+    pub fn parse(expr: &str) -> Result<RoleExpr, RoleExprError> {
+        let chars: Vec<(usize, char)> = expr.char_indices().filter(|(_, c)| !c.is_whitespace()).collect();
+        let mut pos = 0;
+        let e = Self::parse_any(&chars, &mut pos)?;
+        match chars.get(pos) {
+            Some((p, c)) => Err(RoleExprError::UnexpectedChar(*p, *c)),
+            None => Ok(e),
+        }
+    }
+
+    // any = all (';' all)*
+    fn parse_any(chars: &[(usize, char)], pos: &mut usize) -> Result<RoleExpr, RoleExprError> {
+        let mut exprs = vec![Self::parse_all(chars, pos)?];
+        while let Some((_, ';')) = chars.get(*pos) {
+            *pos += 1;
+            exprs.push(Self::parse_all(chars, pos)?);
+        }
+        Ok(if exprs.len() == 1 { exprs.pop().unwrap() } else { RoleExpr::Any(exprs) })
+    }
+
+    // all = unary (',' unary)*
+    fn parse_all(chars: &[(usize, char)], pos: &mut usize) -> Result<RoleExpr, RoleExprError> {
+        let mut exprs = vec![Self::parse_unary(chars, pos)?];
+        while let Some((_, ',')) = chars.get(*pos) {
+            *pos += 1;
+            exprs.push(Self::parse_unary(chars, pos)?);
+        }
+        Ok(if exprs.len() == 1 { exprs.pop().unwrap() } else { RoleExpr::All(exprs) })
+    }
+
+    // unary = '!' unary | '(' any ')' | role
+    fn parse_unary(chars: &[(usize, char)], pos: &mut usize) -> Result<RoleExpr, RoleExprError> {
+        match chars.get(*pos) {
+            Some((_, '!')) => {
+                *pos += 1;
+                Ok(!Self::parse_unary(chars, pos)?)
+            }
+            Some((_, '(')) => {
+                *pos += 1;
+                let e = Self::parse_any(chars, pos)?;
+                match chars.get(*pos) {
+                    Some((_, ')')) => {
+                        *pos += 1;
+                        Ok(e)
+                    }
+                    Some((p, c)) => Err(RoleExprError::UnexpectedChar(*p, *c)),
+                    None => Err(RoleExprError::UnexpectedEnd),
+                }
+            }
+            Some((p, c)) => {
+                let mut role = String::new();
+                while let Some((_, c)) = chars.get(*pos) {
+                    if c.is_alphanumeric() || *c == '_' {
+                        role.push(*c);
+                        *pos += 1;
+                    } else {
+                        break;
+                    }
+                }
+                if role.is_empty() {
+                    Err(RoleExprError::UnexpectedChar(*p, *c))
+                } else {
+                    Ok(RoleExpr::Role(role))
+                }
+            }
+            None => Err(RoleExprError::UnexpectedEnd),
+        }
+    }
+}
+
+/// Negates an expression: `!RoleExpr::role("guest")`.
+impl std::ops::Not for RoleExpr {
+    type Output = RoleExpr;
+    fn not(self) -> RoleExpr {
+        RoleExpr::Not(Box::new(self))
+    }
+}
+
+impl fmt::Display for RoleExpr {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            RoleExpr::Role(r) => write!(f, "{}", r),
+            RoleExpr::All(exprs) => {
+                let s: Vec<String> = exprs
+                    .iter()
+                    .map(|e| match e {
+                        RoleExpr::Any(_) => format!("({})", e),
+                        _ => e.to_string(),
+                    })
+                    .collect();
+                write!(f, "{}", s.join(","))
+            }
+            RoleExpr::Any(exprs) => {
+                let s: Vec<String> = exprs.iter().map(|e| e.to_string()).collect();
+                write!(f, "{}", s.join(";"))
+            }
+            RoleExpr::Not(expr) => match **expr {
+                RoleExpr::Role(_) | RoleExpr::Not(_) => write!(f, "!{}", expr),
+                _ => write!(f, "!({})", expr),
+            },
+        }
+    }
+}
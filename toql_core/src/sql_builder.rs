@@ -35,16 +35,31 @@
 //!  - The second pass will only build the query for the path *phones_* with the help of the phone mapper. 
 //! 
 use crate::query::Concatenation;
+use crate::query::FieldFilter;
 use crate::query::FieldOrder;
 use crate::query::Query;
 use crate::query::QueryToken;
+use crate::query_plan_cache::push_query_shape;
+use crate::query_plan_cache::ParamSlot;
+use crate::query_plan_cache::QueryPlan;
+use crate::query_plan_cache::QueryPlanCache;
+use crate::role_expr::RoleExpr;
+use crate::sql_builder_result::ColumnDescriptor;
 use crate::sql_builder_result::SqlBuilderResult;
+use crate::sql_mapper::sql_param;
+use crate::sql_mapper::FilterType;
 use crate::sql_mapper::Join;
+use crate::sql_mapper::MapperOptions;
 use crate::sql_mapper::SqlMapper;
 use crate::sql_mapper::SqlTarget;
+use crate::sql_mapper::ValueType;
 use std::collections::BTreeSet;
 use std::collections::HashMap;
 use std::fmt;
+use std::sync::Arc;
+
+#[cfg(feature = "json")]
+use serde::Serialize;
 
 struct SqlTargetData {
     selected: bool, // Target is selected
@@ -74,18 +89,85 @@ pub struct SqlBuilder {
     subpath: String,         // Build only subpath
     joins: BTreeSet<String>, // Use this joins
     ignored_paths: Vec<String>, // Ignore paths, no errors are raised for them
+    omit_unselected: bool,   // Skip columns of unselected fields instead of selecting null
+    collect_errors: bool,    // Collect all token errors instead of failing on the first
+    field_policy: FieldPolicy, // Handling of unknown and unauthorized fields
+    filter_only: bool,       // Build only the filter for bulk deletes and updates
+    allow_unfiltered: bool,  // Allow bulk deletes and updates without query filter
+    plan_cache: Option<Arc<QueryPlanCache>>, // Reuse SQL of queries with the same shape
                              // alias: String,           // Alias all fields with this
 }
 
-#[derive(Debug)]
+/// Decides how the SQL Builder treats query fields that are unknown or require a role the query does not have.
+/// This also applies to missing selections.
+/// Lenient policies drop such fields together with their filters, so the query may return more rows.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum FieldPolicy {
+    /// Fail with an error. This is the default.
+    Strict,
+    /// Drop the fields silently.
+    Lenient,
+    /// Drop the fields and report them as [warnings](../sql_builder_result/struct.SqlBuilderResult.html#method.warnings).
+    LenientWithWarnings,
+}
+
+#[derive(Debug, Clone)]
+#[cfg_attr(feature = "json", derive(Serialize))]
 /// Represents all errors from the SQL Builder
 pub enum SqlBuilderError {
     /// The field is not mapped to a column or SQL expression. Contains the field name.
     FieldMissing(String),
     /// The field requires a role that the query does not have. Contains the role.
     RoleRequired(String),
-    /// The filter expects other arguments. Typically raised by custom functions (FN) if the number of arguments is wrong.
-    FilterInvalid(String)
+    /// The selection is not defined on the mapper. Contains the selection name.
+    SelectionMissing(String),
+    /// The field cannot be ordered. Contains the field name.
+    OrderInvalid(String),
+    /// A row filter needs a context value that the query does not have. Contains the context key.
+    ContextMissing(String),
+    /// The filter expects other arguments. Typically raised by custom functions (FN) if the number of arguments is wrong
+    /// or if an argument does not match the [value type](../sql_mapper/enum.ValueType.html) of the field.
+    FilterInvalid(String),
+    /// The query has no filter, but the statement would change rows. Allow it with [allow_unfiltered()](struct.SqlBuilder.html#method.allow_unfiltered).
+    FilterMissing,
+    /// The field cannot be updated, because it belongs to a join or is an aggregate. Contains the field name, empty if no field is changed.
+    UpdateInvalid(String),
+    /// The query has errors in several tokens. Only returned, if the SQL Builder collects errors.
+    Multiple(Vec<TokenError>),
+}
+
+/// An error of a query token together with the token position.
+#[derive(Debug, Clone)]
+#[cfg_attr(feature = "json", derive(Serialize))]
+pub struct TokenError {
+    /// Byte position of the token in the query string. None for queries that were not parsed.
+    pub position: Option<usize>,
+    /// Field or selection name of the token, e.g. `username` or `$std`.
+    pub token: String,
+    /// The error.
+    pub error: SqlBuilderError,
+}
+
+impl TokenError {
+    fn new(token: &QueryToken, error: SqlBuilderError) -> Self {
+        TokenError {
+            position: token.position(),
+            token: match token {
+                QueryToken::Field(field) => field.name.to_owned(),
+                _ => token.to_string(),
+            },
+            error,
+        }
+    }
+}
+
+impl fmt::Display for TokenError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self.position {
+            Some(p) => write!(f, "{} (`{}` at position {})", self.error, self.token, p),
+            None => write!(f, "{} (`{}`)", self.error, self.token),
+        }
+    }
 }
 
 impl fmt::Display for SqlBuilderError {
@@ -95,8 +177,22 @@ impl fmt::Display for SqlBuilderError {
                 write!(f, "field `{}` is missing", s),
             SqlBuilderError::RoleRequired(ref s) =>
                 write!(f, "role `{}` is required", s),
+            SqlBuilderError::SelectionMissing(ref s) =>
+                write!(f, "selection `{}` is missing", s),
+            SqlBuilderError::OrderInvalid(ref s) =>
+                write!(f, "field `{}` cannot be ordered", s),
+            SqlBuilderError::ContextMissing(ref s) =>
+                write!(f, "context value `{}` is missing", s),
             SqlBuilderError::FilterInvalid(ref s) =>
                 write!(f, "filter `{}` is invalid ", s),
+            SqlBuilderError::FilterMissing =>
+                write!(f, "filter is missing"),
+            SqlBuilderError::UpdateInvalid(ref s) =>
+                write!(f, "field `{}` cannot be updated", s),
+            SqlBuilderError::Multiple(ref errors) => {
+                let messages: Vec<String> = errors.iter().map(|e| e.to_string()).collect();
+                write!(f, "{}", messages.join("; "))
+            }
         }
     }
 }
@@ -110,6 +206,12 @@ impl SqlBuilder {
             subpath: "".to_string(),
             joins: BTreeSet::new(),
             ignored_paths: Vec::new(),
+            omit_unselected: false,
+            collect_errors: false,
+            field_policy: FieldPolicy::Strict,
+            filter_only: false,
+            allow_unfiltered: false,
+            plan_cache: None,
         }
     }
     /// Add path to list of ignore paths.
@@ -117,6 +219,36 @@ impl SqlBuilder {
         self.ignored_paths.push(path.into());
         self
     }
+    /// Omit columns of unselected fields in the select clause.
+    /// By default they are selected as `null`, so that every field keeps its column position.
+    /// Use [columns()](../sql_builder_result/struct.SqlBuilderResult.html#method.columns) to find the field of a column.
+    /// Structs from the Toql derive need the default to be loaded.
+    pub fn omit_unselected(mut self, omit_unselected: bool) -> Self {
+        self.omit_unselected = omit_unselected;
+        self
+    }
+    /// Collect the errors of all query tokens instead of failing on the first.
+    /// The build functions then return all errors together as [Multiple](enum.SqlBuilderError.html#variant.Multiple).
+    pub fn collect_errors(mut self, collect_errors: bool) -> Self {
+        self.collect_errors = collect_errors;
+        self
+    }
+    /// Set the policy for unknown fields and fields that require a role, see [FieldPolicy](enum.FieldPolicy.html).
+    pub fn field_policy(mut self, field_policy: FieldPolicy) -> Self {
+        self.field_policy = field_policy;
+        self
+    }
+    /// Allow bulk deletes and updates for queries without filter. Such statements affect all rows, that pass the row filters.
+    pub fn allow_unfiltered(mut self, allow_unfiltered: bool) -> Self {
+        self.allow_unfiltered = allow_unfiltered;
+        self
+    }
+    /// Use a [Query Plan Cache](../query_plan_cache/struct.QueryPlanCache.html) to build queries.
+    /// Queries that differ only in their filter values are then built only once.
+    pub fn plan_cache(mut self, plan_cache: Arc<QueryPlanCache>) -> Self {
+        self.plan_cache = Some(plan_cache);
+        self
+    }
     /* pub fn for_role<T: Into<String>>(mut self, role: T) -> Self {
         self.roles.insert(role.into());
         self
@@ -137,6 +269,115 @@ impl SqlBuilder {
         self.build(sql_mapper, query)
     }
 
+    /// Build DELETE statement for all rows that match the query filter.
+    /// Only filtered fields are considered, their roles and the row filters of the mapper apply.
+    /// Unknown fields and fields that require a role always fail, regardless of the [field policy](struct.SqlBuilder.html#method.field_policy).
+    /// Returns the statement with its parameters.
+    /// Fails with [FilterMissing](enum.SqlBuilderError.html#variant.FilterMissing), if the query has no filter,
+    /// unless unfiltered statements are [allowed](struct.SqlBuilder.html#method.allow_unfiltered).
+    pub fn build_delete(
+        &mut self,
+        sql_mapper: &SqlMapper,
+        query: &Query,
+    ) -> Result<(String, Vec<String>), SqlBuilderError> {
+        let result = self.build_bulk_filter(sql_mapper, query)?;
+        let mut sql = format!("DELETE {} FROM {}", sql_mapper.table_alias(), result.table);
+        Self::push_bulk_filter(&mut sql, &result);
+        Ok((sql, result.params().to_owned()))
+    }
+
+    /// Build UPDATE statement that changes fields for all rows that match the query filter. See [build_delete()](struct.SqlBuilder.html#method.build_delete).
+    /// Changes contain fields and their new values. The fields must be top fields that are mapped to a column,
+    /// other fields fail with [UpdateInvalid](enum.SqlBuilderError.html#variant.UpdateInvalid).
+    /// Values are SQL parameters and must match the [value type](../sql_mapper/enum.ValueType.html) of their field.
+    pub fn build_update(
+        &mut self,
+        sql_mapper: &SqlMapper,
+        query: &Query,
+        changes: &[(&str, &str)],
+    ) -> Result<(String, Vec<String>), SqlBuilderError> {
+        let mut assignments: Vec<String> = Vec::with_capacity(changes.len());
+        let mut params: Vec<String> = Vec::with_capacity(changes.len());
+        for (toql_field, value) in changes {
+            let sql_target = sql_mapper
+                .fields
+                .get(*toql_field)
+                .ok_or_else(|| SqlBuilderError::FieldMissing(toql_field.to_string()))?;
+            if !Self::validate_roles(&query.roles, &sql_target.options.role_expr) {
+                return Err(SqlBuilderError::RoleRequired(format!(
+                    "Field requires a user role: '{}'. ",
+                    toql_field
+                )));
+            }
+            if sql_target.subfields || sql_target.options.aggregate || !sql_target.column {
+                return Err(SqlBuilderError::UpdateInvalid(toql_field.to_string()));
+            }
+            let value = match sql_target.options.value_type {
+                ValueType::Any | ValueType::Text => value.to_string(),
+                value_type => match value_type.coerce_filter(toql_field, &FieldFilter::Eq(value.to_string()))? {
+                    FieldFilter::Eq(v) => v,
+                    _ => unreachable!(),
+                },
+            };
+            assignments.push(format!("{} = ?", sql_target.expression));
+            params.push(value);
+        }
+        if assignments.is_empty() {
+            return Err(SqlBuilderError::UpdateInvalid(String::from("")));
+        }
+
+        let result = self.build_bulk_filter(sql_mapper, query)?;
+        let mut sql = format!("UPDATE {}", result.table);
+        if !result.join_clause.is_empty() {
+            sql.push(' ');
+            sql.push_str(&result.join_clause);
+        }
+        sql.push_str(" SET ");
+        sql.push_str(&assignments.join(", "));
+        if !result.where_clause.is_empty() {
+            sql.push_str(" WHERE ");
+            sql.push_str(&result.where_clause);
+        }
+        params.extend_from_slice(result.params());
+        Ok((sql, params))
+    }
+
+    fn build_bulk_filter(
+        &mut self,
+        sql_mapper: &SqlMapper,
+        query: &Query,
+    ) -> Result<SqlBuilderResult, SqlBuilderError> {
+        // Dropped filter tokens would widen the statement, so every token error fails
+        let field_policy = std::mem::replace(&mut self.field_policy, FieldPolicy::Strict);
+        let collect_errors = std::mem::replace(&mut self.collect_errors, false);
+        self.filter_only = true;
+        let result = self.build(sql_mapper, query);
+        self.field_policy = field_policy;
+        self.collect_errors = collect_errors;
+        self.filter_only = false;
+        let result = result?;
+        if !result.having_clause.is_empty() {
+            return Err(SqlBuilderError::FilterInvalid(String::from(
+                "aggregate filters cannot be used to delete or update rows",
+            )));
+        }
+        if !result.any_filtered && !self.allow_unfiltered {
+            return Err(SqlBuilderError::FilterMissing);
+        }
+        Ok(result)
+    }
+
+    fn push_bulk_filter(sql: &mut String, result: &SqlBuilderResult) {
+        if !result.join_clause.is_empty() {
+            sql.push(' ');
+            sql.push_str(&result.join_clause);
+        }
+        if !result.where_clause.is_empty() {
+            sql.push_str(" WHERE ");
+            sql.push_str(&result.where_clause);
+        }
+    }
+
     // Build normal query for this path
     pub fn build_path<T: Into<String>>(
         &mut self,
@@ -155,20 +396,22 @@ impl SqlBuilder {
         self.build(sql_mapper, query)
     }
 
-    fn validate_roles(proposed: &BTreeSet<String>, required: &BTreeSet<String>) -> bool {
-        if required.is_empty() {
-            return true;
-        } // Is valid, if no roles are required
-        if proposed.is_empty() {
-            return false;
-        } // Is invalid, if roles are required, but no roles proposed
+    fn validate_roles(proposed: &BTreeSet<String>, required: &Option<RoleExpr>) -> bool {
+        // Is valid, if no roles are required
+        required.as_ref().is_none_or(|e| e.is_valid(proposed))
+    }
 
-        for r in required {
-            if !proposed.contains(r) {
-                return false;
-            }
-        }
-        true
+    // Returns the first path of a field, whose join requires roles that are not proposed
+    fn denied_path<'a>(
+        proposed: &BTreeSet<String>,
+        sql_joins: &HashMap<String, Join>,
+        toql_field: &'a str,
+    ) -> Option<&'a str> {
+        toql_field.split('_').rev().skip(1).find(|path| {
+            sql_joins
+                .get(*path)
+                .is_some_and(|j| !Self::validate_roles(proposed, &j.role_expr))
+        })
     }
 
     fn build_ordering(
@@ -206,22 +449,34 @@ impl SqlBuilder {
         result: &mut SqlBuilderResult,
         sql_targets: &HashMap<String, SqlTarget>,
         field_order: &Vec<String>,
+        aggregate_filtered: bool,
     ) {
         let mut any_selected = false;
+        let mut aggregate_selected = false;
+        let mut group_by: Vec<String> = Vec::new();
         for toql_field in field_order {
             if let Some(sql_target) = sql_targets.get(toql_field) {
                 // For selected fields there exists target data
                 if sql_target.options.count_select {
                     if let Some(sql_field) = sql_target.handler.build_select(&sql_target.expression)
                     {
+                        if sql_target.options.aggregate {
+                            aggregate_selected = true;
+                        } else {
+                            group_by.push(sql_field.clone());
+                        }
                         result.select_clause.push_str(&sql_field);
                         result.select_clause.push_str(", ");
+                        result.columns.push(ColumnDescriptor::new(toql_field, true));
                         any_selected = true;
                     }
                 }
             }
         }
         result.any_selected = any_selected;
+        if aggregate_selected || aggregate_filtered {
+            result.group_by_clause = group_by.join(", ");
+        }
         if any_selected {
             // Remove last ,
             result.select_clause = result.select_clause.trim_end_matches(", ").to_string();
@@ -235,34 +490,60 @@ impl SqlBuilder {
         sql_targets: &HashMap<String, SqlTarget>,
         sql_target_data: &HashMap<&str, SqlTargetData>,
         field_order: &Vec<String>,
+        denied_fields: &BTreeSet<&str>,
+        aggregate_filtered: bool,
+        omit_unselected: bool,
     ) {
         // Build select clause
         let mut any_selected = false;
+        let mut aggregate_selected = false;
+        let mut group_by: Vec<String> = Vec::new();
         for toql_field in field_order {
+            let mut sql_field = None;
             if let Some(sql_target) = sql_targets.get(toql_field) {
                 // For selected fields there exists target data
-                let selected = sql_target.options.always_selected
+                let selected = (sql_target.options.always_selected
+                    && !denied_fields.contains(toql_field.as_str()))
                     || sql_target_data
                         .get(toql_field.as_str())
                         .map_or(false, |d| d.selected);
 
                 if selected {
-                    if let Some(sql_field) = sql_target.handler.build_select(&sql_target.expression)
-                    {
-                        result.select_clause.push_str(&sql_field);
-                        any_selected = true;
-                    } else {
-                        result.select_clause.push_str("null");
+                    sql_field = sql_target.handler.build_select(&sql_target.expression);
+                    if let Some(s) = &sql_field {
+                        // Group by plain columns, if aggregates are used
+                        if sql_target.options.aggregate {
+                            aggregate_selected = true;
+                        } else {
+                            group_by.push(s.clone());
+                        }
                     }
-                } else {
+                }
+            }
+            // Unselected and unmapped fields keep their column position, unless they are omitted
+            match sql_field {
+                Some(s) => {
+                    result.select_clause.push_str(&s);
+                    result.columns.push(ColumnDescriptor::new(toql_field, true));
+                    any_selected = true;
+                }
+                None if omit_unselected => continue,
+                None => {
                     result.select_clause.push_str("null");
+                    result.columns.push(ColumnDescriptor::new(toql_field, false));
                 }
-                result.select_clause.push_str(", ");
             }
+            result.select_clause.push_str(", ");
         }
         result.any_selected = any_selected;
+        if aggregate_selected || aggregate_filtered {
+            result.group_by_clause = group_by.join(", ");
+        }
         // Remove last ,
         result.select_clause = result.select_clause.trim_end_matches(", ").to_string();
+        if result.select_clause.is_empty() {
+            result.select_clause = "1".to_string();
+        }
     }
     fn build_join_clause(
         sql_join_data: &mut HashMap<&str, SqlJoinData>,
@@ -301,6 +582,104 @@ impl SqlBuilder {
         sql_mapper: &SqlMapper,
         query: &Query,
     ) -> Result<SqlBuilderResult, SqlBuilderError> {
+        let plan_cache = match &self.plan_cache {
+            Some(plan_cache) => Arc::clone(plan_cache),
+            None => {
+                let plan = self.build_plan(sql_mapper, query)?;
+                return Self::finish(plan.result, sql_mapper, query);
+            }
+        };
+
+        let key = self.plan_key(sql_mapper, query);
+        let result = match plan_cache.get(&key) {
+            Some(plan) => self.fill_params(&plan, sql_mapper, query)?,
+            None => {
+                let plan = self.build_plan(sql_mapper, query)?;
+                let result = plan.result.clone();
+                plan_cache.insert(key, plan);
+                result
+            }
+        };
+        Self::finish(result, sql_mapper, query)
+    }
+
+    // Key of the query plan, contains the mapper version, the query shape and the builder settings that change the SQL
+    fn plan_key(&self, sql_mapper: &SqlMapper, query: &Query) -> String {
+        let mut key = format!(
+            "{}|{}|{}|{}|{}|{:?}|{}|",
+            sql_mapper.version,
+            self.count_query,
+            self.filter_only,
+            self.subpath,
+            self.ignored_paths.join(","),
+            self.field_policy,
+            self.omit_unselected
+        );
+        push_query_shape(&mut key, query);
+        key
+    }
+
+    // Validates the filter values of a query and fills them into a copy of the cached plan
+    fn fill_params(
+        &self,
+        plan: &QueryPlan,
+        sql_mapper: &SqlMapper,
+        query: &Query,
+    ) -> Result<SqlBuilderResult, SqlBuilderError> {
+        let mut result = plan.result.clone();
+        result.where_params.clear();
+        result.having_params.clear();
+        let mut errors: Vec<TokenError> = Vec::new();
+
+        for slot in &plan.slots {
+            let t = &query.tokens[slot.token];
+            let filter = match t {
+                QueryToken::Field(query_field) => query_field.filter.as_ref(),
+                _ => None,
+            };
+            let sql_target = sql_mapper.fields.get(&slot.toql_field);
+            if let (Some(f), Some(sql_target)) = (filter, sql_target) {
+                match Self::build_field_filter(sql_mapper, &slot.toql_field, sql_target, f) {
+                    Ok((_, mut p)) if slot.having => result.having_params.append(&mut p),
+                    Ok((_, mut p)) => result.where_params.append(&mut p),
+                    Err(e) => self.token_error(&mut result, &mut errors, t, e)?,
+                }
+            }
+        }
+        if !errors.is_empty() {
+            return Err(SqlBuilderError::Multiple(errors));
+        }
+        Ok(result)
+    }
+
+    // Applies row filters and combines params
+    fn finish(
+        mut result: SqlBuilderResult,
+        sql_mapper: &SqlMapper,
+        query: &Query,
+    ) -> Result<SqlBuilderResult, SqlBuilderError> {
+        // Apply row filters, queries cannot skip them
+        Self::build_row_filters(&mut result, sql_mapper, query)?;
+
+        // Create combined params if needed
+        if !result.having_params.is_empty() && !result.where_params.is_empty() {
+            result
+                .combined_params
+                .extend_from_slice(&result.where_params);
+            result
+                .combined_params
+                .extend_from_slice(&result.having_params);
+        }
+
+        Ok(result)
+    }
+
+    // Builds the query without row filters
+    fn build_plan(
+        &mut self,
+        sql_mapper: &SqlMapper,
+        query: &Query,
+    ) -> Result<QueryPlan, SqlBuilderError> {
         let mut ordinals: BTreeSet<u8> = BTreeSet::new();
         let mut ordering: HashMap<u8, Vec<(FieldOrder, String)>> = HashMap::new();
 
@@ -310,6 +689,9 @@ impl SqlBuilder {
         let mut pending_having_parens_concatenation: Option<Concatenation> = None;
         let mut pending_where_parens: u8 = 0;
         let mut pending_having_parens: u8 = 0;
+        let mut aggregate_filtered = false; // Aggregate field is filtered
+        let mut errors: Vec<TokenError> = Vec::new();
+        let mut slots: Vec<ParamSlot> = Vec::new();
 
         let mut sql_target_data: HashMap<&str, SqlTargetData> = HashMap::new();
         let mut sql_join_data: HashMap<&str, SqlJoinData> = HashMap::new();
@@ -317,18 +699,22 @@ impl SqlBuilder {
         let mut result = SqlBuilderResult {
             table: sql_mapper.table.clone(),
             any_selected: false,
+            any_filtered: false,
             distinct: query.distinct,
             join_clause: String::from(""),
             select_clause: String::from(""),
+            columns: Vec::new(),
+            warnings: Vec::new(),
             where_clause: String::from(""),
             order_by_clause: String::from(""),
+            group_by_clause: String::from(""),
             having_clause: String::from(""),
             where_params: vec![],
             having_params: vec![],
             combined_params: vec![],
         };
 
-        for t in &query.tokens {
+        for (token_index, t) in query.tokens.iter().enumerate() {
             {
                 match t {
                     QueryToken::LeftBracket(ref concatenation) => {
@@ -353,7 +739,7 @@ impl SqlBuilder {
                     }
                     QueryToken::DoubleWildcard(..) => {
                          // Skip wildcard for count queries
-                        if self.count_query {
+                        if self.count_query || self.filter_only {
                             continue;
                         }
                         for (field_name, sql_target) in &sql_mapper.fields {
@@ -367,10 +753,15 @@ impl SqlBuilder {
                           
                             // Skip fields with missing role
                             let role_valid =
-                                Self::validate_roles(&query.roles, &sql_target.options.roles);
+                                Self::validate_roles(&query.roles, &sql_target.options.role_expr);
                             if role_valid == false {
                                 continue;
                             }
+                            if sql_target.subfields
+                                && Self::denied_path(&query.roles, &sql_mapper.joins, field_name).is_some()
+                            {
+                                continue;
+                            }
                             let f = sql_target_data.entry(field_name.as_str()).or_default();
                             f.selected = true; // Select field
                             // Add JOIN information for subfields
@@ -386,7 +777,7 @@ impl SqlBuilder {
 
                     QueryToken::Wildcard(wildcard) => {
                         // Skip wildcard for count queries
-                        if self.count_query {
+                        if self.count_query || self.filter_only {
                             continue;
                         }
                         // Skip field from other path
@@ -405,10 +796,15 @@ impl SqlBuilder {
                             }
                             // Skip fields with missing role
                             let role_valid =
-                                Self::validate_roles(&query.roles, &sql_target.options.roles);
+                                Self::validate_roles(&query.roles, &sql_target.options.role_expr);
                             if role_valid == false {
                                 continue;
                             }
+                            if sql_target.subfields
+                                && Self::denied_path(&query.roles, &sql_mapper.joins, field_name).is_some()
+                            {
+                                continue;
+                            }
 
                             // Select all top fields, that are top fields or are in the right path level
                             if  (wildcard.path.is_empty() && !sql_target.subfields)
@@ -432,6 +828,57 @@ impl SqlBuilder {
                             }
                         }
                     }
+                    QueryToken::Selection(selection) => {
+                        // Skip selection for count queries
+                        if self.count_query || self.filter_only {
+                            continue;
+                        }
+                        // Selections belong to the top mapper
+                        if !self.subpath.is_empty() {
+                            continue;
+                        }
+                        let fields = match sql_mapper.selections.get(&selection.name) {
+                            Some(fields) => fields,
+                            None => {
+                                let e = SqlBuilderError::SelectionMissing(selection.name.clone());
+                                self.token_error(&mut result, &mut errors, t, e)?;
+                                continue;
+                            }
+                        };
+                        for field_name in fields {
+                            if self.path_ignored(field_name) {
+                                continue;
+                            }
+                            let sql_target = match sql_mapper.fields.get(field_name) {
+                                Some(sql_target) => sql_target,
+                                None => {
+                                    let e = SqlBuilderError::FieldMissing(field_name.clone());
+                                    self.token_error(&mut result, &mut errors, t, e)?;
+                                    continue;
+                                }
+                            };
+
+                            // Skip fields with missing role
+                            if !Self::validate_roles(&query.roles, &sql_target.options.role_expr) {
+                                continue;
+                            }
+                            if sql_target.subfields
+                                && Self::denied_path(&query.roles, &sql_mapper.joins, field_name).is_some()
+                            {
+                                continue;
+                            }
+                            let f = sql_target_data.entry(field_name.as_str()).or_default();
+                            f.selected = true; // Select field
+                            // Add JOIN information for subfields
+                            if sql_target.subfields {
+                                for subfield in field_name.split('_').rev().skip(1) {
+                                    if !sql_join_data.contains_key(subfield) {
+                                        sql_join_data.insert(subfield, SqlJoinData::default());
+                                    }
+                                }
+                            }
+                        }
+                    }
                     QueryToken::Field(query_field) => {
                         // Ignore field if name does not start with path
                         // E.g "user_id" has path "user"
@@ -442,6 +889,10 @@ impl SqlBuilder {
                         if self.ignored_paths.iter().any(|p| query_field.name.starts_with(p)) {
                             continue;
                         }
+                        // Bulk statements need only filtered fields
+                        if self.filter_only && query_field.filter.is_none() {
+                            continue;
+                        }
 
                         let fieldname = if self.subpath.is_empty() {
                             &query_field.name
@@ -456,12 +907,22 @@ impl SqlBuilder {
                             Some(sql_target) => {
                                 // Verify user role and skip field role mismatches
                                 let role_valid =
-                                    Self::validate_roles(&query.roles, &sql_target.options.roles);
+                                    Self::validate_roles(&query.roles, &sql_target.options.role_expr);
                                 if role_valid == false {
-                                    return Err(SqlBuilderError::RoleRequired(format!(
+                                    let e = SqlBuilderError::RoleRequired(format!(
                                         "Field requires a user role: '{}'. ",
                                         fieldname
-                                    )));
+                                    ));
+                                    self.token_error(&mut result, &mut errors, t, e)?;
+                                    continue;
+                                }
+                                if let Some(path) = Self::denied_path(&query.roles, &sql_mapper.joins, fieldname) {
+                                    let e = SqlBuilderError::RoleRequired(format!(
+                                        "Path requires a user role: '{}'. ",
+                                        path
+                                    ));
+                                    self.token_error(&mut result, &mut errors, t, e)?;
+                                    continue;
                                 }
                                 // Skip filtering and ordering in count queries for unfiltered fields
                                 if self.count_query == true && !sql_target.options.count_filter {
@@ -489,11 +950,22 @@ impl SqlBuilder {
                                 data.used = !query_field.hidden;
 
                                 if let Some(f) = &query_field.filter {
-                                    
-                                    if let Some(f) = sql_target.handler.build_filter(&sql_target.expression, &f)?
-                                        
-                                    {
-                                        if query_field.aggregation == true {
+                                    let built = Self::build_field_filter(sql_mapper, fieldname, sql_target, f);
+                                    let (filter_sql, mut p) = match built {
+                                        Ok(built) => built,
+                                        Err(e) => {
+                                            self.token_error(&mut result, &mut errors, t, e)?;
+                                            continue;
+                                        }
+                                    };
+                                    if let Some(f) = filter_sql {
+                                        // Filters on aggregate fields always go into the having clause
+                                        let having = query_field.aggregation
+                                            || sql_target.filter_type == FilterType::Having;
+                                        if sql_target.filter_type == FilterType::Having {
+                                            aggregate_filtered = true;
+                                        }
+                                        if having {
                                             if need_having_concatenation == true {
                                                 if pending_having_parens > 0 {
                                                     SqlBuilderResult::push_concatenation(
@@ -547,12 +1019,18 @@ impl SqlBuilder {
                                             need_where_concatenation = true;
                                         }
                                     }
-                                    let mut p = sql_target.handler.build_param(&f);
-                                    if query_field.aggregation == true {
+                                    let having = query_field.aggregation
+                                        || sql_target.filter_type == FilterType::Having;
+                                    if having {
                                         result.having_params.append(&mut p);
                                     } else {
                                         result.where_params.append(&mut p);
                                     }
+                                    slots.push(ParamSlot {
+                                        token: token_index,
+                                        toql_field: fieldname.to_owned(),
+                                        having,
+                                    });
 
                                     if let Some(j) = sql_target.handler.build_join() {
                                         result.join_clause.push_str(&j);
@@ -560,6 +1038,11 @@ impl SqlBuilder {
                                     }
                                 }
                                 if let Some(o) = &query_field.order {
+                                    if !sql_target.options.sortable {
+                                        let e = SqlBuilderError::OrderInvalid(fieldname.to_owned());
+                                        self.token_error(&mut result, &mut errors, t, e)?;
+                                        continue;
+                                    }
                                     let num = match o {
                                         FieldOrder::Asc(num) => num,
                                         FieldOrder::Desc(num) => num,
@@ -574,7 +1057,9 @@ impl SqlBuilder {
                                 if !query_field.name.contains("_")
                                     || !self.path_ignored(&query_field.name)
                                 {
-                                    return Err(SqlBuilderError::FieldMissing(query_field.name.clone()));
+                                    let e = SqlBuilderError::FieldMissing(query_field.name.clone());
+                                    self.token_error(&mut result, &mut errors, t, e)?;
+                                    continue;
                                 }
                             }
                         }
@@ -583,6 +1068,38 @@ impl SqlBuilder {
             }
         }
 
+        if !errors.is_empty() {
+            return Err(SqlBuilderError::Multiple(errors));
+        }
+
+        // Use default order, if query has no order
+        if !self.count_query && !self.filter_only && ordinals.is_empty() {
+            for (o, toql_field) in &sql_mapper.default_order {
+                if let Some(sql_target) = sql_mapper.fields.get(toql_field) {
+                    // Skip fields with missing role
+                    if !Self::validate_roles(&query.roles, &sql_target.options.role_expr)
+                        || Self::denied_path(&query.roles, &sql_mapper.joins, toql_field).is_some()
+                    {
+                        continue;
+                    }
+                    sql_target_data.entry(toql_field.as_str()).or_default();
+                    if sql_target.subfields {
+                        for subfield in toql_field.split('_').rev().skip(1) {
+                            if !sql_join_data.contains_key(subfield) {
+                                sql_join_data.insert(subfield, SqlJoinData::default());
+                            }
+                        }
+                    }
+                    let num = match o {
+                        FieldOrder::Asc(num) => num,
+                        FieldOrder::Desc(num) => num,
+                    };
+                    ordinals.insert(*num);
+                    ordering.entry(*num).or_default().push((o.clone(), toql_field.clone()));
+                }
+            }
+        }
+
         // Select all fields for count queries that are marked with count_select
         if self.count_query {
             for (field_name, mapper_field) in &sql_mapper.fields {
@@ -595,9 +1112,15 @@ impl SqlBuilder {
 
         // Build select
         // Ensure implicitly selected subfields are joined
+        // Skip them, if the join requires other roles
+        let mut denied_fields: BTreeSet<&str> = BTreeSet::new();
         for toql_field in &sql_mapper.field_order {
             if let Some(sql_target) = sql_mapper.fields.get(toql_field.as_str()) {
-                if sql_target.options.always_selected && sql_target.subfields {
+                if sql_target.options.always_selected && sql_target.subfields && !self.filter_only {
+                    if Self::denied_path(&query.roles, &sql_mapper.joins, toql_field).is_some() {
+                        denied_fields.insert(toql_field);
+                        continue;
+                    }
                     for subfield in toql_field.split('_').rev().skip(1) {
                         if !sql_join_data.contains_key(subfield) {
                             sql_join_data.insert(subfield, SqlJoinData::default());
@@ -612,6 +1135,7 @@ impl SqlBuilder {
                 &mut result,
                 &sql_mapper.fields,
                 &sql_mapper.field_order,
+                aggregate_filtered,
             );
         } else {
             Self::build_ordering(
@@ -626,6 +1150,9 @@ impl SqlBuilder {
                 &sql_mapper.fields,
                 &sql_target_data,
                 &sql_mapper.field_order,
+                &denied_fields,
+                aggregate_filtered,
+                self.omit_unselected,
             );
         }
 
@@ -653,17 +1180,120 @@ impl SqlBuilder {
             result.order_by_clause = result.order_by_clause.trim_end().to_owned();
         }
 
-        // Create combined params if needed
-        if !result.having_params.is_empty() && !result.where_params.is_empty() {
-            result
-                .combined_params
-                .extend_from_slice(&result.where_params);
-            result
-                .combined_params
-                .extend_from_slice(&result.having_params);
+        result.any_filtered = !result.where_clause.is_empty() || !result.having_clause.is_empty();
+
+        Ok(QueryPlan { result, slots })
+    }
+
+    // Rejects or coerces arguments that do not match the value type and builds the filter
+    fn build_field_filter(
+        sql_mapper: &SqlMapper,
+        fieldname: &str,
+        sql_target: &SqlTarget,
+        filter: &FieldFilter,
+    ) -> Result<(Option<String>, Vec<String>), SqlBuilderError> {
+        Self::validate_filter(fieldname, &sql_target.options, filter)
+            .and_then(|_| sql_target.options.value_type.coerce_filter(fieldname, filter))
+            .and_then(|f| Self::build_filter(sql_mapper, sql_target, &f))
+    }
+
+    // Drops the token under a lenient field policy, otherwise returns the error or records it, if errors are collected
+    fn token_error(
+        &self,
+        result: &mut SqlBuilderResult,
+        errors: &mut Vec<TokenError>,
+        token: &QueryToken,
+        error: SqlBuilderError,
+    ) -> Result<(), SqlBuilderError> {
+        let droppable = matches!(
+            error,
+            SqlBuilderError::FieldMissing(_)
+                | SqlBuilderError::RoleRequired(_)
+                | SqlBuilderError::SelectionMissing(_)
+        );
+        if droppable && self.field_policy != FieldPolicy::Strict {
+            if self.field_policy == FieldPolicy::LenientWithWarnings {
+                result.warnings.push(TokenError::new(token, error));
+            }
+            Ok(())
+        } else if self.collect_errors {
+            errors.push(TokenError::new(token, error));
+            Ok(())
+        } else {
+            Err(error)
         }
+    }
 
-        Ok(result)
+    // Checks allowed filter operations and the LIKE prefix
+    fn validate_filter(
+        toql_field: &str,
+        options: &MapperOptions,
+        filter: &FieldFilter,
+    ) -> Result<(), SqlBuilderError> {
+        if !options.is_filter_allowed(filter) {
+            return Err(SqlBuilderError::FilterInvalid(format!(
+                "field `{}` does not allow filter `{}`",
+                toql_field,
+                filter.name()
+            )));
+        }
+        if let FieldFilter::Lk(criteria) = filter {
+            let criteria = sql_param(criteria.to_owned());
+            let prefix = criteria.chars().take_while(|c| *c != '%' && *c != '_').count();
+            if prefix < options.min_like_prefix {
+                return Err(SqlBuilderError::FilterInvalid(format!(
+                    "field `{}` requires at least {} characters before a LIKE wildcard",
+                    toql_field, options.min_like_prefix
+                )));
+            }
+        }
+        Ok(())
+    }
+
+    // Builds filter predicate and parameters with the field handler or a filter function of the mapper
+    fn build_filter(
+        sql_mapper: &SqlMapper,
+        sql_target: &SqlTarget,
+        filter: &FieldFilter,
+    ) -> Result<(Option<String>, Vec<String>), SqlBuilderError> {
+        if let FieldFilter::Fn(name, args) = filter {
+            if let Some(function) = sql_mapper.functions.get(&name.to_uppercase()) {
+                match sql_target.handler.build_filter(&sql_target.expression, filter) {
+                    Err(SqlBuilderError::FilterInvalid(_)) => {
+                        let (sql, params) = function.build(name, &sql_target.expression, args)?;
+                        return Ok((Some(sql), params));
+                    }
+                    r => return Ok((r?, sql_target.handler.build_param(filter))),
+                }
+            }
+        }
+        Ok((
+            sql_target.handler.build_filter(&sql_target.expression, filter)?,
+            sql_target.handler.build_param(filter),
+        ))
+    }
+
+    fn build_row_filters(
+        result: &mut SqlBuilderResult,
+        sql_mapper: &SqlMapper,
+        query: &Query,
+    ) -> Result<(), SqlBuilderError> {
+        let mut predicates: Vec<String> = Vec::new();
+        for row_filter in &sql_mapper.row_filters {
+            if let Some((predicate, mut params)) = row_filter.build_predicate(&query.roles, &query.context)? {
+                predicates.push(format!("({})", predicate));
+                result.where_params.append(&mut params);
+            }
+        }
+        if predicates.is_empty() {
+            return Ok(());
+        }
+        // Parenthesize query filter, it may contain OR
+        if !result.where_clause.is_empty() {
+            predicates.insert(0, format!("({})", result.where_clause));
+        }
+        result.where_clause = predicates.join(" AND ");
+        Ok(())
     }
 
     fn path_ignored(&self, fieldname: &str) -> bool {
@@ -2,18 +2,42 @@
 //! Result of SQL Builder. Use it to get SQL that can be sent to the database.
 
 use crate::query::Concatenation;
+use crate::sql_builder::TokenError;
 
 
+/// Describes a column in the select clause.
+#[derive(Debug, Clone, PartialEq)]
+pub struct ColumnDescriptor {
+    /// Toql field of the column, e.g. `author_id`.
+    pub toql_field: String,
+    /// The field is selected, otherwise the column is `null`.
+    pub selected: bool,
+}
+
+impl ColumnDescriptor {
+    pub(crate) fn new(toql_field: &str, selected: bool) -> Self {
+        ColumnDescriptor {
+            toql_field: toql_field.to_owned(),
+            selected,
+        }
+    }
+}
+
 /// The SQL Builder Result is created by the [SQL Builder](../sql_builder/struct.SqlBuilder.html).
+#[derive(Clone)]
 pub struct SqlBuilderResult {
      
     pub(crate) table: String,
     pub(crate) any_selected: bool,
+    pub(crate) any_filtered: bool, // Query filters, row filters are not considered
      pub(crate) distinct: bool,
     pub(crate) join_clause: String,
     pub(crate) select_clause: String,
+    pub(crate) columns: Vec<ColumnDescriptor>, // Toql field of every column
+    pub(crate) warnings: Vec<TokenError>,      // Dropped fields
     pub(crate) where_clause: String,
     pub(crate) order_by_clause: String,
+    pub(crate) group_by_clause: String,
     pub(crate) having_clause: String,
     pub(crate) where_params: Vec<String>,
     pub(crate) having_params: Vec<String>,
@@ -24,10 +48,10 @@ pub struct SqlBuilderResult {
 impl SqlBuilderResult {
 
     /// Returns true if no field is neither selected nor filtered.
+    /// Predicates from row filters are not considered.
     pub fn is_empty (&self) -> bool{
             !self.any_selected
-        &&  self.where_clause.is_empty()
-        &&  self.having_clause.is_empty()
+        &&  !self.any_filtered
     }
     
     fn sql_body(&self,  s: &mut String)  {
@@ -45,6 +69,10 @@ impl SqlBuilderResult {
             s.push_str(" WHERE " );
             s.push_str(&self.where_clause);
          }
+         if !self.group_by_clause.is_empty() {
+            s.push_str(" GROUP BY " );
+            s.push_str(&self.group_by_clause);
+         }
          if !self.having_clause.is_empty() {
            s.push_str(" HAVING " );
             s.push_str(&self.having_clause);
@@ -83,6 +111,42 @@ impl SqlBuilderResult {
            self.sql_body( &mut s);
            s
     }
+    /// Returns SQL that counts the rows of the query with the same parameters.
+    /// Distinct and grouped queries are counted in a subquery `SELECT COUNT(*) FROM (...) AS toql_count`.
+    /// The order is dropped, because it does not change the count.
+    pub fn to_count_sql(&self) -> String {
+        let mut s = String::from("SELECT COUNT(*) FROM ");
+        if !self.distinct && self.group_by_clause.is_empty() && self.having_clause.is_empty() {
+            s.push_str(&self.table);
+            if !self.join_clause.is_empty() {
+                s.push(' ');
+                s.push_str(&self.join_clause);
+            }
+            if !self.where_clause.is_empty() {
+                s.push_str(" WHERE ");
+                s.push_str(&self.where_clause);
+            }
+            return s;
+        }
+        let unordered = SqlBuilderResult {
+            order_by_clause: String::from(""),
+            ..self.clone()
+        };
+        s.push('(');
+        s.push_str(&unordered.to_sql());
+        s.push_str(") AS toql_count");
+        s
+    }
+    /// Returns a descriptor for every column in the select clause, in column order.
+    /// Unselected fields are selected as `null`, unless the SQL Builder omits them.
+    /// Count queries without selected fields select `1` and have no columns.
+    pub fn columns(&self) -> &[ColumnDescriptor] {
+        &self.columns
+    }
+    /// Returns the query fields that were dropped by the [field policy](../sql_builder/enum.FieldPolicy.html) of the SQL Builder.
+    pub fn warnings(&self) -> &[TokenError] {
+        &self.warnings
+    }
     /// Returns SQL parameters for the WHERE and HAVING clauses in SQL.
     pub fn params(&self) -> &Vec<String> {
         if self.where_params.is_empty() {
@@ -28,35 +28,72 @@
 //!  - to a single field with [map_handler()](struct.SqlMapper.html#method.map_handler).
 //!  - to all fields with [new_with_handler()](struct.SqlMapper.html#method.new_with_handler).
 //! 
+//! ### Row filters
+//! Predicates and [row filters](trait.RowFilter.html) restrict the rows of every query against a mapper, 
+//! for example to scope rows to a tenant or owner. They are applied to normal, count and path queries.
+//! Use [add_predicate()](struct.SqlMapper.html#method.add_predicate) or [add_row_filter()](struct.SqlMapper.html#method.add_row_filter).
+//!
 //! ### Caching
 //! If a struct contains merged fields (collections of structs) then the SQL Builder must build multiple SQL queries with different mappers.
 //! To give high level functions all SQL Mappers, they must be put into a cache. This allows to
 //! load the full dependency tree.
 //! 
 
+use crate::mapper_registry::MapperRegistry;
 use crate::query::FieldFilter;
+use crate::query::FieldOrder;
+use crate::query::Query;
+use crate::query::QueryToken;
+use crate::role_expr::RoleExpr;
 use crate::sql_builder::SqlBuilderError;
 use std::collections::BTreeSet;
 use std::collections::HashMap;
+use std::sync::atomic::AtomicU64;
+use std::sync::atomic::Ordering;
 use std::sync::Arc;
 
 use enquote::unquote;
 
-#[derive(Debug)]
-#[allow(dead_code)] // IMPROVE Having AND None are considered unused
+#[derive(Debug, Clone, PartialEq)]
+#[allow(dead_code)] // IMPROVE None is considered unused
 pub(crate) enum FilterType {
     Where,
     Having,
     None,
 }
 
-#[derive(Debug)]
+impl FilterType {
+    fn from_options(options: &MapperOptions) -> Self {
+        if options.aggregate {
+            FilterType::Having
+        } else {
+            FilterType::Where
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
 pub(crate) struct SqlTarget {
     pub(crate) options: MapperOptions,                   // Options
     pub(crate) filter_type: FilterType,                  // Filter on where or having clause
     pub(crate) handler: Arc<FieldHandler + Send + Sync>, // Handler to create clauses
     pub(crate) subfields: bool,                          // Target name has subfields separated by underscore
     pub(crate) expression: String,                       // Column name or SQL expression
+    pub(crate) column: bool,                             // Expression is a column that can be updated
+}
+
+// Column name with optional table alias, like `b.title`
+fn is_column(expression: &str) -> bool {
+    let mut parts = expression.split('.');
+    let valid = |p: &str| {
+        p.chars().next().is_some_and(|c| c.is_ascii_alphabetic() || c == '_')
+            && p.chars().all(|c| c.is_ascii_alphanumeric() || c == '_')
+    };
+    match (parts.next(), parts.next(), parts.next()) {
+        (Some(c), None, None) => valid(c),
+        (Some(a), Some(c), None) => valid(a) && valid(c),
+        _ => false,
+    }
 }
 
 /// Handles the standart filters as documented in the guide.
@@ -64,14 +101,19 @@ pub(crate) struct SqlTarget {
 #[derive(Debug, Clone)]
 pub struct BasicFieldHandler {}
 
-#[derive(Debug)]
+#[derive(Debug, Clone)]
 /// Options for a mapped field.
 pub struct MapperOptions {
     pub(crate) always_selected: bool,   // Always select this field, regardless of query fields
     pub(crate) count_filter: bool,      // Filter field on count query
     pub(crate) count_select: bool,      // Select field on count query
     pub(crate) ignore_wildcard: bool,   // Ignore field for wildcard selection
-    pub(crate) roles: BTreeSet<String>, // Only for use by these roles
+    pub(crate) role_expr: Option<RoleExpr>, // Only for use by queries with these roles
+    pub(crate) value_type: ValueType,   // Expected type of filter arguments
+    pub(crate) filters: Option<BTreeSet<String>>, // Allowed filter operations, all if None
+    pub(crate) sortable: bool,                    // Field can be ordered
+    pub(crate) min_like_prefix: usize,            // Minimum characters before the first LIKE wildcard
+    pub(crate) aggregate: bool,                   // Field is an aggregate expression
 }
 
 
@@ -84,7 +126,12 @@ impl MapperOptions {
             count_filter: false,
             count_select: false,
             ignore_wildcard: false,
-            roles: BTreeSet::new(),
+            role_expr: None,
+            value_type: ValueType::Any,
+            filters: None,
+            sortable: true,
+            min_like_prefix: 0,
+            aggregate: false,
         }
     }
     /// Field is always selected, regardless of the query.
@@ -117,9 +164,170 @@ impl MapperOptions {
     /// Example: The email address is only visible to users with
     /// the _admin_ role.
     pub fn restrict_roles(mut self, roles: BTreeSet<String>) -> Self {
-        self.roles = roles;
+        self.role_expr = if roles.is_empty() { None } else { Some(RoleExpr::all_of(roles)) };
+        self
+    }
+    /// The field can only be selected and filtered by queries whose roles
+    /// satisfy the [role expression](../role_expr/enum.RoleExpr.html).
+    /// Example: The email address is visible to users with the _admin_ or the _owner_ role: `admin;owner`.
+    pub fn restrict_role_expr(mut self, role_expr: RoleExpr) -> Self {
+        self.role_expr = Some(role_expr);
         self
     }
+    /// Filter arguments must match this value type.
+    /// Arguments that do not match are rejected by the SQL Builder with
+    /// [FilterInvalid](../sql_builder/enum.SqlBuilderError.html).
+    pub fn value_type(mut self, value_type: ValueType) -> Self {
+        self.value_type = value_type;
+        self
+    }
+    /// The field can only be filtered with these operations, e.g. `EQ` and `IN`. 
+    /// `FN` allows all custom functions. An empty set disallows filtering.
+    /// Other operations are rejected by the SQL Builder with [FilterInvalid](../sql_builder/enum.SqlBuilderError.html).
+    pub fn restrict_filters(mut self, filters: BTreeSet<String>) -> Self {
+        self.filters = Some(filters.iter().map(|f| f.to_uppercase()).collect());
+        self
+    }
+    /// The field can be ordered (default).
+    /// Ordering an unsortable field is rejected by the SQL Builder with [OrderInvalid](../sql_builder/enum.SqlBuilderError.html).
+    pub fn sortable(mut self, sortable: bool) -> Self {
+        self.sortable = sortable;
+        self
+    }
+    /// `LK` filters must have at least this many characters before the first wildcard (`%` or `_`).
+    /// This prevents full scans with `LK '%x%'` on large tables.
+    pub fn min_like_prefix(mut self, min_like_prefix: usize) -> Self {
+        self.min_like_prefix = min_like_prefix;
+        self
+    }
+    /// The field is mapped to an aggregate expression, such as `COUNT(o.id)`.
+    /// Filters on the field always go into the HAVING clause. 
+    /// If the field is selected or filtered, the SQL Builder groups by all other selected columns.
+    pub fn aggregate(mut self, aggregate: bool) -> Self {
+        self.aggregate = aggregate;
+        self
+    }
+    /// Returns true, if the field is always selected.
+    pub fn is_select_always(&self) -> bool {
+        self.always_selected
+    }
+    /// Returns true, if filters on the field are used for count queries.
+    pub fn is_count_filter(&self) -> bool {
+        self.count_filter
+    }
+    /// Returns true, if the field is selected for count queries.
+    pub fn is_count_select(&self) -> bool {
+        self.count_select
+    }
+    /// Returns true, if the field is ignored by wildcards.
+    pub fn is_ignore_wildcard(&self) -> bool {
+        self.ignore_wildcard
+    }
+    /// Returns the role expression a query must satisfy to use the field.
+    pub fn role_expr(&self) -> Option<&RoleExpr> {
+        self.role_expr.as_ref()
+    }
+    /// Returns the value type of the field.
+    pub fn get_value_type(&self) -> ValueType {
+        self.value_type
+    }
+    /// Returns the allowed filter operations or `None`, if all operations are allowed.
+    pub fn filters(&self) -> Option<&BTreeSet<String>> {
+        self.filters.as_ref()
+    }
+    /// Returns true, if the filter operation is allowed.
+    pub fn is_filter_allowed(&self, filter: &FieldFilter) -> bool {
+        self.filters.as_ref().is_none_or(|f| f.contains(filter.name()))
+    }
+    /// Returns true, if the field is an aggregate expression.
+    pub fn is_aggregate(&self) -> bool {
+        self.aggregate
+    }
+    /// Returns true, if the field can be ordered.
+    pub fn is_sortable(&self) -> bool {
+        self.sortable
+    }
+    /// Returns the minimum number of characters before the first LIKE wildcard.
+    pub fn get_min_like_prefix(&self) -> usize {
+        self.min_like_prefix
+    }
+}
+
+/// The logical type of a field. Used to validate filter arguments.
+///
+/// Arguments of comparison filters (`EQ`, `NE`, `GT`, `GE`, `LT`, `LE`, `BW`, `IN`, `OUT`) are checked.
+/// Quoted numbers are coerced for numeric fields, so `age eq '5'` becomes `age eq 5`.
+/// Numbers are coerced for text fields, so `name eq 5` becomes `name eq '5'`.
+/// `LK`, `RE` and `FN` are not checked.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "json", derive(serde::Serialize), serde(rename_all = "lowercase"))]
+pub enum ValueType {
+    /// Any argument is accepted (default).
+    Any,
+    /// Booleans, `0`, `1`, `'true'` or `'false'`. Arguments are coerced to `0` or `1`.
+    Boolean,
+    /// Whole numbers.
+    Integer,
+    /// Floating point numbers.
+    Float,
+    /// Text.
+    Text,
+}
+
+impl std::fmt::Display for ValueType {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        match *self {
+            ValueType::Any => write!(f, "any"),
+            ValueType::Boolean => write!(f, "boolean"),
+            ValueType::Integer => write!(f, "integer"),
+            ValueType::Float => write!(f, "float"),
+            ValueType::Text => write!(f, "text"),
+        }
+    }
+}
+
+impl ValueType {
+    /// Checks all arguments of a filter and returns the filter with coerced arguments.
+    /// On mismatch returns [FilterInvalid](../sql_builder/enum.SqlBuilderError.html) with field name and expected type.
+    pub fn coerce_filter(&self, toql_field: &str, filter: &FieldFilter) -> Result<FieldFilter, SqlBuilderError> {
+        if *self == ValueType::Any {
+            return Ok(filter.clone());
+        }
+        let c = |arg: &String| self.coerce_arg(toql_field, arg);
+        Ok(match filter {
+            FieldFilter::Eq(a) => FieldFilter::Eq(c(a)?),
+            FieldFilter::Ne(a) => FieldFilter::Ne(c(a)?),
+            FieldFilter::Gt(a) => FieldFilter::Gt(c(a)?),
+            FieldFilter::Ge(a) => FieldFilter::Ge(c(a)?),
+            FieldFilter::Lt(a) => FieldFilter::Lt(c(a)?),
+            FieldFilter::Le(a) => FieldFilter::Le(c(a)?),
+            FieldFilter::Bw(l, u) => FieldFilter::Bw(c(l)?, c(u)?),
+            FieldFilter::In(args) => FieldFilter::In(args.iter().map(c).collect::<Result<Vec<_>, _>>()?),
+            FieldFilter::Out(args) => FieldFilter::Out(args.iter().map(c).collect::<Result<Vec<_>, _>>()?),
+            _ => filter.clone(),
+        })
+    }
+
+    // Returns the coerced argument or an error. Arguments are either numbers or quoted strings (Pest rule).
+    fn coerce_arg(&self, toql_field: &str, arg: &str) -> Result<String, SqlBuilderError> {
+        let quoted = arg.starts_with('\'');
+        let value = if quoted { sql_param(arg.to_owned()) } else { arg.to_owned() };
+        let coerced = match self {
+            ValueType::Any => Some(arg.to_owned()),
+            ValueType::Boolean => match value.to_lowercase().as_str() {
+                "1" | "true" => Some(String::from("1")),
+                "0" | "false" => Some(String::from("0")),
+                _ => None,
+            },
+            ValueType::Integer => value.parse::<i64>().ok().map(|_| value.clone())
+                .or_else(|| value.parse::<u64>().ok().map(|_| value.clone())),
+            ValueType::Float => value.parse::<f64>().ok().map(|_| value.clone()),
+            ValueType::Text => Some(if quoted { arg.to_owned() } else { enquote::enquote('\'', arg) }),
+        };
+        coerced.ok_or_else(|| {
+            SqlBuilderError::FilterInvalid(format!("field `{}` expects {}, got {}", toql_field, self, arg))
+        })
+    }
 }
 
 trait MapperFilter {
@@ -241,22 +449,238 @@ impl FieldHandler for BasicFieldHandler {
     }
 }
 
-/// A cache that holds mappers.
-pub type SqlMapperCache = HashMap<String, SqlMapper>;
+/// A row filter restricts the rows of all queries against a mapper.
+/// It gets the roles and context values of the query and returns a mandatory predicate for the WHERE clause.
+/// Closures with the same signature as [build_predicate()](trait.RowFilter.html#tymethod.build_predicate) are row filters too.
+///
+/// ## Example
+/// ``` ignore
+/// mapper.add_row_filter(|_roles: &BTreeSet<String>, context: &HashMap<String, String>| {
+///     let tenant = context.get("tenant_id").ok_or(SqlBuilderError::ContextMissing(String::from("tenant_id")))?;
+///     Ok(Some((String::from("b.tenant_id = ?"), vec![tenant.to_owned()])))
+/// });
+/// ```
+pub trait RowFilter {
+    /// Returns the predicate with its parameters or `None`, if the query is not restricted.
+    /// Do not insert parameters in the predicate, use `?` instead.
+    fn build_predicate(
+        &self,
+        roles: &BTreeSet<String>,
+        context: &HashMap<String, String>,
+    ) -> Result<Option<(String, Vec<String>)>, SqlBuilderError>;
+}
+
+impl<F> RowFilter for F
+where
+    F: Fn(&BTreeSet<String>, &HashMap<String, String>) -> Result<Option<(String, Vec<String>)>, SqlBuilderError>,
+{
+    fn build_predicate(
+        &self,
+        roles: &BTreeSet<String>,
+        context: &HashMap<String, String>,
+    ) -> Result<Option<(String, Vec<String>)>, SqlBuilderError> {
+        self(roles, context)
+    }
+}
+
+impl std::fmt::Debug for dyn RowFilter + std::marker::Send + std::marker::Sync + 'static {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        write!(f, "RowFilter()")
+    }
+}
+
+/// A filter function handles a custom `FN` filter, such as `title FN LN 5`.
+/// It gets the SQL expression of the field and the unquoted arguments and returns the SQL predicate with its parameters.
+/// Closures with the same signature as [build_function()](trait.FilterFunction.html#tymethod.build_function) are filter functions too.
+///
+/// ## Example
+/// ``` ignore
+/// mapper.map_function("LN", 1, 1, |expression: &str, args: &[String]| {
+///     Ok((format!("LENGTH({}) = ?", expression), args.to_vec()))
+/// });
+/// ```
+pub trait FilterFunction {
+    /// Returns the predicate and its parameters.
+    /// Do not insert parameters in the predicate, use `?` instead.
+    fn build_function(&self, expression: &str, args: &[String]) -> Result<(String, Vec<String>), SqlBuilderError>;
+}
+
+impl<F> FilterFunction for F
+where
+    F: Fn(&str, &[String]) -> Result<(String, Vec<String>), SqlBuilderError>,
+{
+    fn build_function(&self, expression: &str, args: &[String]) -> Result<(String, Vec<String>), SqlBuilderError> {
+        self(expression, args)
+    }
+}
+
+impl std::fmt::Debug for dyn FilterFunction + std::marker::Send + std::marker::Sync + 'static {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        write!(f, "FilterFunction()")
+    }
+}
+
+// Filter function with arity, see `SqlMapper::map_function`
+#[derive(Debug, Clone)]
+pub(crate) struct Function {
+    function: Arc<dyn FilterFunction + Send + Sync>,
+    min_args: usize,
+    max_args: usize,
+}
+
+impl Function {
+    pub(crate) fn build(
+        &self,
+        name: &str,
+        expression: &str,
+        args: &[String],
+    ) -> Result<(String, Vec<String>), SqlBuilderError> {
+        if args.len() < self.min_args || args.len() > self.max_args {
+            let expected = if self.min_args == self.max_args {
+                format!("{}", self.min_args)
+            } else {
+                format!("{} to {}", self.min_args, self.max_args)
+            };
+            return Err(SqlBuilderError::FilterInvalid(format!(
+                "filter `{}` expects {} argument{}, got {}",
+                name,
+                expected,
+                if self.max_args == 1 { "" } else { "s" },
+                args.len()
+            )));
+        }
+        let args: Vec<String> = args.iter().map(|a| sql_param(a.to_owned())).collect();
+        self.function.build_function(expression, &args)
+    }
+}
+
+// Predicate with fixed parameters, see `SqlMapper::add_predicate`
+struct Predicate {
+    sql: String,
+    params: Vec<String>,
+    role_expr: Option<RoleExpr>,
+}
+
+impl RowFilter for Predicate {
+    fn build_predicate(
+        &self,
+        roles: &BTreeSet<String>,
+        _context: &HashMap<String, String>,
+    ) -> Result<Option<(String, Vec<String>)>, SqlBuilderError> {
+        if self.role_expr.as_ref().is_none_or(|e| e.is_valid(roles)) {
+            Ok(Some((self.sql.to_owned(), self.params.to_owned())))
+        } else {
+            Ok(None)
+        }
+    }
+}
+
+/// A cache that holds mappers. See [MapperRegistry](../mapper_registry/struct.MapperRegistry.html).
+pub type SqlMapperCache = MapperRegistry;
+
+/// Path of the link table in mappers for many-to-many merges, see [join_through()](struct.SqlMapper.html#method.join_through).
+pub const THROUGH_PATH: &str = "toqlThrough";
+/// Field with the key of the merging struct in mappers for many-to-many merges.
+pub const THROUGH_KEY: &str = "toqlThrough_key";
+
+/// Represents all errors from the SQL Mapper
+#[derive(Debug, PartialEq)]
+pub enum SqlMapperError {
+    /// The field is not mapped. Contains the field name.
+    FieldMissing(String),
+    /// A field uses a path, but no join is registered for it. Contains the path.
+    JoinMissing(String),
+    /// A join is registered, but no field uses its path. Contains the path.
+    JoinUnused(String),
+    /// The field appears more than once in the field order. Contains the field name.
+    FieldDuplicate(String),
+    /// The join clause references an alias that is neither the table alias nor introduced by a join. Contains path and alias.
+    AliasUnknown(String, String),
+    /// The mapper for a merge is not in the cache. Contains path and mapper name.
+    MergeMapperMissing(String, String),
+}
+
+impl std::fmt::Display for SqlMapperError {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        match *self {
+            SqlMapperError::FieldMissing(ref s) =>
+                write!(f, "field `{}` is not mapped", s),
+            SqlMapperError::JoinMissing(ref s) =>
+                write!(f, "join for path `{}` is missing", s),
+            SqlMapperError::JoinUnused(ref s) =>
+                write!(f, "join for path `{}` is not used by any field", s),
+            SqlMapperError::FieldDuplicate(ref s) =>
+                write!(f, "field `{}` is mapped more than once", s),
+            SqlMapperError::AliasUnknown(ref p, ref a) =>
+                write!(f, "join for path `{}` uses unknown alias `{}`", p, a),
+            SqlMapperError::MergeMapperMissing(ref p, ref m) =>
+                write!(f, "merge for path `{}` needs missing mapper `{}`", p, m),
+        }
+    }
+}
+
+/// Validates all mappers in a cache and checks that the mappers of all merges are present.
+/// Returns all problems together with the name of the mapper they were found in.
+pub fn validate_cache(cache: &SqlMapperCache) -> Result<(), Vec<(String, SqlMapperError)>> {
+    let mut errors = Vec::new();
+    for name in cache.keys() {
+        let mapper = cache.get(name).unwrap();
+        if let Err(mut e) = mapper.validate() {
+            errors.extend(e.drain(..).map(|e| (name.to_owned(), e)));
+        }
+        let mut paths: Vec<&String> = mapper.merges.keys().collect();
+        paths.sort();
+        for path in paths {
+            let merge_mapper = &mapper.merges.get(path).unwrap().merge_mapper;
+            if !cache.contains_key(merge_mapper) {
+                errors.push((
+                    name.to_owned(),
+                    SqlMapperError::MergeMapperMissing(path.to_owned(), merge_mapper.to_owned()),
+                ));
+            }
+        }
+    }
+
+    if errors.is_empty() {
+        Ok(())
+    } else {
+        Err(errors)
+    }
+}
 
 /// Translates Toql fields into columns or SQL expressions.
-#[derive(Debug)]
+#[derive(Debug, Clone)]
 pub struct SqlMapper {
     pub(crate) handler: Arc<FieldHandler + Send + Sync>,
     pub(crate) table: String,
     pub(crate) field_order: Vec<String>,
     pub(crate) fields: HashMap<String, SqlTarget>,
     pub(crate) joins: HashMap<String, Join>,
+    pub(crate) merges: HashMap<String, Merge>,
+    pub(crate) row_filters: Vec<Arc<dyn RowFilter + Send + Sync>>,
+    pub(crate) default_order: Vec<(FieldOrder, String)>, // Used, if query has no order
+    pub(crate) selections: HashMap<String, Vec<String>>, // Named sets of fields
+    pub(crate) functions: HashMap<String, Function>,     // FN filters by uppercase name
+    pub(crate) version: u64,                             // Unique for every change, part of query plan keys
+}
+
+// Source of mapper versions, shared by all mappers so that no two mappers get the same version
+static MAPPER_VERSION: AtomicU64 = AtomicU64::new(0);
+
+fn next_version() -> u64 {
+    MAPPER_VERSION.fetch_add(1, Ordering::Relaxed) + 1
 }
 
-#[derive(Debug)]
+#[derive(Debug, Clone)]
 pub(crate) struct Join {
     pub(crate) join_clause: String,
+    pub(crate) role_expr: Option<RoleExpr>, // Only for use by queries with these roles
+}
+
+#[derive(Debug, Clone)]
+pub(crate) struct Merge {
+    pub(crate) merge_mapper: String,        // Name of mapper in cache
+    pub(crate) role_expr: Option<RoleExpr>, // Only for use by queries with these roles
 }
 /// Structs that implement `Mapped` can be added to the mapper with [map()](struct.SqlMapper.html#method.map).
 /// 
@@ -271,6 +695,9 @@ pub trait Mapped {
     where  H: 'static + FieldHandler + Send + Sync // TODO improve lifetime
      ;                            // Create new SQL Mapper and map entity fields
     fn map(mapper: &mut SqlMapper, toql_path: &str, sql_alias: &str);       // Map entity fields
+    fn type_name() -> String;                                               // Name of mapper in cache
+    fn table_name() -> String { Self::type_name() }                         // Table name, used to join type parameters of generic structs
+    fn register_dependencies(_cache: &mut SqlMapperCache) {}                // Insert mappers of joined and merged structs
 }
 
 impl SqlMapper {
@@ -298,6 +725,12 @@ impl SqlMapper {
             joins: HashMap::new(),
             fields: HashMap::new(),
             field_order: Vec::new(),
+            merges: HashMap::new(),
+            row_filters: Vec::new(),
+            default_order: Vec::new(),
+            selections: HashMap::new(),
+            functions: HashMap::new(),
+            version: next_version(),
         }
     }
     /// Creates and inserts a new mapper into a cache.
@@ -345,14 +778,16 @@ impl SqlMapper {
      where H: 'static + FieldHandler + Send + Sync
     {
         let t = SqlTarget {
+            filter_type: FilterType::from_options(&options), // Filter on where or having clause
             options: options,
-            filter_type: FilterType::Where, // Filter on where clause
             subfields: toql_field.find('_').is_some(),
             handler: Arc::new(handler),
             expression: expression.to_string(),
+            column: is_column(expression),
         };
-        self.field_order.push(toql_field.to_string());
+        self.order_field(toql_field);
         self.fields.insert(toql_field.to_string(), t);
+        self.changed();
         self
     }
     /// Changes the handler of a field.
@@ -365,13 +800,24 @@ impl SqlMapper {
     ) -> &mut Self 
      where H: 'static + FieldHandler + Send + Sync
     {
-        let sql_target = self.fields.get_mut(toql_field).expect(&format!(
-            "Cannot alter \"{}\": Field is not mapped.",
-            toql_field
-        ));
+        self.try_alter_handler(toql_field, handler)
+            .unwrap_or_else(|e| panic!("Cannot alter \"{}\": {}.", toql_field, e))
+    }
+    /// Changes the handler of a field.
+    /// Returns [FieldMissing](enum.SqlMapperError.html) if the field does not exist.
+    pub fn try_alter_handler<H>(
+        &mut self,
+        toql_field: &str,
+        handler: H,
+    ) -> Result<&mut Self, SqlMapperError>
+     where H: 'static + FieldHandler + Send + Sync
+    {
+        let sql_target = self.fields.get_mut(toql_field)
+            .ok_or(SqlMapperError::FieldMissing(toql_field.to_owned()))?;
 
         sql_target.handler = Arc::new(handler);
-        self
+        self.changed();
+        Ok(self)
     }
     /// Changes the handler and options of a field.
     /// This will panic if the field does not exist
@@ -382,13 +828,24 @@ impl SqlMapper {
         handler: Arc<FieldHandler + Sync + Send>,
         options: MapperOptions,
     ) -> &mut Self {
-        let sql_target = self.fields.get_mut(toql_field).expect(&format!(
-            "Cannot alter \"{}\": Field is not mapped.",
-            toql_field
-        ));
+        self.try_alter_handler_with_options(toql_field, handler, options)
+            .unwrap_or_else(|e| panic!("Cannot alter \"{}\": {}.", toql_field, e))
+    }
+    /// Changes the handler and options of a field.
+    /// Returns [FieldMissing](enum.SqlMapperError.html) if the field does not exist.
+    pub fn try_alter_handler_with_options(
+        &mut self,
+        toql_field: &str,
+        handler: Arc<dyn FieldHandler + Sync + Send>,
+        options: MapperOptions,
+    ) -> Result<&mut Self, SqlMapperError> {
+        let sql_target = self.fields.get_mut(toql_field)
+            .ok_or(SqlMapperError::FieldMissing(toql_field.to_owned()))?;
+        sql_target.filter_type = FilterType::from_options(&options);
         sql_target.options = options;
         sql_target.handler = handler;
-        self
+        self.changed();
+        Ok(self)
     }
     /// Changes the database column or SQL expression of a field.
     /// This will panic if the field does not exist
@@ -399,13 +856,47 @@ impl SqlMapper {
         sql_expression: &str,
         options: MapperOptions,
     ) -> &mut Self {
-        let sql_target = self.fields.get_mut(toql_field).expect(&format!(
-            "Cannot alter \"{}\": Field is not mapped.",
-            toql_field
-        ));
+        self.try_alter_field(toql_field, sql_expression, options)
+            .unwrap_or_else(|e| panic!("Cannot alter \"{}\": {}.", toql_field, e))
+    }
+    /// Changes the database column or SQL expression of a field.
+    /// Returns [FieldMissing](enum.SqlMapperError.html) if the field does not exist.
+    pub fn try_alter_field(
+        &mut self,
+        toql_field: &str,
+        sql_expression: &str,
+        options: MapperOptions,
+    ) -> Result<&mut Self, SqlMapperError> {
+        let sql_target = self.fields.get_mut(toql_field)
+            .ok_or(SqlMapperError::FieldMissing(toql_field.to_owned()))?;
         sql_target.expression = sql_expression.to_string();
+        sql_target.column = is_column(sql_expression);
+        sql_target.filter_type = FilterType::from_options(&options);
         sql_target.options = options;
-        self
+        self.changed();
+        Ok(self)
+    }
+    /// Removes a field from the mapper. Queries that use the field will fail with
+    /// [FieldMissing](../sql_builder/enum.SqlBuilderError.html) and wildcards skip it.
+    /// The field keeps its position in the select clause and is selected as `null`,
+    /// so that structs from the Toql derive can still be loaded. Only unmap optional fields of derived structs.
+    /// Mapping the field again puts it back into its old position.
+    /// Returns [FieldMissing](enum.SqlMapperError.html) if the field does not exist.
+    pub fn unmap_field(&mut self, toql_field: &str) -> Result<&mut Self, SqlMapperError> {
+        self.fields.remove(toql_field)
+            .ok_or(SqlMapperError::FieldMissing(toql_field.to_owned()))?;
+        self.changed();
+        Ok(self)
+    }
+    // Mappers get a new version on every change, so that cached query plans are not reused
+    fn changed(&mut self) {
+        self.version = next_version();
+    }
+    // Fields that are mapped again keep their position in the select clause
+    fn order_field(&mut self, toql_field: &str) {
+        if !self.field_order.iter().any(|f| f == toql_field) {
+            self.field_order.push(toql_field.to_string());
+        }
     }
     /// Adds a new field -or updates an existing field- to the mapper.
     pub fn map_field<'a>(&'a mut self, toql_field: &str, sql_field: &str) -> &'a mut Self {
@@ -422,14 +913,16 @@ impl SqlMapper {
        
         let t = SqlTarget {
             expression: sql_expression.to_string(),
+            column: is_column(sql_expression),
+            filter_type: FilterType::from_options(&options), // Filter on where or having clause
             options: options,
-            filter_type: FilterType::Where, // Filter on where clause
             subfields: toql_field.find('_').is_some(),
             handler: Arc::clone(&self.handler)
         };
 
-        self.field_order.push(toql_field.to_string());
+        self.order_field(toql_field);
         self.fields.insert(toql_field.to_string(), t);
+        self.changed();
         self
     }
     /// Adds a join for a given path to the mapper. 
@@ -439,19 +932,503 @@ impl SqlMapper {
             toql_path.to_string(),
             Join {
                 join_clause: join_clause.to_string(),
+                role_expr: None,
             },
         );
-
-        // Find targets that use join and set join field
-
+        self.changed();
+        self
+    }
+    /// Adds a join that can only be used by queries whose roles satisfy the role expression.
+    /// Queries without the roles fail with [RoleRequired](../sql_builder/enum.SqlBuilderError.html)
+    /// if they use a field from the path. Wildcards skip those fields.
+    pub fn join_with_roles<'a>(&'a mut self, toql_path: &str, join_clause: &str, role_expr: RoleExpr) -> &'a mut Self {
+        self.joins.insert(
+            toql_path.to_string(),
+            Join {
+                join_clause: join_clause.to_string(),
+                role_expr: Some(role_expr),
+            },
+        );
+        self.changed();
         self
     }
     /// Changes an already added join.
     /// This will panic if the join does not exist
     /// Use it to make changes, it prevents typing errors of path names.
     pub fn alter_join<'a>(&'a mut self, toql_path: &str, join_clause: &str) -> &'a mut Self {
-        let j = self.joins.get_mut(toql_path).expect("Join is missing.");
+        self.try_alter_join(toql_path, join_clause)
+            .unwrap_or_else(|e| panic!("Cannot alter \"{}\": {}.", toql_path, e))
+    }
+    /// Changes an already added join.
+    /// Returns [JoinMissing](enum.SqlMapperError.html) if the join does not exist.
+    pub fn try_alter_join<'a>(&'a mut self, toql_path: &str, join_clause: &str) -> Result<&'a mut Self, SqlMapperError> {
+        let j = self.joins.get_mut(toql_path)
+            .ok_or(SqlMapperError::JoinMissing(toql_path.to_owned()))?;
         j.join_clause = join_clause.to_string();
+        self.changed();
+        Ok(self)
+    }
+    /// Removes a join and unmaps all fields that use its path. 
+    /// Joins below the path, that no other field uses, are removed too.
+    /// See [unmap_field()](struct.SqlMapper.html#method.unmap_field) for details.
+    /// Returns [JoinMissing](enum.SqlMapperError.html) if the join does not exist.
+    pub fn remove_join<'a>(&'a mut self, toql_path: &str) -> Result<&'a mut Self, SqlMapperError> {
+        self.joins.remove(toql_path)
+            .ok_or(SqlMapperError::JoinMissing(toql_path.to_owned()))?;
+        let mut nested_paths: BTreeSet<String> = BTreeSet::new();
+        self.fields.retain(|toql_field, _| {
+            let through_path = toql_field.split('_').rev().skip(1).any(|p| p == toql_path);
+            if through_path {
+                nested_paths.extend(toql_field.split('_').rev().skip(1).map(String::from));
+            }
+            !through_path
+        });
+        let used_paths: BTreeSet<&str> = self.fields.keys().flat_map(|f| f.split('_').rev().skip(1)).collect();
+        self.joins.retain(|path, _| {
+            !path.split('_').any(|p| p == toql_path) && (!nested_paths.contains(path) || used_paths.contains(path.as_str()))
+        });
+        self.changed();
+        Ok(self)
+    }
+    /// Joins a link table for many-to-many merges. The Toql derive uses it for `merge(through=..)`.
+    /// The link column `link_column` refers to the field `toql_field` of this mapper,
+    /// the link column `key_column` refers to the key of the merging struct.
+    /// The key column is mapped as last field [THROUGH_KEY](constant.THROUGH_KEY.html), so that path queries can filter and merge by it.
+    /// Example: `map.join_through("UserRole", "role_id", "id", "user_id")`
+    pub fn join_through<'a>(
+        &'a mut self,
+        link_table: &str,
+        link_column: &str,
+        toql_field: &str,
+        key_column: &str,
+    ) -> Result<&'a mut Self, SqlMapperError> {
+        let expression = self
+            .fields
+            .get(toql_field)
+            .ok_or(SqlMapperError::FieldMissing(toql_field.to_owned()))?
+            .expression
+            .clone();
+        self.join(
+            THROUGH_PATH,
+            &format!("JOIN {} toql_through ON (toql_through.{} = {})", link_table, link_column, expression),
+        );
+        self.map_field(THROUGH_KEY, &format!("toql_through.{}", key_column));
+        Ok(self)
+    }
+    /// Returns the table with optional alias, as given to [new()](struct.SqlMapper.html#method.new).
+    pub fn table(&self) -> &str {
+        &self.table
+    }
+    /// Returns the table alias or the table name, if the mapper has no alias.
+    pub fn table_alias(&self) -> &str {
+        Self::table_alias_of(&self.table)
+    }
+    /// Returns the names of all mapped fields in the order of the select clause.
+    pub fn field_names(&self) -> Vec<&str> {
+        // Field order has no duplicates, see order_field()
+        self.field_order
+            .iter()
+            .filter(|f| self.fields.contains_key(*f))
+            .map(|f| f.as_str())
+            .collect()
+    }
+    /// Returns the column or SQL expression of a field.
+    pub fn field_expression(&self, toql_field: &str) -> Option<&str> {
+        self.fields.get(toql_field).map(|t| t.expression.as_str())
+    }
+    /// Returns the options of a field.
+    pub fn field_options(&self, toql_field: &str) -> Option<&MapperOptions> {
+        self.fields.get(toql_field).map(|t| &t.options)
+    }
+    /// Returns the paths of all joins, sorted by name.
+    pub fn join_paths(&self) -> Vec<&str> {
+        let mut paths: Vec<&str> = self.joins.keys().map(|p| p.as_str()).collect();
+        paths.sort();
+        paths
+    }
+    /// Returns the join clause for a path.
+    pub fn join_clause(&self, toql_path: &str) -> Option<&str> {
+        self.joins.get(toql_path).map(|j| j.join_clause.as_str())
+    }
+    /// Returns the role expression of the join for a path.
+    pub fn join_role_expr(&self, toql_path: &str) -> Option<&RoleExpr> {
+        self.joins.get(toql_path).and_then(|j| j.role_expr.as_ref())
+    }
+    /// Returns the paths of all merges, sorted by name.
+    pub fn merge_paths(&self) -> Vec<&str> {
+        let mut paths: Vec<&str> = self.merges.keys().map(|p| p.as_str()).collect();
+        paths.sort();
+        paths
+    }
+    /// Returns the name of the mapper that loads the merge for a path.
+    pub fn merge_mapper(&self, toql_path: &str) -> Option<&str> {
+        self.merges.get(toql_path).map(|m| m.merge_mapper.as_str())
+    }
+    /// Adds a merge for a given path to the mapper.
+    /// The merged collection is loaded with the mapper that is registered under `merge_mapper` in the [cache](type.SqlMapperCache.html).
+    /// Example: `map.merge("phones", "Phone")`
+    pub fn merge<'a>(&'a mut self, toql_path: &str, merge_mapper: &str) -> &'a mut Self {
+        self.merges.insert(
+            toql_path.to_string(),
+            Merge {
+                merge_mapper: merge_mapper.to_string(),
+                role_expr: None,
+            },
+        );
+        self.changed();
+        self
+    }
+    /// Adds a merge that is only loaded for queries whose roles satisfy the role expression.
+    /// For other queries the collection stays empty.
+    pub fn merge_with_roles<'a>(&'a mut self, toql_path: &str, merge_mapper: &str, role_expr: RoleExpr) -> &'a mut Self {
+        self.merges.insert(
+            toql_path.to_string(),
+            Merge {
+                merge_mapper: merge_mapper.to_string(),
+                role_expr: Some(role_expr),
+            },
+        );
+        self.changed();
         self
     }
+    /// Returns the role expression of the merge for a path.
+    pub fn merge_role_expr(&self, toql_path: &str) -> Option<&RoleExpr> {
+        self.merges.get(toql_path).and_then(|m| m.role_expr.as_ref())
+    }
+    /// Returns true, if the roles allow to load the merge for a path.
+    /// Paths without merge are always valid.
+    pub fn merge_roles_valid(&self, toql_path: &str, roles: &BTreeSet<String>) -> bool {
+        self.merge_role_expr(toql_path).is_none_or(|e| e.is_valid(roles))
+    }
+
+    /// Sets the order for queries that have no order. The ordered fields of the query are used, 
+    /// other fields and filters are ignored. Without default order the database may return rows in any order,
+    /// which breaks pagination.
+    /// Example: `map.default_order(&QueryParser::parse("+1name, -2id")?)`
+    pub fn default_order<'a>(&'a mut self, order: &Query) -> &'a mut Self {
+        self.default_order = order
+            .tokens
+            .iter()
+            .filter_map(|t| match t {
+                QueryToken::Field(field) => field.order.as_ref().map(|o| (o.clone(), field.name.to_owned())),
+                _ => None,
+            })
+            .collect();
+        self.changed();
+        self
+    }
+    /// Returns the fields of the default order with their direction, `true` for ascending.
+    pub fn default_order_fields(&self) -> Vec<(&str, bool)> {
+        self.default_order
+            .iter()
+            .map(|(o, f)| (f.as_str(), match o { FieldOrder::Asc(_) => true, FieldOrder::Desc(_) => false }))
+            .collect()
+    }
+    /// Adds a named selection. Queries select all its fields with `$name`.
+    /// Example: `map.selection("summary", &["id", "title"])` for the query `$summary`
+    pub fn selection<'a>(&'a mut self, name: &str, toql_fields: &[&str]) -> &'a mut Self {
+        self.selections.insert(
+            name.trim_start_matches('$').to_string(),
+            toql_fields.iter().map(|f| f.to_string()).collect(),
+        );
+        self.changed();
+        self
+    }
+    /// Returns the names of all selections, sorted by name.
+    pub fn selection_names(&self) -> Vec<&str> {
+        let mut names: Vec<&str> = self.selections.keys().map(|n| n.as_str()).collect();
+        names.sort();
+        names
+    }
+    /// Returns the fields of a selection.
+    pub fn selection_fields(&self, name: &str) -> Option<Vec<&str>> {
+        self.selections.get(name).map(|f| f.iter().map(|f| f.as_str()).collect())
+    }
+    /// Adds a predicate that restricts the rows of all queries against this mapper.
+    /// The predicate is only applied to queries whose roles satisfy the role expression, or always if there is none.
+    /// Example: Non-admins only see published books `map.add_predicate("b.published = ?", vec![String::from("1")], Some(RoleExpr::parse("!admin")?))`
+    pub fn add_predicate<'a>(
+        &'a mut self,
+        sql: &str,
+        params: Vec<String>,
+        role_expr: Option<RoleExpr>,
+    ) -> &'a mut Self {
+        self.add_row_filter(Predicate {
+            sql: sql.to_string(),
+            params,
+            role_expr,
+        })
+    }
+    /// Adds a row filter that restricts the rows of all queries against this mapper.
+    /// Use it if the predicate depends on context values of the query, such as the tenant.
+    pub fn add_row_filter<F>(&mut self, row_filter: F) -> &mut Self
+    where
+        F: 'static + RowFilter + Send + Sync,
+    {
+        self.row_filters.push(Arc::new(row_filter));
+        self.changed();
+        self
+    }
+    /// Adds a function for `FN` filters, e.g. `LN` for `title FN LN 5`. Function names are case insensitive.
+    /// The SQL Builder rejects calls with less than `min_args` or more than `max_args` arguments.
+    /// A field handler that builds the `FN` filter itself takes precedence over the function.
+    pub fn map_function<'a, F>(&'a mut self, name: &str, min_args: usize, max_args: usize, function: F) -> &'a mut Self
+    where
+        F: 'static + FilterFunction + Send + Sync,
+    {
+        self.functions.insert(
+            name.to_uppercase(),
+            Function {
+                function: Arc::new(function),
+                min_args,
+                max_args,
+            },
+        );
+        self.changed();
+        self
+    }
+    /// Maps a tree, where each row refers to its parent row with `parent_column`.
+    /// Adds the `FN` filters `DESCENDANTS` and `ANCESTORS`. They take one or more keys and filter
+    /// all rows below or above them, using a recursive common table expression (`WITH RECURSIVE`).
+    /// The rows of the given keys are not included. `max_depth` limits the levels, with 1 only children or parents are found.
+    /// Without `max_depth` all levels are found and cycles stop, once they reach a row of their branch again.
+    /// Then keys must not contain commas.
+    /// Columns must be given without table alias. A mapper can map only one tree.
+    /// Example: `mapper.map_recursive("id", "parent_id", None)`, then query `id FN DESCENDANTS 5`.
+    pub fn map_recursive(&mut self, key_column: &str, parent_column: &str, max_depth: Option<u32>) -> &mut Self {
+        // Table without alias
+        let table = self.table.split_whitespace().next().unwrap_or_default().to_owned();
+        // Without maximum depth the keys of a branch are tracked to stop at cycles
+        let (columns, descendant_path, ancestor_path, descendant_stop, ancestor_stop) = match max_depth {
+            Some(d) => (
+                String::from(""),
+                String::from(""),
+                String::from(""),
+                format!(" WHERE toql_tree.toql_depth < {}", d),
+                format!(" AND toql_tree.toql_depth < {}", d),
+            ),
+            None => (
+                String::from(", toql_path"),
+                format!(", CAST({} AS CHAR(4000))", key_column),
+                format!(", CAST({} AS CHAR(4000))", parent_column),
+                format!(" WHERE FIND_IN_SET(toql_node.{}, toql_tree.toql_path) = 0", key_column),
+                format!(" AND FIND_IN_SET(toql_node.{}, toql_tree.toql_path) = 0", parent_column),
+            ),
+        };
+        let (descendant_step, ancestor_step) = match max_depth {
+            Some(_) => (String::from(""), String::from("")),
+            None => (
+                format!(", CONCAT(toql_tree.toql_path, ',', toql_node.{})", key_column),
+                format!(", CONCAT(toql_tree.toql_path, ',', toql_node.{})", parent_column),
+            ),
+        };
+
+        let descendants = format!(
+            "IN (WITH RECURSIVE toql_tree (toql_key, toql_depth{columns}) AS \
+             (SELECT {key}, 1{path} FROM {table} WHERE {parent} IN ({{}}) \
+             UNION ALL SELECT toql_node.{key}, toql_tree.toql_depth + 1{step} FROM {table} toql_node \
+             JOIN toql_tree ON (toql_node.{parent} = toql_tree.toql_key){stop}) \
+             SELECT toql_key FROM toql_tree)",
+            key = key_column,
+            parent = parent_column,
+            table = table,
+            columns = columns,
+            path = descendant_path,
+            step = descendant_step,
+            stop = descendant_stop
+        );
+        let ancestors = format!(
+            "IN (WITH RECURSIVE toql_tree (toql_key, toql_depth{columns}) AS \
+             (SELECT {parent}, 1{path} FROM {table} WHERE {key} IN ({{}}) AND {parent} IS NOT NULL \
+             UNION ALL SELECT toql_node.{parent}, toql_tree.toql_depth + 1{step} FROM {table} toql_node \
+             JOIN toql_tree ON (toql_node.{key} = toql_tree.toql_key) WHERE toql_node.{parent} IS NOT NULL{stop}) \
+             SELECT toql_key FROM toql_tree)",
+            key = key_column,
+            parent = parent_column,
+            table = table,
+            columns = columns,
+            path = ancestor_path,
+            step = ancestor_step,
+            stop = ancestor_stop
+        );
+
+        for (name, subquery) in [("DESCENDANTS", descendants), ("ANCESTORS", ancestors)] {
+            self.map_function(name, 1, usize::MAX, move |expression: &str, args: &[String]| {
+                let placeholders = vec!["?"; args.len()].join(",");
+                Ok((
+                    format!("{} {}", expression, subquery.replacen("{}", &placeholders, 1)),
+                    args.to_vec(),
+                ))
+            });
+        }
+        self
+    }
+    /// Returns the names of all `FN` filter functions, sorted.
+    pub fn function_names(&self) -> Vec<&str> {
+        let mut names: Vec<&str> = self.functions.keys().map(|n| n.as_str()).collect();
+        names.sort();
+        names
+    }
+
+    /// Checks the mapper for inconsistencies that would otherwise only show up as broken SQL.
+    /// Returns all problems found:
+    ///  - Field paths without registered join.
+    ///  - Joins that are not used by any field.
+    ///  - Fields that appear more than once in the field order, typically after re-mapping a field.
+    ///  - Join clauses that reference unknown aliases.
+    ///
+    /// Merges are only checked by [validate_cache()](fn.validate_cache.html), because they need other mappers.
+    pub fn validate(&self) -> Result<(), Vec<SqlMapperError>> {
+        let mut errors = Vec::new();
+
+        // Duplicates in field order
+        let mut seen: BTreeSet<&str> = BTreeSet::new();
+        let mut reported: BTreeSet<&str> = BTreeSet::new();
+        for toql_field in &self.field_order {
+            if !seen.insert(toql_field.as_str()) && reported.insert(toql_field.as_str()) {
+                errors.push(SqlMapperError::FieldDuplicate(toql_field.to_owned()));
+            }
+        }
+
+        // Paths of fields must be joined
+        let mut used_paths: BTreeSet<&str> = BTreeSet::new();
+        for toql_field in &self.field_order {
+            if let Some(sql_target) = self.fields.get(toql_field) {
+                if sql_target.subfields {
+                    for path in toql_field.split('_').rev().skip(1) {
+                        if used_paths.insert(path) && !self.joins.contains_key(path) {
+                            errors.push(SqlMapperError::JoinMissing(path.to_owned()));
+                        }
+                    }
+                }
+            }
+        }
+
+        // Joins must be used and reference known aliases only
+        let mut join_paths: Vec<&String> = self.joins.keys().collect();
+        join_paths.sort();
+
+        let mut known_aliases: BTreeSet<String> = BTreeSet::new();
+        known_aliases.insert(Self::table_alias_of(&self.table).to_owned());
+        if let Some(table_name) = self.table.split_whitespace().next() {
+            known_aliases.insert(table_name.to_owned());
+        }
+        for path in &join_paths {
+            let join = self.joins.get(*path).unwrap();
+            for (table_name, alias) in Self::join_aliases(&join.join_clause) {
+                known_aliases.insert(table_name);
+                known_aliases.insert(alias);
+            }
+        }
+
+        for path in join_paths {
+            if !used_paths.contains(path.as_str()) {
+                errors.push(SqlMapperError::JoinUnused(path.to_owned()));
+            }
+            let join = self.joins.get(path).unwrap();
+            for alias in Self::referenced_aliases(&join.join_clause) {
+                if !known_aliases.contains(&alias) {
+                    errors.push(SqlMapperError::AliasUnknown(path.to_owned(), alias));
+                }
+            }
+        }
+
+        if errors.is_empty() {
+            Ok(())
+        } else {
+            Err(errors)
+        }
+    }
+
+    // Returns the alias of a table: `Book b` -> `b`, `Book AS b` -> `b` and `Book` -> `Book`
+    fn table_alias_of(table: &str) -> &str {
+        table.split_whitespace().last().unwrap_or("")
+    }
+
+    // Returns tuples with table name and alias for all tables in a join clause.
+    fn join_aliases(join_clause: &str) -> Vec<(String, String)> {
+        let mut aliases = Vec::new();
+        let words: Vec<&str> = join_clause
+            .split(|c: char| c.is_whitespace() || c == '(' || c == ')' || c == ',')
+            .filter(|w| !w.is_empty())
+            .collect();
+        let mut i = 0;
+        while i < words.len() {
+            if words[i].eq_ignore_ascii_case("JOIN") && i + 1 < words.len() {
+                let table_name = words[i + 1].trim_matches('`');
+                let table_name = table_name.rsplit('.').next().unwrap_or(table_name);
+                let mut j = i + 2;
+                if j < words.len() && words[j].eq_ignore_ascii_case("AS") {
+                    j += 1;
+                }
+                let alias = match words.get(j) {
+                    Some(w) if !w.eq_ignore_ascii_case("ON") && !w.eq_ignore_ascii_case("USING") => {
+                        w.trim_matches('`')
+                    }
+                    _ => table_name,
+                };
+                aliases.push((table_name.to_owned(), alias.to_owned()));
+                i = j;
+            } else {
+                i += 1;
+            }
+        }
+        aliases
+    }
+
+    // Returns the aliases of all qualified columns (`alias.column`) in a join clause.
+    // Joined table names and string literals are skipped.
+    fn referenced_aliases(join_clause: &str) -> Vec<String> {
+        let mut aliases = Vec::new();
+        let mut words: Vec<String> = Vec::new();
+        let mut word = String::new();
+        let mut quote: Option<char> = None;
+
+        for c in join_clause.chars() {
+            match quote {
+                Some(q) => {
+                    if c == q {
+                        quote = None;
+                    }
+                }
+                None => {
+                    if c == '\'' || c == '"' {
+                        quote = Some(c);
+                    } else if c.is_alphanumeric() || c == '_' || c == '.' || c == '`' || c == '$' {
+                        word.push(c);
+                        continue;
+                    }
+                    if !word.is_empty() {
+                        words.push(word.clone());
+                        word.clear();
+                    }
+                }
+            }
+        }
+        if !word.is_empty() {
+            words.push(word);
+        }
+
+        let mut after_join = false;
+        for w in &words {
+            let joined_table = after_join;
+            after_join = w.eq_ignore_ascii_case("JOIN");
+            if joined_table {
+                continue;
+            }
+            let starts_alphabetic = w
+                .chars()
+                .next()
+                .is_some_and(|c| c.is_alphabetic() || c == '_' || c == '`');
+            if starts_alphabetic {
+                if let Some(p) = w.find('.') {
+                    let alias = w[..p].trim_matches('`').to_owned();
+                    if !aliases.contains(&alias) {
+                        aliases.push(alias);
+                    }
+                }
+            }
+        }
+        aliases
+    }
 }
@@ -0,0 +1,497 @@
+//!
+//! The SQL Mapper Config builds [SQL Mappers](../sql_mapper/struct.SqlMapper.html) from TOML or JSON documents.
+//!
+//! Use it for tables that are only known at deploy time and can therefore not be mapped with the Toql derive.
+//! This module is only available, if you have the _config_ feature enabled in your Toql dependency.
+//!
+//! ## Example
+//! ``` toml
+//! [functions.LN]             # FN filter for all mappers, `{}` is replaced by the field expression
+//! sql = "LENGTH({}) = ?"
+//! min_args = 1
+//!
+//! [mappers.Report]
+//! table = "ReportView r"
+//! order = "+name"
+//! selections = { std = ["id", "name"] }
+//!
+//! [[mappers.Report.fields]]
+//! name = "id"
+//! sql = "r.id"
+//! value_type = "integer"
+//! select_always = true
+//!
+//! [[mappers.Report.fields]]
+//! name = "customer_name"
+//! sql = "c.name"
+//! roles = "admin;sales"
+//! filters = ["EQ", "LK"]
+//!
+//! [[mappers.Report.joins]]
+//! path = "customer"
+//! sql = "LEFT JOIN Customer c ON (r.customer_id = c.id)"
+//! ```
+//!
+//! Load it with [cache_from_toml()](fn.cache_from_toml.html). Syntax errors contain the position in the document,
+//! mapping errors the key path of the offending value.
+//!
+
+use crate::query_parser::QueryParser;
+use crate::role_expr::RoleExpr;
+use crate::sql_builder::SqlBuilderError;
+use crate::sql_mapper::MapperOptions;
+use crate::sql_mapper::SqlMapper;
+use crate::sql_mapper::SqlMapperCache;
+use crate::sql_mapper::SqlMapperError;
+use crate::sql_mapper::ValueType;
+use serde::Deserialize;
+use std::collections::BTreeMap;
+use std::fmt;
+
+/// Line and column in a config document, both starting at 1.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct ConfigPosition {
+    pub line: usize,
+    pub column: usize,
+}
+
+impl fmt::Display for ConfigPosition {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "line {}, column {}", self.line, self.column)
+    }
+}
+
+/// Represents all errors from loading a config document.
+#[derive(Debug, PartialEq)]
+pub enum SqlMapperConfigError {
+    /// The document is no valid TOML or JSON or contains unknown keys. Contains message and position.
+    Syntax(String, Option<ConfigPosition>),
+    /// The document describes an invalid mapping. Contains mapper name, message and key path of the offending value,
+    /// such as `mappers.Report.fields.price.roles`.
+    Mapping(String, String, String),
+    /// The document contains several errors. Contains all of them in document order.
+    Multiple(Vec<SqlMapperConfigError>),
+}
+
+impl fmt::Display for SqlMapperConfigError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            SqlMapperConfigError::Syntax(ref m, Some(ref p)) => write!(f, "{} at {}", m, p),
+            SqlMapperConfigError::Syntax(ref m, None) => write!(f, "{}", m),
+            SqlMapperConfigError::Mapping(ref n, ref m, ref p) => {
+                if !n.is_empty() {
+                    write!(f, "mapper `{}`: ", n)?;
+                }
+                if p.is_empty() {
+                    write!(f, "{}", m)
+                } else {
+                    write!(f, "{} at `{}`", m, p)
+                }
+            }
+            SqlMapperConfigError::Multiple(ref errors) => {
+                for (i, e) in errors.iter().enumerate() {
+                    if i > 0 {
+                        writeln!(f)?;
+                    }
+                    write!(f, "{}", e)?;
+                }
+                Ok(())
+            }
+        }
+    }
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(deny_unknown_fields)]
+struct CacheConfig {
+    #[serde(default)]
+    functions: BTreeMap<String, FunctionConfig>,
+    #[serde(default)]
+    mappers: BTreeMap<String, MapperConfig>,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(deny_unknown_fields)]
+struct MapperConfig {
+    table: String,
+    #[serde(default)]
+    fields: Vec<FieldConfig>,
+    #[serde(default)]
+    joins: Vec<JoinConfig>,
+    #[serde(default)]
+    merges: Vec<MergeConfig>,
+    #[serde(default)]
+    predicates: Vec<PredicateConfig>,
+    #[serde(default)]
+    functions: BTreeMap<String, FunctionConfig>,
+    #[serde(default)]
+    selections: BTreeMap<String, Vec<String>>,
+    order: Option<String>,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(deny_unknown_fields)]
+struct FieldConfig {
+    name: String,
+    sql: String,
+    #[serde(default)]
+    select_always: bool,
+    #[serde(default)]
+    count_filter: bool,
+    #[serde(default)]
+    count_select: bool,
+    #[serde(default)]
+    ignore_wildcard: bool,
+    #[serde(default = "default_true")]
+    sortable: bool,
+    #[serde(default)]
+    aggregate: bool,
+    #[serde(default)]
+    like_prefix: usize,
+    value_type: Option<String>,
+    filters: Option<Vec<String>>,
+    roles: Option<String>,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(deny_unknown_fields)]
+struct JoinConfig {
+    path: String,
+    sql: String,
+    roles: Option<String>,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(deny_unknown_fields)]
+struct MergeConfig {
+    path: String,
+    mapper: String,
+    roles: Option<String>,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(deny_unknown_fields)]
+struct PredicateConfig {
+    sql: String,
+    #[serde(default)]
+    params: Vec<String>,
+    roles: Option<String>,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+#[serde(deny_unknown_fields)]
+struct FunctionConfig {
+    sql: String,
+    #[serde(default)]
+    min_args: usize,
+    max_args: Option<usize>,
+}
+
+fn default_true() -> bool {
+    true
+}
+
+const FILTERS: [&str; 14] = [
+    "EQ", "EQN", "NE", "NEN", "GT", "GE", "LT", "LE", "BW", "LK", "IN", "OUT", "RE", "FN",
+];
+
+// Builds mappers and reports errors at the key path of the offending value
+struct ConfigLoader<'a> {
+    mapper_name: &'a str,
+    config: &'a MapperConfig,
+    path: Vec<&'a str>, // Key path of the mapper in the document
+}
+
+impl<'a> ConfigLoader<'a> {
+    fn new(mapper_name: &'a str, config: &'a MapperConfig) -> Self {
+        let path = if mapper_name.is_empty() {
+            Vec::new()
+        } else {
+            vec!["mappers", mapper_name]
+        };
+        ConfigLoader {
+            mapper_name,
+            config,
+            path,
+        }
+    }
+
+    // Key path of a value in the current mapper, such as `mappers.Report.fields.price.roles`
+    fn key_path(&self, keys: &[&str]) -> String {
+        self.path.iter().chain(keys.iter()).cloned().collect::<Vec<&str>>().join(".")
+    }
+
+    fn error(&self, message: String, keys: &[&str]) -> SqlMapperConfigError {
+        SqlMapperConfigError::Mapping(self.mapper_name.to_owned(), message, self.key_path(keys))
+    }
+
+    fn role_expr(
+        &self,
+        roles: &Option<String>,
+        keys: &[&str],
+    ) -> Result<Option<RoleExpr>, SqlMapperConfigError> {
+        match roles {
+            Some(r) => RoleExpr::parse(r).map(Some).map_err(|e| {
+                let keys: Vec<&str> = keys.iter().cloned().chain(Some("roles")).collect();
+                self.error(format!("invalid role expression `{}`: {}", r, e), &keys)
+            }),
+            None => Ok(None),
+        }
+    }
+
+    fn value_type(&self, value_type: &str, field: &FieldConfig) -> Result<ValueType, SqlMapperConfigError> {
+        match value_type.to_lowercase().as_str() {
+            "any" => Ok(ValueType::Any),
+            "boolean" => Ok(ValueType::Boolean),
+            "integer" => Ok(ValueType::Integer),
+            "float" => Ok(ValueType::Float),
+            "text" => Ok(ValueType::Text),
+            _ => Err(self.error(
+                format!("unknown value type `{}`", value_type),
+                &["fields", &field.name, "value_type"],
+            )),
+        }
+    }
+
+    fn options(&self, field: &FieldConfig) -> Result<MapperOptions, SqlMapperConfigError> {
+        let mut options = MapperOptions::new()
+            .select_always(field.select_always)
+            .count_filter(field.count_filter)
+            .count_select(field.count_select)
+            .ignore_wildcard(field.ignore_wildcard)
+            .sortable(field.sortable)
+            .aggregate(field.aggregate)
+            .min_like_prefix(field.like_prefix);
+        if let Some(v) = &field.value_type {
+            options = options.value_type(self.value_type(v, field)?);
+        }
+        if let Some(filters) = &field.filters {
+            for f in filters {
+                if !FILTERS.contains(&f.to_uppercase().as_str()) {
+                    return Err(self.error(
+                        format!("unknown filter `{}` on field `{}`", f, field.name),
+                        &["fields", &field.name, "filters"],
+                    ));
+                }
+            }
+            options = options.restrict_filters(filters.iter().cloned().collect());
+        }
+        if let Some(e) = self.role_expr(&field.roles, &["fields", &field.name])? {
+            options = options.restrict_role_expr(e);
+        }
+        Ok(options)
+    }
+
+    // Functions of the document are located at the top, not in the mapper
+    fn map_function(
+        &self,
+        mapper: &mut SqlMapper,
+        name: &str,
+        function: &FunctionConfig,
+        mapper_function: bool,
+    ) -> Result<(), SqlMapperConfigError> {
+        let max_args = function.max_args.unwrap_or(function.min_args);
+        if max_args < function.min_args {
+            let message = format!("function `{}` has more min_args than max_args", name);
+            let keys = ["functions", name, "max_args"];
+            return Err(if mapper_function {
+                self.error(message, &keys)
+            } else {
+                SqlMapperConfigError::Mapping(self.mapper_name.to_owned(), message, keys.join("."))
+            });
+        }
+        let sql = function.sql.to_owned();
+        mapper.map_function(
+            name,
+            function.min_args,
+            max_args,
+            move |expression: &str, args: &[String]| -> Result<(String, Vec<String>), SqlBuilderError> {
+                Ok((sql.replace("{}", expression), args.to_vec()))
+            },
+        );
+        Ok(())
+    }
+
+    fn build(&self, functions: &'a BTreeMap<String, FunctionConfig>) -> Result<SqlMapper, SqlMapperConfigError> {
+        let config = self.config;
+        let mut mapper = SqlMapper::new(config.table.as_str());
+
+        for join in &config.joins {
+            match self.role_expr(&join.roles, &["joins", &join.path])? {
+                Some(e) => mapper.join_with_roles(&join.path, &join.sql, e),
+                None => mapper.join(&join.path, &join.sql),
+            };
+        }
+        for field in &config.fields {
+            let options = self.options(field)?;
+            mapper.map_field_with_options(&field.name, &field.sql, options);
+        }
+        for merge in &config.merges {
+            match self.role_expr(&merge.roles, &["merges", &merge.path])? {
+                Some(e) => mapper.merge_with_roles(&merge.path, &merge.mapper, e),
+                None => mapper.merge(&merge.path, &merge.mapper),
+            };
+        }
+        for (i, predicate) in config.predicates.iter().enumerate() {
+            let role_expr = self.role_expr(&predicate.roles, &["predicates", &i.to_string()])?;
+            mapper.add_predicate(&predicate.sql, predicate.params.clone(), role_expr);
+        }
+        // Functions of the mapper override document functions
+        for (name, function) in functions {
+            self.map_function(&mut mapper, name, function, false)?;
+        }
+        for (name, function) in &config.functions {
+            self.map_function(&mut mapper, name, function, true)?;
+        }
+        for (name, fields) in &config.selections {
+            for f in fields {
+                if mapper.field_expression(f).is_none() {
+                    return Err(self.error(
+                        format!("selection `{}` contains unknown field `{}`", name, f),
+                        &["selections", name],
+                    ));
+                }
+            }
+            let fields: Vec<&str> = fields.iter().map(|f| f.as_str()).collect();
+            mapper.selection(name, &fields);
+        }
+        if let Some(order) = &config.order {
+            let query = QueryParser::parse(order)
+                .map_err(|_| self.error(format!("invalid order `{}`", order), &["order"]))?;
+            mapper.default_order(&query);
+        }
+
+        if let Err(errors) = mapper.validate() {
+            return Err(self.errors(errors));
+        }
+        Ok(mapper)
+    }
+
+    // Reports all mapper errors at the value that causes them
+    fn errors(&self, errors: Vec<SqlMapperError>) -> SqlMapperConfigError {
+        let errors = errors
+            .iter()
+            .map(|e| self.error(e.to_string(), &self.error_keys(e)))
+            .collect();
+        SqlMapperConfigError::from_errors(errors)
+    }
+
+    fn error_keys(&self, error: &SqlMapperError) -> Vec<&'a str> {
+        let config = self.config;
+        let field = |matches: &dyn Fn(&str) -> bool| {
+            config
+                .fields
+                .iter()
+                .rfind(|f| matches(&f.name))
+                .map(|f| vec!["fields", f.name.as_str()])
+        };
+        let join = |path: &str, key: Option<&'static str>| {
+            config
+                .joins
+                .iter()
+                .find(|j| j.path == path)
+                .map(|j| ["joins", j.path.as_str()].iter().cloned().chain(key).collect())
+        };
+        let keys = match error {
+            SqlMapperError::FieldMissing(s) | SqlMapperError::FieldDuplicate(s) => field(&|n| n == s),
+            SqlMapperError::JoinMissing(p) => field(&|n| n.split('_').rev().skip(1).any(|s| s == p)),
+            SqlMapperError::JoinUnused(p) => join(p, None),
+            SqlMapperError::AliasUnknown(p, _) => join(p, Some("sql")),
+            SqlMapperError::MergeMapperMissing(p, _) => config
+                .merges
+                .iter()
+                .find(|m| &m.path == p)
+                .map(|m| vec!["merges", m.path.as_str(), "mapper"]),
+        };
+        keys.unwrap_or_default()
+    }
+}
+
+impl SqlMapperConfigError {
+    // Returns a single error unwrapped
+    fn from_errors(mut errors: Vec<SqlMapperConfigError>) -> Self {
+        if errors.len() == 1 {
+            errors.pop().unwrap()
+        } else {
+            SqlMapperConfigError::Multiple(errors)
+        }
+    }
+
+    fn into_errors(self) -> Vec<SqlMapperConfigError> {
+        match self {
+            SqlMapperConfigError::Multiple(errors) => errors,
+            e => vec![e],
+        }
+    }
+}
+
+fn build_cache(config: CacheConfig) -> Result<SqlMapperCache, SqlMapperConfigError> {
+    let mut cache = SqlMapperCache::new();
+    let mut errors = Vec::new();
+    for (name, mapper_config) in &config.mappers {
+        match ConfigLoader::new(name, mapper_config).build(&config.functions) {
+            Ok(mapper) => {
+                cache.insert(name.to_owned(), mapper);
+            }
+            Err(e) => errors.extend(e.into_errors()),
+        }
+    }
+    if errors.is_empty() {
+        if let Err(cache_errors) = cache.validate() {
+            for (name, e) in cache_errors {
+                let loader = ConfigLoader::new(&name, &config.mappers[&name]);
+                errors.extend(loader.errors(vec![e]).into_errors());
+            }
+        }
+    }
+    if errors.is_empty() {
+        Ok(cache)
+    } else {
+        Err(SqlMapperConfigError::from_errors(errors))
+    }
+}
+
+fn toml_error(e: toml::de::Error) -> SqlMapperConfigError {
+    let position = e.line_col().map(|(l, c)| ConfigPosition {
+        line: l + 1,
+        column: c + 1,
+    });
+    SqlMapperConfigError::Syntax(e.to_string(), position)
+}
+
+fn json_error(e: serde_json::Error) -> SqlMapperConfigError {
+    let position = if e.line() > 0 {
+        Some(ConfigPosition {
+            line: e.line(),
+            column: e.column(),
+        })
+    } else {
+        None
+    };
+    SqlMapperConfigError::Syntax(e.to_string(), position)
+}
+
+/// Builds a cache with all mappers from a TOML document.
+/// Merges must refer to mappers in the same document.
+pub fn cache_from_toml(source: &str) -> Result<SqlMapperCache, SqlMapperConfigError> {
+    let config: CacheConfig = toml::from_str(source).map_err(toml_error)?;
+    build_cache(config)
+}
+
+/// Builds a cache with all mappers from a JSON document. See [cache_from_toml()](fn.cache_from_toml.html).
+pub fn cache_from_json(source: &str) -> Result<SqlMapperCache, SqlMapperConfigError> {
+    let config: CacheConfig = serde_json::from_str(source).map_err(json_error)?;
+    build_cache(config)
+}
+
+/// Builds a single mapper from a TOML document. The document contains the keys of one mapper, such as `table` and `fields`.
+pub fn mapper_from_toml(source: &str) -> Result<SqlMapper, SqlMapperConfigError> {
+    let config: MapperConfig = toml::from_str(source).map_err(toml_error)?;
+    ConfigLoader::new("", &config).build(&BTreeMap::new())
+}
+
+/// Builds a single mapper from a JSON document. See [mapper_from_toml()](fn.mapper_from_toml.html).
+pub fn mapper_from_json(source: &str) -> Result<SqlMapper, SqlMapperConfigError> {
+    let config: MapperConfig = serde_json::from_str(source).map_err(json_error)?;
+    ConfigLoader::new("", &config).build(&BTreeMap::new())
+}
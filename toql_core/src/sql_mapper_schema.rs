@@ -0,0 +1,159 @@
+//!
+//! The SQL Mapper Schema describes the Toql fields of a [SQL Mapper](../sql_mapper/struct.SqlMapper.html) for frontends.
+//!
+//! It contains field names, options, roles and paths but no SQL. A grid UI can use it to build its query UI automatically.
+//! With the feature `json` the schema can be exported as JSON.
+//!
+//! ## Example
+//! ``` ignore
+//! let schema = MapperSchema::from_mapper(&mapper);
+//! let json = schema.to_json();
+//! ```
+//!
+//! To export all mappers in a cache use [cache_schema()](fn.cache_schema.html).
+//!
+
+use crate::sql_mapper::SqlMapper;
+use crate::sql_mapper::SqlMapperCache;
+use crate::sql_mapper::ValueType;
+use std::collections::BTreeMap;
+
+#[cfg(feature = "json")]
+use serde::Serialize;
+
+/// Describes all Toql fields and paths of a mapper.
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "json", derive(Serialize))]
+pub struct MapperSchema {
+    /// Fields in the order of the select clause.
+    pub fields: Vec<FieldSchema>,
+    /// Paths that are joined.
+    pub joins: Vec<JoinSchema>,
+    /// Paths that are merged.
+    pub merges: Vec<MergeSchema>,
+    /// Named selections with their fields.
+    pub selections: BTreeMap<String, Vec<String>>,
+}
+
+/// Describes a Toql field.
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "json", derive(Serialize))]
+pub struct FieldSchema {
+    /// Full field name, e.g. `author_id`.
+    pub name: String,
+    /// Path of the field, e.g. `author`. Empty for top fields.
+    pub path: String,
+    /// Type of filter arguments.
+    pub value_type: ValueType,
+    /// Field can be filtered.
+    pub filterable: bool,
+    /// Allowed filter operations. All operations are allowed, if missing.
+    pub filters: Option<Vec<String>>,
+    /// Field can be ordered.
+    pub sortable: bool,
+    /// Field is always selected.
+    pub select_always: bool,
+    /// Field is ignored by wildcards.
+    pub ignore_wildcard: bool,
+    /// Field is an aggregate expression, filters go into the HAVING clause.
+    pub aggregate: bool,
+    /// Role expression required to access the field, e.g. `admin;owner`.
+    pub roles: Option<String>,
+}
+
+/// Describes a joined path.
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "json", derive(Serialize))]
+pub struct JoinSchema {
+    /// Path of the join, e.g. `author`.
+    pub path: String,
+    /// Role expression required to use the join, e.g. `admin;owner`.
+    pub roles: Option<String>,
+}
+
+/// Describes a merged path.
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "json", derive(Serialize))]
+pub struct MergeSchema {
+    /// Path of the merged collection, e.g. `phones`.
+    pub path: String,
+    /// Name of the mapper that loads the merged collection.
+    pub mapper: String,
+    /// Role expression required to load the merged collection.
+    pub roles: Option<String>,
+}
+
+impl MapperSchema {
+    /// Creates the schema for a mapper.
+    pub fn from_mapper(mapper: &SqlMapper) -> Self {
+        let fields = mapper
+            .field_names()
+            .into_iter()
+            .map(|name| {
+                let options = mapper.field_options(name).unwrap();
+                FieldSchema {
+                    name: name.to_owned(),
+                    path: name.rfind('_').map_or("", |i| &name[..i]).to_owned(),
+                    value_type: options.get_value_type(),
+                    filterable: options.filters().is_none_or(|f| !f.is_empty()),
+                    filters: options.filters().map(|f| f.iter().cloned().collect()),
+                    sortable: options.is_sortable(),
+                    select_always: options.is_select_always(),
+                    ignore_wildcard: options.is_ignore_wildcard(),
+                    aggregate: options.is_aggregate(),
+                    roles: options.role_expr().map(|e| e.to_string()),
+                }
+            })
+            .collect();
+
+        MapperSchema {
+            fields,
+            joins: mapper
+                .join_paths()
+                .iter()
+                .map(|p| JoinSchema {
+                    path: p.to_string(),
+                    roles: mapper.join_role_expr(p).map(|e| e.to_string()),
+                })
+                .collect(),
+            merges: mapper
+                .merge_paths()
+                .iter()
+                .map(|p| MergeSchema {
+                    path: p.to_string(),
+                    mapper: mapper.merge_mapper(p).unwrap().to_owned(),
+                    roles: mapper.merge_role_expr(p).map(|e| e.to_string()),
+                })
+                .collect(),
+            selections: mapper
+                .selection_names()
+                .iter()
+                .map(|n| {
+                    let fields = mapper.selection_fields(n).unwrap();
+                    (n.to_string(), fields.iter().map(|f| f.to_string()).collect())
+                })
+                .collect(),
+        }
+    }
+    /// Returns the schema as JSON.
+    /// This function is only available, if you have the _json_ feature enabled in your Toql dependency.
+    #[cfg(feature = "json")]
+    pub fn to_json(&self) -> String {
+        serde_json::to_string(self).expect("Schema must serialize")
+    }
+}
+
+/// Creates the schemas for all mappers in a cache. The schemas are keyed by mapper name.
+pub fn cache_schema(cache: &SqlMapperCache) -> BTreeMap<String, MapperSchema> {
+    cache
+        .iter()
+        .map(|(name, mapper)| (name.to_owned(), MapperSchema::from_mapper(mapper)))
+        .collect()
+}
+
+/// Returns the schemas for all mappers in a cache as JSON.
+/// This function is only available, if you have the _json_ feature enabled in your Toql dependency.
+#[cfg(feature = "json")]
+pub fn cache_schema_json(cache: &SqlMapperCache) -> String {
+    serde_json::to_string(&cache_schema(cache)).expect("Schema must serialize")
+}
@@ -0,0 +1,65 @@
+use toql_core::query_parser::QueryParser;
+use toql_core::sql_builder::SqlBuilder;
+use toql_core::sql_mapper::MapperOptions;
+use toql_core::sql_mapper::SqlMapper;
+
+fn setup_mapper() -> SqlMapper {
+    let mut mapper = SqlMapper::new("Order o");
+    mapper
+        .join("user", "JOIN User u ON (o.user_id = u.id)")
+        .map_field_with_options(
+            "userId",
+            "o.user_id",
+            MapperOptions::new().select_always(true).count_select(true),
+        )
+        .map_field_with_options(
+            "total",
+            "SUM(o.amount)",
+            MapperOptions::new().aggregate(true).count_filter(true),
+        )
+        .map_field("user_name", "u.name");
+    mapper
+}
+
+#[test]
+fn aggregate_filter() {
+    let mapper = setup_mapper();
+    let query = QueryParser::parse("total gt 100, user_name eq 'Foo'").unwrap();
+    let result = SqlBuilder::new().build(&mapper, &query).unwrap();
+
+    assert_eq!(
+        "SELECT o.user_id, SUM(o.amount), u.name FROM Order o JOIN User u ON (o.user_id = u.id) \
+         WHERE u.name = ? GROUP BY o.user_id, u.name HAVING SUM(o.amount) > ?",
+        result.to_sql()
+    );
+    assert_eq!(*result.params(), ["Foo", "100"]);
+}
+
+#[test]
+fn aggregate_select() {
+    let mapper = setup_mapper();
+    let query = QueryParser::parse("user_name").unwrap();
+    let result = SqlBuilder::new().build(&mapper, &query).unwrap();
+    assert_eq!(
+        "SELECT o.user_id, null, u.name FROM Order o JOIN User u ON (o.user_id = u.id)",
+        result.to_sql()
+    );
+
+    let query = QueryParser::parse("total").unwrap();
+    let result = SqlBuilder::new().build(&mapper, &query).unwrap();
+    assert_eq!(
+        "SELECT o.user_id, SUM(o.amount), null FROM Order o GROUP BY o.user_id",
+        result.to_sql()
+    );
+}
+
+#[test]
+fn aggregate_count() {
+    let mapper = setup_mapper();
+    let query = QueryParser::parse("total gt 100").unwrap();
+    let result = SqlBuilder::new().build_count(&mapper, &query).unwrap();
+    assert_eq!(
+        "SELECT o.user_id FROM Order o GROUP BY o.user_id HAVING SUM(o.amount) > ?",
+        result.to_sql()
+    );
+}
@@ -0,0 +1,109 @@
+use toql_core::query_parser::QueryParser;
+use toql_core::sql_builder::SqlBuilder;
+use toql_core::sql_builder::SqlBuilderError;
+use toql_core::sql_mapper::MapperOptions;
+use toql_core::sql_mapper::SqlMapper;
+use toql_core::sql_mapper::SqlMapperError;
+
+fn setup_mapper() -> SqlMapper {
+    let mut mapper = SqlMapper::new("Book b");
+    mapper
+        .join("author", "JOIN User a ON (b.author_id = a.id)")
+        .map_field_with_options("id", "b.id", MapperOptions::new().select_always(true))
+        .map_field("title", "b.title")
+        .map_field("author_id", "a.id")
+        .map_field("author_username", "a.username");
+    mapper
+}
+
+#[test]
+fn try_alter_missing() {
+    let mut mapper = setup_mapper();
+
+    assert_eq!(
+        SqlMapperError::FieldMissing(String::from("subtitle")),
+        mapper
+            .try_alter_field("subtitle", "b.subtitle", MapperOptions::new())
+            .err()
+            .unwrap()
+    );
+    assert_eq!(
+        SqlMapperError::JoinMissing(String::from("publisher")),
+        mapper
+            .try_alter_join("publisher", "JOIN Publisher p ON (b.publisher_id = p.id)")
+            .err()
+            .unwrap()
+    );
+    assert!(mapper
+        .try_alter_field("title", "b.long_title", MapperOptions::new())
+        .is_ok());
+}
+
+#[test]
+fn unmap_field() {
+    let mut mapper = setup_mapper();
+    mapper.unmap_field("title").unwrap();
+
+    let query = QueryParser::parse("*").unwrap();
+    let result = SqlBuilder::new().build(&mapper, &query).unwrap();
+    assert_eq!("SELECT b.id, null, null, null FROM Book b", result.to_sql());
+
+    let query = QueryParser::parse("title").unwrap();
+    match SqlBuilder::new().build(&mapper, &query) {
+        Err(SqlBuilderError::FieldMissing(f)) => assert_eq!("title", f),
+        _ => panic!("Field `title` must be missing"),
+    }
+
+    assert_eq!(
+        SqlMapperError::FieldMissing(String::from("title")),
+        mapper.unmap_field("title").err().unwrap()
+    );
+}
+
+#[test]
+fn unmap_and_map_field() {
+    let mut mapper = setup_mapper();
+    mapper.unmap_field("title").unwrap();
+    mapper.map_field("title", "b.long_title");
+
+    let query = QueryParser::parse("*").unwrap();
+    let result = SqlBuilder::new().build(&mapper, &query).unwrap();
+    assert_eq!("SELECT b.id, b.long_title, null, null FROM Book b", result.to_sql());
+    assert_eq!(Ok(()), mapper.validate());
+}
+
+#[test]
+fn remove_join() {
+    let mut mapper = setup_mapper();
+    mapper.remove_join("author").unwrap();
+
+    let query = QueryParser::parse("**").unwrap();
+    let result = SqlBuilder::new().build(&mapper, &query).unwrap();
+    assert_eq!("SELECT b.id, b.title, null, null FROM Book b", result.to_sql());
+    assert_eq!(Ok(()), mapper.validate());
+}
+
+#[test]
+fn remove_nested_join() {
+    let mut mapper = setup_mapper();
+    mapper
+        .join("publisher", "JOIN Publisher p ON (b.publisher_id = p.id)")
+        .join("address", "JOIN Address ad ON (a.address_id = ad.id)")
+        .join("country", "JOIN Country c ON (ad.country_id = c.id)")
+        .map_field("author_address_city", "ad.city")
+        .map_field("author_address_country_name", "c.name")
+        .map_field("publisher_name", "p.name");
+    assert_eq!(Ok(()), mapper.validate());
+
+    // Joins of `author_address` and `author_address_country` go with `author`
+    mapper.remove_join("author").unwrap();
+    assert_eq!(Ok(()), mapper.validate());
+    assert_eq!(vec!["publisher"], mapper.join_paths());
+
+    let query = QueryParser::parse("**").unwrap();
+    let result = SqlBuilder::new().build(&mapper, &query).unwrap();
+    assert_eq!(
+        "SELECT b.id, b.title, null, null, null, null, p.name FROM Book b JOIN Publisher p ON (b.publisher_id = p.id)",
+        result.to_sql()
+    );
+}
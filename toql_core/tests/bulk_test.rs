@@ -0,0 +1,126 @@
+use toql_core::query_parser::QueryParser;
+use toql_core::role_expr::RoleExpr;
+use toql_core::sql_builder::FieldPolicy;
+use toql_core::sql_builder::SqlBuilder;
+use toql_core::sql_builder::SqlBuilderError;
+use toql_core::sql_mapper::MapperOptions;
+use toql_core::sql_mapper::SqlMapper;
+use toql_core::sql_mapper::ValueType;
+
+fn setup_mapper() -> SqlMapper {
+    let mut mapper = SqlMapper::new("Book b");
+    mapper
+        .join("author", "JOIN User a ON (b.author_id = a.id)")
+        .map_field_with_options("id", "b.id", MapperOptions::new().select_always(true))
+        .map_field_with_options("active", "b.active", MapperOptions::new().value_type(ValueType::Boolean))
+        .map_field_with_options(
+            "price",
+            "b.price",
+            MapperOptions::new().restrict_role_expr(RoleExpr::role("admin")),
+        )
+        .map_field_with_options("author_name", "a.name", MapperOptions::new().select_always(true))
+        .add_predicate("b.tenant_id = ?", vec![String::from("7")], None);
+    mapper
+}
+
+#[test]
+fn build_delete() {
+    let mapper = setup_mapper();
+    let query = QueryParser::parse("**, id gt 5").unwrap();
+    let (sql, params) = SqlBuilder::new().build_delete(&mapper, &query).unwrap();
+    assert_eq!("DELETE b FROM Book b WHERE (b.id > ?) AND (b.tenant_id = ?)", sql);
+    assert_eq!(params, ["5", "7"]);
+
+    let query = QueryParser::parse("author_name eq 'Ann'").unwrap();
+    let (sql, _) = SqlBuilder::new().build_delete(&mapper, &query).unwrap();
+    assert_eq!(
+        "DELETE b FROM Book b JOIN User a ON (b.author_id = a.id) WHERE (a.name = ?) AND (b.tenant_id = ?)",
+        sql
+    );
+}
+
+#[test]
+fn build_update() {
+    let mapper = setup_mapper();
+    let query = QueryParser::parse("id in 1 2").unwrap();
+    let (sql, params) = SqlBuilder::new()
+        .build_update(&mapper, &query, &[("active", "false")])
+        .unwrap();
+    assert_eq!("UPDATE Book b SET b.active = ? WHERE (b.id IN (?,?)) AND (b.tenant_id = ?)", sql);
+    assert_eq!(params, ["0", "1", "2", "7"]);
+
+    match SqlBuilder::new().build_update(&mapper, &query, &[("price", "5")]) {
+        Err(SqlBuilderError::RoleRequired(_)) => {}
+        _ => panic!("Field `price` must require a role"),
+    }
+    match SqlBuilder::new().build_update(&mapper, &query, &[("author_name", "Bob")]) {
+        Err(SqlBuilderError::UpdateInvalid(f)) => assert_eq!("author_name", f),
+        _ => panic!("Field `author_name` must not be updatable"),
+    }
+}
+
+#[test]
+fn refuse_expression_update() {
+    let mut mapper = setup_mapper();
+    mapper
+        .map_field("title", "b.title")
+        .map_field("fullName", "CONCAT(b.first, b.last)");
+    let query = QueryParser::parse("id eq 1").unwrap();
+    let (sql, _) = SqlBuilder::new().build_update(&mapper, &query, &[("title", "Foo")]).unwrap();
+    assert_eq!("UPDATE Book b SET b.title = ? WHERE (b.id = ?) AND (b.tenant_id = ?)", sql);
+
+    match SqlBuilder::new().build_update(&mapper, &query, &[("fullName", "Foo")]) {
+        Err(SqlBuilderError::UpdateInvalid(f)) => assert_eq!("fullName", f),
+        _ => panic!("Field `fullName` must not be updatable"),
+    }
+
+    // Altered fields are checked again
+    mapper.alter_field("title", "UPPER(b.title)", MapperOptions::new());
+    match SqlBuilder::new().build_update(&mapper, &query, &[("title", "Foo")]) {
+        Err(SqlBuilderError::UpdateInvalid(f)) => assert_eq!("title", f),
+        _ => panic!("Field `title` must not be updatable"),
+    }
+}
+
+#[test]
+fn refuse_unfiltered() {
+    let mapper = setup_mapper();
+    let query = QueryParser::parse("id, author_name").unwrap();
+    match SqlBuilder::new().build_delete(&mapper, &query) {
+        Err(SqlBuilderError::FilterMissing) => {}
+        _ => panic!("Filter must be missing"),
+    }
+
+    let (sql, _) = SqlBuilder::new()
+        .allow_unfiltered(true)
+        .build_delete(&mapper, &query)
+        .unwrap();
+    assert_eq!("DELETE b FROM Book b WHERE (b.tenant_id = ?)", sql);
+}
+
+#[test]
+fn refuse_dropped_filters() {
+    let mapper = setup_mapper();
+    let mut builder = SqlBuilder::new()
+        .field_policy(FieldPolicy::Lenient)
+        .collect_errors(true);
+
+    // Dropping the filters would delete all rows of the tenant
+    let query = QueryParser::parse("id gt 5, price eq 7").unwrap();
+    match builder.build_delete(&mapper, &query) {
+        Err(SqlBuilderError::RoleRequired(_)) => {}
+        _ => panic!("Field `price` must require a role"),
+    }
+    let query = QueryParser::parse("id gt 5, ide eq 7").unwrap();
+    match builder.build_update(&mapper, &query, &[("active", "false")]) {
+        Err(SqlBuilderError::FieldMissing(f)) => assert_eq!("ide", f),
+        _ => panic!("Field `ide` must be unknown"),
+    }
+
+    // Queries stay lenient
+    let result = builder.build(&mapper, &query).unwrap();
+    assert_eq!(
+        "SELECT b.id, null, null, a.name FROM Book b JOIN User a ON (b.author_id = a.id) WHERE (b.id > ?) AND (b.tenant_id = ?)",
+        result.to_sql()
+    );
+}
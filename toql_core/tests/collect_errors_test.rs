@@ -0,0 +1,59 @@
+use std::collections::BTreeSet;
+use toql_core::query_parser::QueryParser;
+use toql_core::sql_builder::SqlBuilder;
+use toql_core::sql_builder::SqlBuilderError;
+use toql_core::sql_mapper::MapperOptions;
+use toql_core::sql_mapper::SqlMapper;
+
+fn setup_mapper() -> SqlMapper {
+    let mut mapper = SqlMapper::new("Book b");
+    mapper
+        .map_field("id", "b.id")
+        .map_field_with_options(
+            "title",
+            "b.title",
+            MapperOptions::new().restrict_filters(["LK"].iter().map(|f| f.to_string()).collect()),
+        )
+        .map_field_with_options(
+            "price",
+            "b.price",
+            MapperOptions::new().restrict_roles(["admin"].iter().map(|r| r.to_string()).collect::<BTreeSet<_>>()),
+        );
+    mapper
+}
+
+#[test]
+fn collect_errors() {
+    let mapper = setup_mapper();
+    let query = QueryParser::parse("id, nme, price, title eq 'a', $std").unwrap();
+
+    match SqlBuilder::new().collect_errors(true).build(&mapper, &query) {
+        Err(SqlBuilderError::Multiple(errors)) => {
+            let tokens: Vec<(Option<usize>, &str)> =
+                errors.iter().map(|e| (e.position, e.token.as_str())).collect();
+            assert_eq!(
+                tokens,
+                [(Some(4), "nme"), (Some(9), "price"), (Some(16), "title"), (Some(30), "$std")]
+            );
+            match errors[0].error {
+                SqlBuilderError::FieldMissing(ref f) => assert_eq!("nme", f),
+                _ => panic!("Field `nme` must be missing"),
+            }
+        }
+        _ => panic!("Query must have multiple errors"),
+    }
+
+    // Without collecting, the first error is returned
+    match SqlBuilder::new().build(&mapper, &query) {
+        Err(SqlBuilderError::FieldMissing(f)) => assert_eq!("nme", f),
+        _ => panic!("Field `nme` must be missing"),
+    }
+}
+
+#[test]
+fn collect_no_errors() {
+    let mapper = setup_mapper();
+    let query = QueryParser::parse("id, title lk 'a%'").unwrap();
+    let result = SqlBuilder::new().collect_errors(true).build(&mapper, &query).unwrap();
+    assert_eq!("SELECT b.id, b.title, null FROM Book b WHERE b.title LIKE ?", result.to_sql());
+}
@@ -0,0 +1,150 @@
+#![cfg(feature = "config")]
+use toql_core::query_parser::QueryParser;
+use toql_core::sql_builder::SqlBuilder;
+use toql_core::sql_mapper_config::cache_from_json;
+use toql_core::sql_mapper_config::cache_from_toml;
+use toql_core::sql_mapper_config::mapper_from_toml;
+use toql_core::sql_mapper_config::SqlMapperConfigError;
+
+const TOML: &str = r#"
+[functions.LN]
+sql = "LENGTH({}) = ?"
+min_args = 1
+
+[mappers.Report]
+table = "ReportView r"
+order = "-id"
+selections = { std = ["id", "customer_name"] }
+
+[[mappers.Report.fields]]
+name = "id"
+sql = "r.id"
+value_type = "integer"
+select_always = true
+count_filter = true
+
+[[mappers.Report.fields]]
+name = "customer_name"
+sql = "c.name"
+roles = "admin;sales"
+
+[[mappers.Report.joins]]
+path = "customer"
+sql = "LEFT JOIN Customer c ON (r.customer_id = c.id)"
+
+[[mappers.Report.merges]]
+path = "lines"
+mapper = "ReportLine"
+
+[mappers.ReportLine]
+table = "ReportLineView l"
+
+[[mappers.ReportLine.fields]]
+name = "id"
+sql = "l.id"
+"#;
+
+#[test]
+fn load_toml() {
+    let cache = cache_from_toml(TOML).unwrap();
+    assert_eq!(cache.keys(), ["Report", "ReportLine"]);
+
+    let mapper = cache.get("Report").unwrap();
+    let mut query = QueryParser::parse("$std, id FN LN 2").unwrap();
+    query.roles.insert(String::from("sales"));
+    let result = SqlBuilder::new().build(mapper, &query).unwrap();
+    assert_eq!(
+        "SELECT r.id, c.name FROM ReportView r LEFT JOIN Customer c ON (r.customer_id = c.id) WHERE LENGTH(r.id) = ? ORDER BY r.id DESC",
+        result.to_sql()
+    );
+
+    let result = SqlBuilder::new().build_count(mapper, &query).unwrap();
+    assert_eq!("SELECT 1 FROM ReportView r WHERE LENGTH(r.id) = ?", result.to_sql());
+}
+
+#[test]
+fn load_json() {
+    let json = r#"{
+        "mappers": {
+            "Report": {
+                "table": "ReportView r",
+                "fields": [ { "name": "id", "sql": "r.id", "filters": ["eq"] } ]
+            }
+        }
+    }"#;
+    let cache = cache_from_json(json).unwrap();
+    let query = QueryParser::parse("id eq 5").unwrap();
+    let result = SqlBuilder::new().build(cache.get("Report").unwrap(), &query).unwrap();
+    assert_eq!("SELECT r.id FROM ReportView r WHERE r.id = ?", result.to_sql());
+}
+
+#[test]
+fn report_key_paths() {
+    let toml = "table = \"Report r\"\n\n[[fields]]\nname = \"id\"\nsql = \"r.id\"\nroles = \"admin;;x\"\n";
+    match mapper_from_toml(toml) {
+        Err(SqlMapperConfigError::Mapping(_, _, p)) => {
+            assert_eq!("fields.id.roles", p)
+        }
+        _ => panic!("Role expression must be invalid"),
+    }
+
+    let toml = "table = \"Report r\"\n\n[[fields]]\nname = \"id\"\nsq = \"r.id\"\n";
+    match mapper_from_toml(toml) {
+        Err(SqlMapperConfigError::Syntax(_, Some(p))) => assert_eq!(3, p.line), // Start of table
+
+        _ => panic!("Key `sq` must be unknown"),
+    }
+
+    let json = "{\n  \"mappers\": {\n    \"Report\": {\n      \"table\": \"Report r\",\n      \"merges\": [ { \"path\": \"lines\", \"mapper\": \"Line\" } ]\n    }\n  }\n}";
+    match cache_from_json(json) {
+        Err(SqlMapperConfigError::Mapping(n, _, p)) => {
+            assert_eq!("Report", n);
+            assert_eq!("mappers.Report.merges.lines.mapper", p);
+        }
+        _ => panic!("Merge mapper must be missing"),
+    }
+}
+
+#[test]
+fn report_all_errors() {
+    // Paths of unused joins appear earlier in the document
+    let toml = "table = \"Report r\"\n\n[[fields]]\nname = \"customer\"\nsql = \"r.seller\"\n\n[[joins]]\npath = \"customer\"\nsql = \"LEFT JOIN Customer c ON (r.customer_id = c.id)\"\n\n[[joins]]\npath = \"seller\"\nsql = \"LEFT JOIN Seller s ON (r.seller_id = s.id)\"\n";
+    match mapper_from_toml(toml) {
+        Err(SqlMapperConfigError::Multiple(errors)) => {
+            let paths: Vec<String> = errors
+                .into_iter()
+                .map(|e| match e {
+                    SqlMapperConfigError::Mapping(_, _, p) => p,
+                    _ => panic!("Error must be a mapping error"),
+                })
+                .collect();
+            assert_eq!(
+                vec!["joins.customer", "joins.seller"],
+                paths
+            );
+        }
+        _ => panic!("Both joins must be unused"),
+    }
+
+    let json = r#"{
+        "mappers": {
+            "Line": { "table": "Line l", "merges": [ { "path": "report", "mapper": "Reports" } ] },
+            "Report": { "table": "Report r", "merges": [ { "path": "lines", "mapper": "Lines" } ] }
+        }
+    }"#;
+    match cache_from_json(json) {
+        Err(SqlMapperConfigError::Multiple(errors)) => {
+            assert_eq!(2, errors.len());
+            assert_eq!(
+                SqlMapperConfigError::Mapping(
+                    String::from("Report"),
+                    String::from("merge for path `lines` needs missing mapper `Lines`"),
+                    String::from("mappers.Report.merges.lines.mapper")
+                ),
+                errors[1]
+            );
+        }
+        Ok(_) => panic!("Mappers of both merges must be missing"),
+        Err(e) => panic!("Mappers of both merges must be missing: {}", e),
+    }
+}
@@ -47,3 +47,21 @@ fn count_select() {
     );
     assert_eq!(*result.params(), ["1"]);
 }
+#[test]
+fn count_sql() {
+    let mapper = setup_mapper();
+    let query = QueryParser::parse("+username, book_id eq 1").unwrap();
+    let result = SqlBuilder::new().build(&mapper, &query).unwrap();
+    assert_eq!(
+        "SELECT COUNT(*) FROM User JOIN Book b ON (id = b.id) WHERE b.id = ?",
+        result.to_count_sql()
+    );
+
+    let mut query = QueryParser::parse("username").unwrap();
+    query.distinct = true;
+    let result = SqlBuilder::new().build(&mapper, &query).unwrap();
+    assert_eq!(
+        "SELECT COUNT(*) FROM (SELECT  DISTINCT null, username, null FROM User) AS toql_count",
+        result.to_count_sql()
+    );
+}
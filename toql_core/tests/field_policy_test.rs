@@ -0,0 +1,55 @@
+use std::collections::BTreeSet;
+use toql_core::query_parser::QueryParser;
+use toql_core::sql_builder::FieldPolicy;
+use toql_core::sql_builder::SqlBuilder;
+use toql_core::sql_builder::SqlBuilderError;
+use toql_core::sql_mapper::MapperOptions;
+use toql_core::sql_mapper::SqlMapper;
+
+fn setup_mapper() -> SqlMapper {
+    let mut mapper = SqlMapper::new("Book b");
+    mapper
+        .map_field("id", "b.id")
+        .map_field("title", "b.title")
+        .map_field_with_options(
+            "price",
+            "b.price",
+            MapperOptions::new().restrict_roles(["admin"].iter().map(|r| r.to_string()).collect::<BTreeSet<_>>()),
+        );
+    mapper
+}
+
+#[test]
+fn lenient_policy() {
+    let mapper = setup_mapper();
+    let query = QueryParser::parse("id, (price lt 10; title eq 'a'), isbn").unwrap();
+
+    let result = SqlBuilder::new()
+        .field_policy(FieldPolicy::Lenient)
+        .build(&mapper, &query)
+        .unwrap();
+    assert_eq!("SELECT b.id, b.title, null FROM Book b WHERE (b.title = ?)", result.to_sql());
+    assert!(result.warnings().is_empty());
+
+    let result = SqlBuilder::new()
+        .field_policy(FieldPolicy::LenientWithWarnings)
+        .build(&mapper, &query)
+        .unwrap();
+    let warnings: Vec<&str> = result.warnings().iter().map(|w| w.token.as_str()).collect();
+    assert_eq!(warnings, ["price", "isbn"]);
+    match result.warnings()[0].error {
+        SqlBuilderError::RoleRequired(_) => {}
+        _ => panic!("Field `price` must require a role"),
+    }
+}
+
+#[test]
+fn strict_policy() {
+    let mapper = setup_mapper();
+    let query = QueryParser::parse("id, price").unwrap();
+
+    match SqlBuilder::new().field_policy(FieldPolicy::Strict).build(&mapper, &query) {
+        Err(SqlBuilderError::RoleRequired(_)) => {}
+        _ => panic!("Field `price` must require a role"),
+    }
+}
@@ -0,0 +1,113 @@
+use toql_core::query_parser::QueryParser;
+use toql_core::sql_builder::SqlBuilder;
+use toql_core::sql_builder::SqlBuilderError;
+use toql_core::sql_mapper::SqlMapper;
+
+fn setup_mapper() -> SqlMapper {
+    let mut mapper = SqlMapper::new("Book b");
+    mapper
+        .map_field("id", "b.id")
+        .map_field("title", "b.title")
+        .map_function("LN", 1, 1, |expression: &str, args: &[String]| {
+            Ok((format!("LENGTH({}) = ?", expression), args.to_vec()))
+        })
+        .map_function("near", 1, 2, |expression: &str, args: &[String]| {
+            let distance = args.get(1).cloned().unwrap_or(String::from("10"));
+            Ok((format!("ABS({} - ?) <= ?", expression), vec![args[0].to_owned(), distance]))
+        });
+    mapper
+}
+
+#[test]
+fn build_function() {
+    let mapper = setup_mapper();
+    let query = QueryParser::parse("id FN NEAR 5, title FN ln 'abc'").unwrap();
+    let result = SqlBuilder::new().build(&mapper, &query).unwrap();
+
+    assert_eq!(
+        "SELECT b.id, b.title FROM Book b WHERE ABS(b.id - ?) <= ? AND LENGTH(b.title) = ?",
+        result.to_sql()
+    );
+    assert_eq!(*result.params(), ["5", "10", "abc"]);
+    assert_eq!(mapper.function_names(), ["LN", "NEAR"]);
+}
+
+#[test]
+fn reject_function() {
+    let mapper = setup_mapper();
+
+    let query = QueryParser::parse("title FN LN 1 2").unwrap();
+    match SqlBuilder::new().build(&mapper, &query) {
+        Err(SqlBuilderError::FilterInvalid(s)) => assert_eq!("filter `LN` expects 1 argument, got 2", s),
+        _ => panic!("Filter `LN` must be invalid"),
+    }
+
+    let query = QueryParser::parse("title FN SOUNDEX 'a'").unwrap();
+    match SqlBuilder::new().build(&mapper, &query) {
+        Err(SqlBuilderError::FilterInvalid(_)) => {}
+        _ => panic!("Filter `SOUNDEX` must be missing"),
+    }
+}
+
+#[test]
+fn recursive_function() {
+    let mut mapper = SqlMapper::new("Category c");
+    mapper
+        .map_field("id", "c.id")
+        .map_field("name", "c.name")
+        .map_recursive("id", "parent_id", Some(3));
+
+    let query = QueryParser::parse("id FN DESCENDANTS 1 2, name").unwrap();
+    let result = SqlBuilder::new().build(&mapper, &query).unwrap();
+    assert_eq!(
+        "SELECT c.id, c.name FROM Category c WHERE c.id IN (WITH RECURSIVE toql_tree (toql_key, toql_depth) AS \
+         (SELECT id, 1 FROM Category WHERE parent_id IN (?,?) \
+         UNION ALL SELECT toql_node.id, toql_tree.toql_depth + 1 FROM Category toql_node \
+         JOIN toql_tree ON (toql_node.parent_id = toql_tree.toql_key) WHERE toql_tree.toql_depth < 3) \
+         SELECT toql_key FROM toql_tree)",
+        result.to_sql()
+    );
+    assert_eq!(*result.params(), ["1", "2"]);
+
+    let query = QueryParser::parse("id FN ANCESTORS 7").unwrap();
+    let result = SqlBuilder::new().build(&mapper, &query).unwrap();
+    assert_eq!(
+        "SELECT c.id, null FROM Category c WHERE c.id IN (WITH RECURSIVE toql_tree (toql_key, toql_depth) AS \
+         (SELECT parent_id, 1 FROM Category WHERE id IN (?) AND parent_id IS NOT NULL \
+         UNION ALL SELECT toql_node.parent_id, toql_tree.toql_depth + 1 FROM Category toql_node \
+         JOIN toql_tree ON (toql_node.id = toql_tree.toql_key) WHERE toql_node.parent_id IS NOT NULL AND toql_tree.toql_depth < 3) \
+         SELECT toql_key FROM toql_tree)",
+        result.to_sql()
+    );
+    assert_eq!(mapper.function_names(), ["ANCESTORS", "DESCENDANTS"]);
+}
+
+#[test]
+fn recursive_function_without_depth() {
+    let mut mapper = SqlMapper::new("Category c");
+    mapper.map_field("id", "c.id").map_recursive("id", "parent_id", None);
+
+    // Branches stop at keys they already contain
+    let query = QueryParser::parse("id FN DESCENDANTS 1").unwrap();
+    let result = SqlBuilder::new().build(&mapper, &query).unwrap();
+    assert_eq!(
+        "SELECT c.id FROM Category c WHERE c.id IN (WITH RECURSIVE toql_tree (toql_key, toql_depth, toql_path) AS \
+         (SELECT id, 1, CAST(id AS CHAR(4000)) FROM Category WHERE parent_id IN (?) \
+         UNION ALL SELECT toql_node.id, toql_tree.toql_depth + 1, CONCAT(toql_tree.toql_path, ',', toql_node.id) FROM Category toql_node \
+         JOIN toql_tree ON (toql_node.parent_id = toql_tree.toql_key) WHERE FIND_IN_SET(toql_node.id, toql_tree.toql_path) = 0) \
+         SELECT toql_key FROM toql_tree)",
+        result.to_sql()
+    );
+
+    let query = QueryParser::parse("id FN ANCESTORS 7").unwrap();
+    let result = SqlBuilder::new().build(&mapper, &query).unwrap();
+    assert_eq!(
+        "SELECT c.id FROM Category c WHERE c.id IN (WITH RECURSIVE toql_tree (toql_key, toql_depth, toql_path) AS \
+         (SELECT parent_id, 1, CAST(parent_id AS CHAR(4000)) FROM Category WHERE id IN (?) AND parent_id IS NOT NULL \
+         UNION ALL SELECT toql_node.parent_id, toql_tree.toql_depth + 1, CONCAT(toql_tree.toql_path, ',', toql_node.parent_id) FROM Category toql_node \
+         JOIN toql_tree ON (toql_node.id = toql_tree.toql_key) WHERE toql_node.parent_id IS NOT NULL \
+         AND FIND_IN_SET(toql_node.parent_id, toql_tree.toql_path) = 0) \
+         SELECT toql_key FROM toql_tree)",
+        result.to_sql()
+    );
+}
@@ -0,0 +1,114 @@
+use std::collections::HashMap;
+use toql_core::merge::merge;
+use toql_core::merge::merge_tree;
+use toql_core::merge::MergeTarget;
+
+#[derive(Debug, Clone, PartialEq)]
+struct Translation {
+    text_id: u64,
+    lang_code: String,
+    text: String,
+}
+
+struct Text {
+    id: u64,
+    translations: HashMap<String, Translation>,
+    history: Vec<Translation>,
+}
+
+fn translation(text_id: u64, lang_code: &str, text: &str) -> Translation {
+    Translation {
+        text_id,
+        lang_code: String::from(lang_code),
+        text: String::from(text),
+    }
+}
+
+#[test]
+fn merge_into_map() {
+    let mut texts = vec![
+        Text { id: 1, translations: HashMap::new(), history: Vec::new() },
+        Text { id: 2, translations: HashMap::new(), history: Vec::new() },
+    ];
+    let translations = vec![
+        translation(1, "en", "Hello"),
+        translation(1, "de", "Hallo"),
+        translation(2, "en", "Bye"),
+        translation(3, "en", "Orphan"),
+    ];
+
+    merge(
+        &mut texts,
+        translations.clone(),
+        |t| Some(t.id),
+        |o| Some(o.text_id),
+        |t, o| MergeTarget::merge_item(&mut t.translations, o.lang_code.clone(), o),
+    );
+    merge(
+        &mut texts,
+        translations,
+        |t| Some(t.id),
+        |o| Some(o.text_id),
+        |t, o| MergeTarget::merge_item(&mut t.history, (), o),
+    );
+
+    assert_eq!(texts[0].translations.len(), 2);
+    assert_eq!(texts[0].translations["de"].text, "Hallo");
+    assert_eq!(texts[1].translations["en"].text, "Bye");
+    assert_eq!(texts[1].history, [translation(2, "en", "Bye")]);
+}
+
+#[derive(Debug, Clone, PartialEq)]
+struct Category {
+    id: u64,
+    parent_id: Option<u64>,
+    children: Vec<Category>,
+}
+
+fn category(id: u64, parent_id: Option<u64>) -> Category {
+    Category { id, parent_id, children: Vec::new() }
+}
+
+#[test]
+fn merge_into_tree() {
+    // Category 2 is a top node and a child of 1
+    let mut categories = vec![category(1, None), category(2, Some(1))];
+    let nodes = vec![category(3, Some(2)), category(2, Some(1)), category(4, Some(1)), category(5, Some(9))];
+
+    merge_tree(
+        &mut categories,
+        nodes,
+        |t| Option::from(t.id),
+        |o| o.parent_id,
+        |t, o| MergeTarget::merge_item(&mut t.children, (), o),
+    );
+
+    let ids = |c: &Category| c.children.iter().map(|c| c.id).collect::<Vec<u64>>();
+    assert_eq!(ids(&categories[0]), [2, 4]);
+    assert_eq!(ids(&categories[0].children[0]), [3]);
+    assert_eq!(ids(&categories[1]), [3]);
+
+    // Cycles are dropped
+    let mut categories = vec![category(1, Some(2))];
+    merge_tree(
+        &mut categories,
+        vec![category(2, Some(1))],
+        |t| Option::from(t.id),
+        |o| o.parent_id,
+        |t, o| MergeTarget::merge_item(&mut t.children, (), o),
+    );
+    assert_eq!(ids(&categories[0]), [2]);
+    assert!(categories[0].children[0].children.is_empty());
+
+    // Nodes of a cycle include the top node, it is not attached below itself
+    let mut categories = vec![category(1, Some(2))];
+    merge_tree(
+        &mut categories,
+        vec![category(2, Some(1)), category(1, Some(2))],
+        |t| Option::from(t.id),
+        |o| o.parent_id,
+        |t, o| MergeTarget::merge_item(&mut t.children, (), o),
+    );
+    assert_eq!(ids(&categories[0]), [2]);
+    assert!(categories[0].children[0].children.is_empty());
+}
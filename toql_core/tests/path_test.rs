@@ -50,3 +50,15 @@ fn ignore_path() {
 
     assert_eq!("SELECT id, username, null FROM User", result.to_sql());
 }
+
+#[test]
+fn contains_path() {
+    let query = QueryParser::parse("id, orders_items_id eq 5, orders_*").unwrap();
+    assert!(query.contains_path("orders"));
+    assert!(query.contains_path("orders_items"));
+    assert!(!query.contains_path("orders_items_tags"));
+    assert!(!query.contains_path("order"));
+
+    let query = QueryParser::parse("id, **").unwrap();
+    assert!(query.contains_path("orders_items_tags"));
+}
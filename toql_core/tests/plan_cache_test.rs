@@ -0,0 +1,117 @@
+use std::collections::BTreeSet;
+use std::collections::HashMap;
+use std::sync::Arc;
+use toql_core::query_parser::QueryParser;
+use toql_core::query_plan_cache::QueryPlanCache;
+use toql_core::sql_builder::SqlBuilder;
+use toql_core::sql_builder::SqlBuilderError;
+use toql_core::sql_mapper::MapperOptions;
+use toql_core::sql_mapper::SqlMapper;
+use toql_core::sql_mapper::ValueType;
+
+fn setup_mapper() -> SqlMapper {
+    let mut mapper = SqlMapper::new("Book b");
+    mapper
+        .map_field_with_options("id", "b.id", MapperOptions::new().value_type(ValueType::Integer))
+        .map_field("title", "b.title")
+        .add_row_filter(|_roles: &BTreeSet<String>, context: &HashMap<String, String>| {
+            let tenant = context
+                .get("tenant_id")
+                .ok_or(SqlBuilderError::ContextMissing(String::from("tenant_id")))?;
+            Ok(Some((String::from("b.tenant_id = ?"), vec![tenant.to_owned()])))
+        });
+    mapper
+}
+
+fn build(cache: &Arc<QueryPlanCache>, mapper: &SqlMapper, query: &str, tenant: &str) -> Result<(String, Vec<String>), SqlBuilderError> {
+    let mut query = QueryParser::parse(query).unwrap();
+    query.context.insert(String::from("tenant_id"), String::from(tenant));
+    let result = SqlBuilder::new().plan_cache(Arc::clone(cache)).build(mapper, &query)?;
+    Ok((result.to_sql(), result.params().to_owned()))
+}
+
+#[test]
+fn plan_cache_hit() {
+    let mapper = setup_mapper();
+    let cache = Arc::new(QueryPlanCache::new(10));
+
+    let (sql, params) = build(&cache, &mapper, "id gt 5, title lk 'a%'", "1").unwrap();
+    assert_eq!(
+        "SELECT b.id, b.title FROM Book b WHERE (b.id > ? AND b.title LIKE ?) AND (b.tenant_id = ?)",
+        sql
+    );
+    assert_eq!(params, ["5", "a%", "1"]);
+
+    // Same shape, other values and context
+    let (cached_sql, params) = build(&cache, &mapper, "id gt 7, title lk 'b%'", "2").unwrap();
+    assert_eq!(sql, cached_sql);
+    assert_eq!(params, ["7", "b%", "2"]);
+
+    let metrics = cache.metrics();
+    assert_eq!((1, 1, 1), (metrics.hits, metrics.misses, metrics.len));
+}
+
+#[test]
+fn plan_cache_shape() {
+    let mapper = setup_mapper();
+    let cache = Arc::new(QueryPlanCache::new(10));
+
+    build(&cache, &mapper, "id in 1 2", "1").unwrap();
+    let (sql, params) = build(&cache, &mapper, "id in 1 2 3", "1").unwrap();
+    assert_eq!("SELECT b.id, null FROM Book b WHERE (b.id IN (?,?,?)) AND (b.tenant_id = ?)", sql);
+    assert_eq!(params, ["1", "2", "3", "1"]);
+
+    build(&cache, &mapper, "id eq 1, title", "1").unwrap();
+    build(&cache, &mapper, "id eq 1; title", "1").unwrap();
+    assert_eq!(0, cache.metrics().hits);
+
+    // Values are validated on cache hits too
+    match build(&cache, &mapper, "id in 1 'x' 3", "1") {
+        Err(SqlBuilderError::FilterInvalid(_)) => {}
+        _ => panic!("Filter must be invalid"),
+    }
+    assert_eq!(1, cache.metrics().hits);
+}
+
+#[test]
+fn plan_cache_eviction() {
+    let mapper = setup_mapper();
+    let cache = Arc::new(QueryPlanCache::new(2));
+
+    build(&cache, &mapper, "id", "1").unwrap();
+    build(&cache, &mapper, "title", "1").unwrap();
+    build(&cache, &mapper, "id", "1").unwrap();
+    build(&cache, &mapper, "*", "1").unwrap(); // Evicts `title`, the least recently used
+    build(&cache, &mapper, "id", "1").unwrap();
+
+    let metrics = cache.metrics();
+    assert_eq!((2, 3, 1, 2), (metrics.hits, metrics.misses, metrics.evictions, metrics.len));
+
+    cache.clear();
+    assert!(cache.is_empty());
+}
+
+#[test]
+fn plan_cache_mapper_change() {
+    let mut mapper = setup_mapper();
+    let cache = Arc::new(QueryPlanCache::new(10));
+
+    build(&cache, &mapper, "title eq 'a'", "1").unwrap();
+    mapper.alter_field("title", "b.name", MapperOptions::new());
+    let (sql, _) = build(&cache, &mapper, "title eq 'a'", "1").unwrap();
+    assert_eq!("SELECT null, b.name FROM Book b WHERE (b.name = ?) AND (b.tenant_id = ?)", sql);
+
+    // Other mappers on the same table
+    let mut other = setup_mapper();
+    other.unmap_field("title").unwrap();
+    match build(&cache, &other, "title eq 'a'", "1") {
+        Err(SqlBuilderError::FieldMissing(_)) => {}
+        _ => panic!("Field `title` must be unmapped"),
+    }
+    assert_eq!(0, cache.metrics().hits);
+
+    // Clones keep the plans of their mapper
+    let clone = mapper.clone();
+    build(&cache, &clone, "title eq 'b'", "1").unwrap();
+    assert_eq!(1, cache.metrics().hits);
+}
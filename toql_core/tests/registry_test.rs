@@ -0,0 +1,133 @@
+use std::sync::Arc;
+use toql_core::mapper_registry::MapperRegistry;
+use toql_core::mapper_registry::DEFAULT_MAX_MERGE_DEPTH;
+use toql_core::sql_mapper::FieldHandler;
+use toql_core::sql_mapper::Mapped;
+use toql_core::sql_mapper::SqlMapper;
+use toql_core::sql_mapper::SqlMapperCache;
+
+struct Book {}
+struct Chapter {}
+
+impl Mapped for Book {
+    fn insert_new_mapper(cache: &mut SqlMapperCache) -> &mut SqlMapper {
+        cache.insert(Self::type_name(), Self::new_mapper("b"));
+        Self::register_dependencies(cache);
+        cache.get_mut("Book").unwrap()
+    }
+    fn insert_new_mapper_with_handler<H>(cache: &mut SqlMapperCache, handler: H) -> &mut SqlMapper
+    where
+        H: 'static + FieldHandler + Send + Sync,
+    {
+        cache.insert(Self::type_name(), Self::new_mapper_with_handler("b", handler));
+        Self::register_dependencies(cache);
+        cache.get_mut("Book").unwrap()
+    }
+    fn new_mapper(sql_alias: &str) -> SqlMapper {
+        let mut m = SqlMapper::new(format!("Book {}", sql_alias));
+        Self::map(&mut m, "", sql_alias);
+        m
+    }
+    fn new_mapper_with_handler<H>(sql_alias: &str, handler: H) -> SqlMapper
+    where
+        H: 'static + FieldHandler + Send + Sync,
+    {
+        let mut m = SqlMapper::new_with_handler(format!("Book {}", sql_alias), handler);
+        Self::map(&mut m, "", sql_alias);
+        m
+    }
+    fn map(mapper: &mut SqlMapper, _toql_path: &str, sql_alias: &str) {
+        mapper
+            .map_field("id", &format!("{}.id", sql_alias))
+            .merge("chapters", "Chapter");
+    }
+    fn type_name() -> String {
+        String::from("Book")
+    }
+    fn register_dependencies(cache: &mut SqlMapperCache) {
+        cache.register::<Chapter>();
+    }
+}
+
+impl Mapped for Chapter {
+    fn insert_new_mapper(cache: &mut SqlMapperCache) -> &mut SqlMapper {
+        cache.insert(Self::type_name(), Self::new_mapper("c"));
+        cache.get_mut("Chapter").unwrap()
+    }
+    fn insert_new_mapper_with_handler<H>(cache: &mut SqlMapperCache, handler: H) -> &mut SqlMapper
+    where
+        H: 'static + FieldHandler + Send + Sync,
+    {
+        cache.insert(Self::type_name(), Self::new_mapper_with_handler("c", handler));
+        cache.get_mut("Chapter").unwrap()
+    }
+    fn new_mapper(sql_alias: &str) -> SqlMapper {
+        let mut m = SqlMapper::new(format!("Chapter {}", sql_alias));
+        Self::map(&mut m, "", sql_alias);
+        m
+    }
+    fn new_mapper_with_handler<H>(sql_alias: &str, handler: H) -> SqlMapper
+    where
+        H: 'static + FieldHandler + Send + Sync,
+    {
+        let mut m = SqlMapper::new_with_handler(format!("Chapter {}", sql_alias), handler);
+        Self::map(&mut m, "", sql_alias);
+        m
+    }
+    fn map(mapper: &mut SqlMapper, _toql_path: &str, sql_alias: &str) {
+        mapper.map_field("title", &format!("{}.title", sql_alias));
+    }
+    fn type_name() -> String {
+        String::from("Chapter")
+    }
+}
+
+#[test]
+fn register_dependencies() {
+    let mut registry = MapperRegistry::new();
+    registry.register::<Book>();
+
+    assert_eq!(registry.keys(), ["Book", "Chapter"]);
+    assert!(registry.get_for::<Chapter>().is_some());
+    assert!(registry.validate().is_ok());
+}
+
+#[test]
+fn overlay_registry() {
+    let mut base = MapperRegistry::new();
+    base.register::<Book>();
+    let base = Arc::new(base);
+
+    let mut tenant = MapperRegistry::with_base(Arc::clone(&base));
+    assert_eq!(2, tenant.len());
+    tenant.get_mut_for::<Book>().unwrap().map_field("tenant", "b.tenant");
+
+    assert_eq!(tenant.get("Book").unwrap().field_names(), ["id", "tenant"]);
+    assert_eq!(base.get("Book").unwrap().field_names(), ["id"]);
+
+    // Removing from the overlay uncovers the base mapper
+    tenant.remove("Book");
+    assert_eq!(tenant.get("Book").unwrap().field_names(), ["id"]);
+}
+
+#[test]
+fn share_registry() {
+    fn assert_send_sync<T: Send + Sync>(_: &T) {}
+    let registry = Arc::new(MapperRegistry::new());
+    assert_send_sync(&registry);
+}
+
+#[test]
+fn max_merge_depth() {
+    let mut base = MapperRegistry::new();
+    assert_eq!(DEFAULT_MAX_MERGE_DEPTH, base.max_merge_depth());
+    base.set_max_merge_depth(2);
+    let base = Arc::new(base);
+
+    // Overlays inherit the depth from their base
+    let mut tenant = MapperRegistry::with_base(Arc::clone(&base));
+    assert_eq!(2, tenant.max_merge_depth());
+    tenant.set_max_merge_depth(0);
+    assert_eq!(0, tenant.max_merge_depth());
+    assert_eq!(2, base.max_merge_depth());
+}
@@ -0,0 +1,65 @@
+use toql_core::query_parser::QueryParser;
+use toql_core::sql_builder::SqlBuilder;
+use toql_core::sql_builder::SqlBuilderError;
+use toql_core::sql_mapper::MapperOptions;
+use toql_core::sql_mapper::SqlMapper;
+
+fn setup_mapper() -> SqlMapper {
+    let mut mapper = SqlMapper::new("Book b");
+    mapper
+        .map_field_with_options(
+            "id",
+            "b.id",
+            MapperOptions::new().restrict_filters(["eq", "IN"].iter().map(|f| f.to_string()).collect()),
+        )
+        .map_field_with_options(
+            "title",
+            "b.title",
+            MapperOptions::new().sortable(false).min_like_prefix(3),
+        );
+    mapper
+}
+
+fn assert_invalid(toql: &str, expected: &str) {
+    let mapper = setup_mapper();
+    let query = QueryParser::parse(toql).unwrap();
+    match SqlBuilder::new().build(&mapper, &query) {
+        Err(SqlBuilderError::FilterInvalid(s)) => assert_eq!(expected, s),
+        _ => panic!("Filter `{}` must be invalid", toql),
+    }
+}
+
+#[test]
+fn restrict_filters() {
+    assert_invalid("id re '^1'", "field `id` does not allow filter `RE`");
+
+    let mapper = setup_mapper();
+    let query = QueryParser::parse("id in 1 2, title eq 'Foo'").unwrap();
+    let result = SqlBuilder::new().build(&mapper, &query).unwrap();
+    assert_eq!(
+        "SELECT b.id, b.title FROM Book b WHERE b.id IN (?,?) AND b.title = ?",
+        result.to_sql()
+    );
+}
+
+#[test]
+fn restrict_order() {
+    let mapper = setup_mapper();
+    let query = QueryParser::parse("+title").unwrap();
+    match SqlBuilder::new().build(&mapper, &query) {
+        Err(SqlBuilderError::OrderInvalid(f)) => assert_eq!("title", f),
+        _ => panic!("Field `title` must not be sortable"),
+    }
+}
+
+#[test]
+fn restrict_like_prefix() {
+    assert_invalid(
+        "title lk 'Fo%'",
+        "field `title` requires at least 3 characters before a LIKE wildcard",
+    );
+
+    let mapper = setup_mapper();
+    let query = QueryParser::parse("title lk 'Foo%'").unwrap();
+    assert!(SqlBuilder::new().build(&mapper, &query).is_ok());
+}
@@ -0,0 +1,110 @@
+use std::collections::BTreeSet;
+use toql_core::query_parser::QueryParser;
+use toql_core::role_expr::RoleExpr;
+use toql_core::role_expr::RoleExprError;
+use toql_core::sql_builder::SqlBuilder;
+use toql_core::sql_builder::SqlBuilderError;
+use toql_core::sql_mapper::MapperOptions;
+use toql_core::sql_mapper::SqlMapper;
+
+fn roles(roles: &[&str]) -> BTreeSet<String> {
+    roles.iter().map(|r| r.to_string()).collect()
+}
+
+fn setup_mapper() -> SqlMapper {
+    let mut mapper = SqlMapper::new("Book b");
+    mapper
+        .join_with_roles(
+            "author",
+            "JOIN User a ON (b.author_id = a.id)",
+            RoleExpr::parse("!guest").unwrap(),
+        )
+        .map_field_with_options("id", "b.id", MapperOptions::new().select_always(true))
+        .map_field_with_options(
+            "price",
+            "b.price",
+            MapperOptions::new().restrict_role_expr(RoleExpr::parse("admin;owner").unwrap()),
+        )
+        .map_field("author_id", "a.id")
+        .merge_with_roles("chapters", "Chapter", RoleExpr::role("editor"));
+    mapper
+}
+
+#[test]
+fn parse_role_expr() {
+    let expr = RoleExpr::parse("admin; owner, !guest").unwrap();
+    assert_eq!(
+        RoleExpr::Any(vec![
+            RoleExpr::role("admin"),
+            RoleExpr::All(vec![RoleExpr::role("owner"), !RoleExpr::role("guest")])
+        ]),
+        expr
+    );
+    assert_eq!("admin;owner,!guest", expr.to_string());
+    assert_eq!("(admin;owner),!(guest,temp)", RoleExpr::parse("(admin;owner),!(guest,temp)").unwrap().to_string());
+
+    assert!(expr.is_valid(&roles(&["admin", "guest"])));
+    assert!(expr.is_valid(&roles(&["owner"])));
+    assert!(!expr.is_valid(&roles(&["owner", "guest"])));
+    assert_eq!(roles(&["admin", "guest", "owner"]), expr.roles());
+
+    assert_eq!(Err(RoleExprError::UnexpectedChar(6, ';')), RoleExpr::parse("admin;;owner"));
+    assert_eq!(Err(RoleExprError::UnexpectedEnd), RoleExpr::parse("(admin"));
+}
+
+#[test]
+fn field_role_expr() {
+    let mapper = setup_mapper();
+    let mut query = QueryParser::parse("price").unwrap();
+
+    match SqlBuilder::new().build(&mapper, &query) {
+        Err(SqlBuilderError::RoleRequired(_)) => {}
+        _ => panic!("Field `price` must require a role"),
+    }
+
+    query.roles = roles(&["owner"]);
+    let result = SqlBuilder::new().build(&mapper, &query).unwrap();
+    assert_eq!("SELECT b.id, b.price, null FROM Book b", result.to_sql());
+    let columns: Vec<(&str, bool)> = result
+        .columns()
+        .iter()
+        .map(|c| (c.toql_field.as_str(), c.selected))
+        .collect();
+    assert_eq!(columns, [("id", true), ("price", true), ("author_id", false)]);
+}
+
+#[test]
+fn join_role_expr() {
+    let mapper = setup_mapper();
+    let mut query = QueryParser::parse("**").unwrap();
+    query.roles = roles(&["guest"]);
+    let result = SqlBuilder::new().build(&mapper, &query).unwrap();
+    assert_eq!("SELECT b.id, null, null FROM Book b", result.to_sql());
+
+    let mut query = QueryParser::parse("author_id").unwrap();
+    query.roles = roles(&["guest"]);
+    match SqlBuilder::new().build(&mapper, &query) {
+        Err(SqlBuilderError::RoleRequired(_)) => {}
+        _ => panic!("Path `author` must require a role"),
+    }
+
+    query.roles = BTreeSet::new();
+    let result = SqlBuilder::new().build(&mapper, &query).unwrap();
+    assert_eq!(
+        "SELECT b.id, null, a.id FROM Book b JOIN User a ON (b.author_id = a.id)",
+        result.to_sql()
+    );
+
+    let result = SqlBuilder::new().omit_unselected(true).build(&mapper, &query).unwrap();
+    assert_eq!("SELECT b.id, a.id FROM Book b JOIN User a ON (b.author_id = a.id)", result.to_sql());
+    let columns: Vec<&str> = result.columns().iter().map(|c| c.toql_field.as_str()).collect();
+    assert_eq!(columns, ["id", "author_id"]);
+}
+
+#[test]
+fn merge_role_expr() {
+    let mapper = setup_mapper();
+    assert!(!mapper.merge_roles_valid("chapters", &roles(&["guest"])));
+    assert!(mapper.merge_roles_valid("chapters", &roles(&["editor"])));
+    assert!(mapper.merge_roles_valid("reviews", &BTreeSet::new()));
+}
@@ -0,0 +1,69 @@
+use std::collections::BTreeSet;
+use std::collections::HashMap;
+use toql_core::query_parser::QueryParser;
+use toql_core::role_expr::RoleExpr;
+use toql_core::sql_builder::SqlBuilder;
+use toql_core::sql_builder::SqlBuilderError;
+use toql_core::sql_mapper::SqlMapper;
+
+fn setup_mapper() -> SqlMapper {
+    let mut mapper = SqlMapper::new("Book b");
+    mapper
+        .map_field("id", "b.id")
+        .map_field("title", "b.title")
+        .add_predicate("b.published = ?", vec![String::from("1")], Some(RoleExpr::parse("!admin").unwrap()))
+        .add_row_filter(|_roles: &BTreeSet<String>, context: &HashMap<String, String>| {
+            let tenant = context
+                .get("tenant_id")
+                .ok_or(SqlBuilderError::ContextMissing(String::from("tenant_id")))?;
+            Ok(Some((String::from("b.tenant_id = ?"), vec![tenant.to_owned()])))
+        });
+    mapper
+}
+
+#[test]
+fn row_filter_build() {
+    let mapper = setup_mapper();
+    let mut query = QueryParser::parse("id eq 1; title lk 'a%'").unwrap();
+    query.context.insert(String::from("tenant_id"), String::from("7"));
+
+    let result = SqlBuilder::new().build(&mapper, &query).unwrap();
+    assert_eq!(
+        "SELECT b.id, b.title FROM Book b WHERE (b.id = ? OR b.title LIKE ?) AND (b.published = ?) AND (b.tenant_id = ?)",
+        result.to_sql()
+    );
+    assert_eq!(*result.params(), ["1", "a%", "1", "7"]);
+
+    query.roles.insert(String::from("admin"));
+    let result = SqlBuilder::new().build_count(&mapper, &query).unwrap();
+    assert_eq!("SELECT 1 FROM Book b WHERE (b.tenant_id = ?)", result.to_sql());
+    assert_eq!(*result.params(), ["7"]);
+}
+
+#[test]
+fn row_filter_path() {
+    let mapper = setup_mapper();
+    let mut query = QueryParser::parse("id, author_title").unwrap();
+    query.context.insert(String::from("tenant_id"), String::from("7"));
+
+    let result = SqlBuilder::new().build_path("author", &mapper, &query).unwrap();
+    assert_eq!(
+        "SELECT null, b.title FROM Book b WHERE (b.published = ?) AND (b.tenant_id = ?)",
+        result.to_sql()
+    );
+
+    // Path queries without fields are skipped, even with row filters
+    let result = SqlBuilder::new().build_path("publisher", &mapper, &query).unwrap();
+    assert!(result.is_empty());
+}
+
+#[test]
+fn row_filter_context_missing() {
+    let mapper = setup_mapper();
+    let query = QueryParser::parse("*").unwrap();
+
+    match SqlBuilder::new().build(&mapper, &query) {
+        Err(SqlBuilderError::ContextMissing(k)) => assert_eq!("tenant_id", k),
+        _ => panic!("Context `tenant_id` must be missing"),
+    }
+}
@@ -0,0 +1,87 @@
+use toql_core::role_expr::RoleExpr;
+use toql_core::sql_mapper::MapperOptions;
+use toql_core::sql_mapper::SqlMapper;
+use toql_core::sql_mapper::SqlMapperCache;
+use toql_core::sql_mapper_schema::cache_schema;
+use toql_core::sql_mapper_schema::JoinSchema;
+use toql_core::sql_mapper_schema::MapperSchema;
+
+fn setup_mapper() -> SqlMapper {
+    let mut mapper = SqlMapper::new("Book b");
+    mapper
+        .join("author", "JOIN User a ON (b.author_id = a.id)")
+        .map_field_with_options("id", "b.id", MapperOptions::new().select_always(true))
+        .map_field_with_options(
+            "title",
+            "b.title",
+            MapperOptions::new().restrict_roles(["editor"].iter().map(|r| r.to_string()).collect()),
+        )
+        .map_field("author_id", "a.id")
+        .merge("chapters", "Chapter");
+    mapper
+}
+
+#[test]
+fn introspect_mapper() {
+    let mapper = setup_mapper();
+
+    assert_eq!("b", mapper.table_alias());
+    assert_eq!(vec!["id", "title", "author_id"], mapper.field_names());
+    assert_eq!(Some("a.id"), mapper.field_expression("author_id"));
+    assert!(mapper.field_options("id").unwrap().is_select_always());
+    assert_eq!(vec!["author"], mapper.join_paths());
+    assert_eq!(Some("Chapter"), mapper.merge_mapper("chapters"));
+}
+
+#[test]
+fn mapper_schema() {
+    let schema = MapperSchema::from_mapper(&setup_mapper());
+
+    assert_eq!(3, schema.fields.len());
+    assert_eq!("author", schema.fields[2].path);
+    assert_eq!(Some(String::from("editor")), schema.fields[1].roles);
+    assert_eq!(vec![JoinSchema { path: String::from("author"), roles: None }], schema.joins);
+    assert_eq!("Chapter", schema.merges[0].mapper);
+    assert_eq!(None, schema.merges[0].roles);
+}
+
+#[test]
+fn join_and_merge_roles() {
+    let mut mapper = SqlMapper::new("Book b");
+    mapper
+        .join_with_roles("author", "JOIN User a ON (b.author_id = a.id)", RoleExpr::parse("admin;editor").unwrap())
+        .map_field("id", "b.id")
+        .map_field("author_id", "a.id")
+        .merge_with_roles("chapters", "Chapter", RoleExpr::parse("reader").unwrap());
+
+    // Mapped again, the field is not listed twice
+    mapper.map_field("id", "b.id");
+    assert_eq!(vec!["id", "author_id"], mapper.field_names());
+
+    let schema = MapperSchema::from_mapper(&mapper);
+    assert_eq!(Some(String::from("admin;editor")), schema.joins[0].roles);
+    assert_eq!(Some(String::from("reader")), schema.merges[0].roles);
+}
+
+#[test]
+fn mapper_cache_schema() {
+    let mut cache = SqlMapperCache::new();
+    cache.insert(String::from("Book"), setup_mapper());
+
+    let schemas = cache_schema(&cache);
+    assert_eq!(vec!["Book"], schemas.keys().collect::<Vec<_>>());
+}
+
+#[cfg(feature = "json")]
+#[test]
+fn mapper_schema_json() {
+    use toql_core::sql_mapper::ValueType;
+
+    let mut mapper = SqlMapper::new("User u");
+    mapper.map_field_with_options("id", "u.id", MapperOptions::new().value_type(ValueType::Integer));
+
+    assert_eq!(
+        r#"{"fields":[{"name":"id","path":"","value_type":"integer","filterable":true,"filters":null,"sortable":true,"select_always":false,"ignore_wildcard":false,"aggregate":false,"roles":null}],"joins":[],"merges":[],"selections":{}}"#,
+        MapperSchema::from_mapper(&mapper).to_json()
+    );
+}
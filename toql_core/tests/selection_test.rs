@@ -0,0 +1,60 @@
+use toql_core::query::Field;
+use toql_core::query::Query;
+use toql_core::query::Selection;
+use toql_core::query_parser::QueryParser;
+use toql_core::sql_builder::SqlBuilder;
+use toql_core::sql_builder::SqlBuilderError;
+use toql_core::sql_mapper::SqlMapper;
+
+fn setup_mapper() -> SqlMapper {
+    let mut mapper = SqlMapper::new("Book b");
+    mapper
+        .join("author", "JOIN User a ON (b.author_id = a.id)")
+        .map_field("id", "b.id")
+        .map_field("title", "b.title")
+        .map_field("author_username", "a.username")
+        .default_order(&QueryParser::parse("+1author_username, -2id").unwrap())
+        .selection("summary", &["id", "title"])
+        .selection("detail", &["id", "title", "author_username"]);
+    mapper
+}
+
+#[test]
+fn parse_selection() {
+    let query = QueryParser::parse("$summary, title eq 'Foo'").unwrap();
+    assert_eq!("$summary,title EQ 'Foo'", query.to_string());
+
+    let query = Query::new().and(Field::from("id")).or(Selection::from("$detail"));
+    assert_eq!("id;$detail", query.to_string());
+}
+
+#[test]
+fn build_selection() {
+    let mapper = setup_mapper();
+    let query = QueryParser::parse("$summary, +title").unwrap();
+    let result = SqlBuilder::new().build(&mapper, &query).unwrap();
+    assert_eq!(
+        "SELECT b.id, b.title, null FROM Book b ORDER BY b.title ASC",
+        result.to_sql()
+    );
+
+    let query = QueryParser::parse("$preview").unwrap();
+    match SqlBuilder::new().build(&mapper, &query) {
+        Err(SqlBuilderError::SelectionMissing(s)) => assert_eq!("preview", s),
+        _ => panic!("Selection `preview` must be missing"),
+    }
+}
+
+#[test]
+fn build_default_order() {
+    let mapper = setup_mapper();
+    let query = QueryParser::parse("*").unwrap();
+    let result = SqlBuilder::new().build(&mapper, &query).unwrap();
+    assert_eq!(
+        "SELECT b.id, b.title, null FROM Book b JOIN User a ON (b.author_id = a.id) ORDER BY a.username ASC, b.id DESC",
+        result.to_sql()
+    );
+
+    let result = SqlBuilder::new().build_count(&mapper, &query).unwrap();
+    assert_eq!("SELECT 1 FROM Book b", result.to_sql());
+}
@@ -0,0 +1,39 @@
+use toql_core::query::Field;
+use toql_core::query::Query;
+use toql_core::sql_builder::SqlBuilder;
+use toql_core::sql_mapper::SqlMapper;
+use toql_core::sql_mapper::SqlMapperError;
+use toql_core::sql_mapper::THROUGH_KEY;
+
+fn setup_mapper() -> SqlMapper {
+    let mut mapper = SqlMapper::new("Role r");
+    mapper.map_field("id", "r.id").map_field("name", "r.name");
+    mapper
+}
+
+#[test]
+fn join_through() {
+    let mut mapper = setup_mapper();
+    mapper.join_through("UserRole", "role_id", "id", "user_id").unwrap();
+    assert!(mapper.validate().is_ok());
+
+    let query = Query::new()
+        .and(Field::from("roles_name"))
+        .and(Field::from(format!("roles_{}", THROUGH_KEY)).ins(vec![1, 2]));
+    let result = SqlBuilder::new().build_path("roles", &mapper, &query).unwrap();
+    assert_eq!(
+        "SELECT null, r.name, toql_through.user_id FROM Role r JOIN UserRole toql_through ON (toql_through.role_id = r.id) \
+         WHERE toql_through.user_id IN (?,?)",
+        result.to_sql()
+    );
+    assert_eq!(*result.params(), ["1", "2"]);
+}
+
+#[test]
+fn join_through_field_missing() {
+    let mut mapper = setup_mapper();
+    assert_eq!(
+        mapper.join_through("UserRole", "role_id", "key", "user_id").err(),
+        Some(SqlMapperError::FieldMissing(String::from("key")))
+    );
+}
@@ -0,0 +1,85 @@
+use toql_core::sql_mapper::validate_cache;
+use toql_core::sql_mapper::SqlMapper;
+use toql_core::sql_mapper::SqlMapperCache;
+use toql_core::sql_mapper::SqlMapperError;
+
+fn setup_mapper() -> SqlMapper {
+    let mut mapper = SqlMapper::new("Book b");
+    mapper
+        .join("author", "JOIN User a ON (b.author_id = a.id)")
+        .map_field("id", "b.id")
+        .map_field("title", "b.title")
+        .map_field("author_id", "a.id");
+    mapper
+}
+
+#[test]
+fn validate_valid() {
+    let mapper = setup_mapper();
+    assert_eq!(Ok(()), mapper.validate());
+}
+
+#[test]
+fn validate_join_missing() {
+    let mut mapper = setup_mapper();
+    mapper.map_field("publisher_name", "p.name");
+
+    assert_eq!(
+        Err(vec![SqlMapperError::JoinMissing(String::from("publisher"))]),
+        mapper.validate()
+    );
+}
+
+#[test]
+fn validate_join_unused() {
+    let mut mapper = setup_mapper();
+    mapper.join("publisher", "LEFT JOIN Publisher p ON (b.publisher_id = p.id)");
+
+    assert_eq!(
+        Err(vec![SqlMapperError::JoinUnused(String::from("publisher"))]),
+        mapper.validate()
+    );
+}
+
+#[test]
+fn validate_remapped() {
+    let mut mapper = setup_mapper();
+    mapper.map_field("title", "b.long_title");
+
+    // Remapped fields keep their position and are no duplicates
+    assert_eq!(Ok(()), mapper.validate());
+}
+
+#[test]
+fn validate_alias_unknown() {
+    let mut mapper = setup_mapper();
+    mapper
+        .join("editor", "LEFT JOIN User e ON (x.editor_id = e.id AND e.name <> 'n.a.')")
+        .map_field("editor_id", "e.id");
+
+    assert_eq!(
+        Err(vec![SqlMapperError::AliasUnknown(String::from("editor"), String::from("x"))]),
+        mapper.validate()
+    );
+}
+
+#[test]
+fn validate_cache_merge_missing() {
+    let mut cache = SqlMapperCache::new();
+    let mut mapper = setup_mapper();
+    mapper.merge("chapters", "Chapter");
+    cache.insert(String::from("Book"), mapper);
+
+    assert_eq!(
+        Err(vec![(
+            String::from("Book"),
+            SqlMapperError::MergeMapperMissing(String::from("chapters"), String::from("Chapter"))
+        )]),
+        validate_cache(&cache)
+    );
+
+    let mut chapter_mapper = SqlMapper::new("Chapter c");
+    chapter_mapper.map_field("id", "c.id");
+    cache.insert(String::from("Chapter"), chapter_mapper);
+    assert_eq!(Ok(()), validate_cache(&cache));
+}
@@ -0,0 +1,56 @@
+use toql_core::query_parser::QueryParser;
+use toql_core::sql_builder::SqlBuilder;
+use toql_core::sql_builder::SqlBuilderError;
+use toql_core::sql_mapper::MapperOptions;
+use toql_core::sql_mapper::SqlMapper;
+use toql_core::sql_mapper::ValueType;
+
+fn setup_mapper() -> SqlMapper {
+    let mut mapper = SqlMapper::new("User");
+    mapper
+        .map_field_with_options("age", "age", MapperOptions::new().value_type(ValueType::Integer))
+        .map_field_with_options("score", "score", MapperOptions::new().value_type(ValueType::Float))
+        .map_field_with_options("active", "active", MapperOptions::new().value_type(ValueType::Boolean))
+        .map_field_with_options("name", "name", MapperOptions::new().value_type(ValueType::Text))
+        .map_field("note", "note");
+    mapper
+}
+
+fn assert_invalid(toql: &str, expected: &str) {
+    let mapper = setup_mapper();
+    let query = QueryParser::parse(toql).unwrap();
+    match SqlBuilder::new().build(&mapper, &query) {
+        Err(SqlBuilderError::FilterInvalid(s)) => assert_eq!(expected, s),
+        _ => panic!("Filter `{}` must be invalid", toql),
+    }
+}
+
+#[test]
+fn reject_arguments() {
+    assert_invalid("age eq 'abc'", "field `age` expects integer, got 'abc'");
+    assert_invalid("age bw 1 2.5", "field `age` expects integer, got 2.5");
+    assert_invalid("score in 1.5 'x'", "field `score` expects float, got 'x'");
+    assert_invalid("active eq 2", "field `active` expects boolean, got 2");
+}
+
+#[test]
+fn coerce_arguments() {
+    let mapper = setup_mapper();
+    let query = QueryParser::parse("age eq '5', score gt '1.5', active eq 'true', name gt 5, note eq 'abc'").unwrap();
+    let result = SqlBuilder::new().build(&mapper, &query).unwrap();
+
+    assert_eq!(
+        "SELECT age, score, active, name, note FROM User WHERE age = ? AND score > ? AND active = ? AND name > ? AND note = ?",
+        result.to_sql()
+    );
+    assert_eq!(*result.params(), ["5", "1.5", "1", "5", "abc"]);
+}
+
+#[test]
+fn unchecked_filters() {
+    let mapper = setup_mapper();
+    let query = QueryParser::parse("age lk '1%', age re '^1'").unwrap();
+    let result = SqlBuilder::new().build(&mapper, &query).unwrap();
+
+    assert_eq!(*result.params(), ["1%", "^1"]);
+}
@@ -11,6 +11,7 @@ use crate::codegen_mysql_query::GeneratedMysqlQuery;
 
 use syn::Ident;
 use syn::GenericArgument::Type;
+use heck::SnakeCase;
 
 
 
@@ -18,9 +19,41 @@ use syn::GenericArgument::Type;
 pub struct KeyPair {
     #[darling(rename="self")]
     pub this: String,
-    pub other: String
+    pub other: String,
+    #[darling(default)]
+    pub through: Option<String>, // Link table for many-to-many merges
+    #[darling(default)]
+    pub through_self: Option<String>, // Link column for this struct
+    #[darling(default)]
+    pub through_other: Option<String> // Link column for other struct
+}
+
+impl KeyPair {
+    // Link columns, default is the snake case struct name with `_id`, e.g. `link_user_id` for `LinkUser`.
+    // The names of the `self` and `other` key fields are not used.
+    pub fn through_columns(&self, this_struct: &str, other_struct: &str) -> (String, String) {
+        (
+            self.through_self.clone().unwrap_or(format!("{}_id", this_struct.to_snake_case())),
+            self.through_other.clone().unwrap_or(format!("{}_id", other_struct.to_snake_case())),
+        )
+    }
+}
+
+
+#[derive(Debug, FromMeta)]
+pub struct RecursiveArg {
+    #[darling(rename="self")]
+    pub this: String,
+    pub other: String, // Field with the key of the parent
+    #[darling(default)]
+    pub max_depth: Option<u32>
 }
 
+#[derive(Debug, FromMeta)]
+pub struct SelectionArg {
+    pub name: String,
+    pub fields: String, // Toql fields, separated by comma or whitespace
+}
 
 #[derive(Debug, FromMeta)]
 pub struct JoinArg {
@@ -62,9 +95,21 @@ pub struct ToqlField {
     pub sql: Option<String>,
     #[darling(multiple)]
     pub role: Vec<String>,
+    #[darling(default)]
+    pub filters: Option<String>, // Allowed filter operations, e.g. "EQ IN"
+    #[darling(default)]
+    pub unsortable: bool,
+    #[darling(default)]
+    pub aggregate: bool,
+    #[darling(default)]
+    pub like_prefix: Option<usize>,
      #[darling(default, multiple)]
     pub merge: Vec<KeyPair>,
     #[darling(default)]
+    pub key: Option<String>, // Field of merged struct, that is the key in a map
+    #[darling(default)]
+    pub recursive: Option<RecursiveArg>, // Tree of this struct
+    #[darling(default)]
     pub alias: Option<String>,
     #[darling(default)]
     pub table: Option<String> // Alternative sql table name
@@ -92,6 +137,32 @@ impl ToqlField {
         Some(types.0)
         }
     }
+    // Merged field, trees are merged too
+    pub fn is_merged(&self) -> bool {
+        !self.merge.is_empty() || self.recursive.is_some()
+    }
+    // Merge through a link table
+    pub fn merge_through(&self) -> Option<&KeyPair> {
+        self.merge.iter().find(|m| m.through.is_some())
+    }
+    // Type of a joined or merged struct, for maps the value type
+    pub fn merge_type(&self) -> Option<&Ident> {
+        if self.is_map() {
+            Self::generic_type(&self.ty, 1)
+        } else {
+            self.first_non_generic_type()
+        }
+    }
+    pub fn is_map(&self) -> bool {
+        let base = self.get_types().0;
+        base == "HashMap" || base == "BTreeMap"
+    }
+    pub fn is_collection(&self) -> bool {
+        let base = self.get_types().0;
+        ["Vec", "VecDeque", "LinkedList", "HashMap", "BTreeMap", "HashSet", "BTreeSet"]
+            .iter()
+            .any(|c| base == c)
+    }
     pub fn number_of_options<'a>(&'a self)-> u8 {
       let types= self.get_types();
         
@@ -152,6 +223,23 @@ impl ToqlField {
             }
          }
 
+     fn generic_type(ty: &syn::Type, n: usize) -> Option<&syn::Ident> {
+         match ty {
+             syn::Type::Path(syn::TypePath{qself:_, path}) => {
+                 match &path.segments[0].arguments {
+                     syn::PathArguments::AngleBracketed(syn::AngleBracketedGenericArguments { args, ..}) => {
+                         args.iter()
+                             .filter_map(|a| match a { Type(t) => Some(t), _ => None })
+                             .nth(n)
+                             .and_then(|t| Self::get_type(t))
+                     },
+                     _ => None
+                 }
+             },
+             _ => None
+         }
+     }
+
      fn get_type<'a>(ty: &'a  syn::Type) -> Option<&'a syn::Ident> {
          match ty {
              syn::Type::Path(syn::TypePath{qself:_, path}) => {
@@ -182,6 +270,7 @@ pub enum RenameCase {
 pub struct Toql {
     pub vis: syn::Visibility,
     pub ident: syn::Ident,
+    pub generics: syn::Generics,
     pub attrs: Vec<syn::Attribute>,
     #[darling(default)]
     pub tables: Option<RenameCase>,
@@ -189,6 +278,8 @@ pub struct Toql {
     pub table: Option<String>,
      #[darling(default)]
     pub columns: Option<RenameCase>,
+    #[darling(default)]
+    pub fields: Option<RenameCase>,
       #[darling(default)]
     pub alias: Option<String>,
      #[darling(default)]
@@ -197,11 +288,96 @@ pub struct Toql {
     pub skip_query: bool,
     #[darling(default)]
     pub skip_query_builder: bool,
+    #[darling(default)]
+    pub order: Option<String>,
+    #[darling(default, multiple)]
+    pub selection: Vec<SelectionArg>,
     pub data: darling::ast::Data<(), ToqlField>
 }
 
 
 
+impl Toql {
+    // Type of a struct field, used for merge keys
+    pub fn field_type(&self, rust_field: &str) -> Option<&syn::Type> {
+        self.data
+            .as_ref()
+            .take_struct()
+            .expect("Should never be enum")
+            .fields
+            .into_iter()
+            .find(|f| f.ident.as_ref().is_some_and(|i| i == rust_field))
+            .map(|f| &f.ty)
+    }
+    // Key of the struct is generated by the database, used for link rows
+    pub fn is_auto_key(&self, rust_field: &str) -> bool {
+        self.data
+            .as_ref()
+            .take_struct()
+            .expect("Should never be enum")
+            .fields
+            .into_iter()
+            .find(|f| f.ident.as_ref().is_some_and(|i| i == rust_field))
+            .is_some_and(|f| f.skip_inup)
+    }
+    // Type parameter of the struct
+    pub fn is_type_param(&self, ident: &Ident) -> bool {
+        self.generics.type_params().any(|p| &p.ident == ident)
+    }
+    // Type parameters of the struct, that are joined
+    pub fn joined_type_params(&self) -> Vec<&Ident> {
+        self.data
+            .as_ref()
+            .take_struct()
+            .expect("Should never be enum")
+            .fields
+            .into_iter()
+            .filter(|field| !field.sql_join.is_empty())
+            .filter_map(|field| field.first_non_generic_type())
+            .filter(|t| self.is_type_param(t))
+            .collect()
+    }
+    // Generics of the struct, type parameters that are joined get the bounds
+    pub fn bounded_generics(&self, join_bounds: proc_macro2::TokenStream) -> syn::Generics {
+        let mut generics = self.generics.clone();
+        for ty in self.joined_type_params() {
+            generics.make_where_clause().predicates.push(syn::parse_quote!(#ty : #join_bounds));
+        }
+        generics
+    }
+    // Name of the mapper in the cache, joined type parameters add their mapper, e.g. `Page<User>`.
+    // Other type parameters do not change the mapping and are left out.
+    pub fn type_name(&self) -> proc_macro2::TokenStream {
+        let struct_name = self.ident.to_string();
+        let mapped = self.joined_type_params();
+        let args: Vec<_> = self
+            .generics
+            .type_params()
+            .filter(|p| mapped.contains(&&p.ident))
+            .map(|p| {
+                let ident = &p.ident;
+                quote!( <#ident as toql::sql_mapper::Mapped>::type_name() )
+            })
+            .collect();
+        if args.is_empty() {
+            return quote!(String::from(#struct_name));
+        }
+        quote!( format!("{}<{}>", #struct_name, vec![ #(#args),* ].join(",")) )
+    }
+    // SQL column of a struct field without table alias
+    pub fn column_name(&self, rust_field: &str) -> String {
+        self.data
+            .as_ref()
+            .take_struct()
+            .expect("Should never be enum")
+            .fields
+            .into_iter()
+            .find(|f| f.ident.as_ref().is_some_and(|i| i == rust_field))
+            .and_then(|f| f.column.clone())
+            .unwrap_or_else(|| crate::util::rename(rust_field, &self.columns))
+    }
+}
+
 impl quote::ToTokens for Toql {
     fn to_tokens(&self, tokens: &mut  proc_macro2::TokenStream) {
 
@@ -221,14 +397,18 @@ impl quote::ToTokens for Toql {
          let Toql {
              vis: _,
              ident:_,
+             generics:_,
              attrs:_,
              tables:_,
              table:_,
              columns:_,
+             fields:_,
              alias:_,
              skip_indelup,
              skip_query,
              skip_query_builder,
+             order:_,
+             selection:_,
              ref data,
         } = *self;
 
@@ -271,10 +451,13 @@ impl quote::ToTokens for Toql {
 
                         #[cfg(feature = "mysqldb")]
                         mysql_query.add_path_loader(&self, field);
+                    } else if field.recursive.is_some() {
+                        #[cfg(feature = "mysqldb")]
+                        mysql_query.add_ignored_path(self, field);
 
                         #[cfg(feature = "mysqldb")]
-                        mysql_query.add_merge_predicates(&self, field);
-                    } 
+                        mysql_query.add_tree_loader(self, field);
+                    }
                     
                     #[cfg(feature = "mysqldb")]
                     mysql_query.add_mysql_deserialize(&self, field);
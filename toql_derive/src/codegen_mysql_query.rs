@@ -5,7 +5,6 @@
 
 use crate::annot::Toql;
 use crate::annot::ToqlField;
-use heck::MixedCase;
 use proc_macro2::Span;
 
 use syn::Ident;
@@ -13,27 +12,33 @@ use syn::Ident;
 
 pub(crate) struct GeneratedMysqlQuery<'a> {
     struct_ident: &'a Ident,
-    
+    generics: syn::Generics,
 
     mysql_deserialize_fields: Vec<proc_macro2::TokenStream>,
     path_loaders: Vec<proc_macro2::TokenStream>,
-    ignored_paths: Vec<proc_macro2::TokenStream>,
-    merge_one_predicates: Vec<proc_macro2::TokenStream>,
-    merge_many_predicates: Vec<proc_macro2::TokenStream>,
+    merge_query_functions: Vec<proc_macro2::TokenStream>, // Paths and queries of merges
+    ignored_paths: Vec<String>, // Toql paths of merged fields
     forward_joins: Vec<proc_macro2::TokenStream>,
     regular_fields: usize, // Impl for mysql::row::ColumnIndex
 }
 
 impl<'a> GeneratedMysqlQuery<'a> {
     pub(crate) fn from_toql(toql: &Toql) -> GeneratedMysqlQuery {
+        // Joined type parameters are deserialized from the same row
+        let mut generics = toql.generics.clone();
+        for ty in toql.joined_type_params() {
+            generics.make_where_clause().predicates.push(syn::parse_quote!(
+                #ty : toql::sql_mapper::Mapped + toql::mysql::row::FromResultRow< #ty >
+            ));
+        }
 
         GeneratedMysqlQuery {
             struct_ident: &toql.ident,
+            generics,
             mysql_deserialize_fields: Vec::new(),
             path_loaders: Vec::new(),
+            merge_query_functions: Vec::new(),
             ignored_paths: Vec::new(),
-            merge_one_predicates: Vec::new(),
-            merge_many_predicates: Vec::new(),
             forward_joins: Vec::new(),
             regular_fields: 0,
         }
@@ -42,9 +47,8 @@ impl<'a> GeneratedMysqlQuery<'a> {
     
     pub(crate) fn add_mysql_deserialize_skip_field(&mut self, field: &'a ToqlField) {
         let field_ident = &field.ident;
-        let field_type = &field.ty;
         self.mysql_deserialize_fields.push(quote!(
-             #field_ident : #field_type :: default()
+             #field_ident : Default::default()
         ));
     }
 
@@ -52,7 +56,7 @@ impl<'a> GeneratedMysqlQuery<'a> {
         let field_ident = &field.ident;
 
         // Regular fields
-        if field.sql_join.is_empty() && field.merge.is_empty() {
+        if field.sql_join.is_empty() && !field.is_merged() {
             self.regular_fields += 1;
 
             let assignment = if self.mysql_deserialize_fields.is_empty() {
@@ -99,138 +103,234 @@ impl<'a> GeneratedMysqlQuery<'a> {
         // Merged fields
         else {
             self.mysql_deserialize_fields.push(quote!(
-                #field_ident : Vec::new()
-            ));
-        }
-    }
-    pub(crate) fn add_merge_predicates(&mut self, _toql: &Toql, field: &'a ToqlField) {
-        let field_name = &field.ident.as_ref().unwrap().to_string();
-        let toql_field = field_name.to_mixed_case();
-        //let vk :Vec<&str>= field.merge.as_ref().expect("Merge self struct field <= other struct field").split("<=").collect();
-        //let toql_merge_field =format!("{}_{}",toql_field, vk.get(1).unwrap().trim().to_mixed_case());
-        //let merge_struct_key_ident = Ident::new( vk.get(0).unwrap().trim(), Span::call_site());
-
-        for merge in &field.merge {
-            let toql_merge_field = format!("{}_{}", toql_field, merge.other.to_mixed_case());
-            let merge_struct_key_ident = Ident::new(&merge.this, Span::call_site());
-            self.merge_one_predicates.push( quote!(
-                        query.and(toql::query::Field::from(#toql_merge_field).eq( _entity. #merge_struct_key_ident));
-            ));
-
-            self.merge_many_predicates.push( quote!(
-                   query.and(toql::query::Field::from(#toql_merge_field).ins(entities.iter().map(|entity| entity. #merge_struct_key_ident).collect()));
+                #field_ident : Default::default()
             ));
         }
     }
     pub(crate) fn add_ignored_path(&mut self, _toql: &Toql, field: &'a ToqlField) {
-        let field_name = &field.ident.as_ref().unwrap().to_string();
-        let toql_field = field_name.to_mixed_case();
+        let toql_field = crate::util::toql_field_name(_toql, field);
 
-        self.ignored_paths.push(quote!(
-                    .ignore_path( #toql_field)));
+        self.ignored_paths.push(toql_field);
     }
     pub(crate) fn add_path_loader(&mut self, _toql: &Toql, field: &'a ToqlField) {
         let struct_ident = &self.struct_ident;
         let field_ident = &field.ident;
-        let field_name = &field.ident.as_ref().unwrap().to_string();
-        let toql_field = field_name.to_mixed_case();
-        let merge_type = field.first_non_generic_type().unwrap();
+        let toql_field = crate::util::toql_field_name(_toql, field);
+        let merge_type = field.merge_type().unwrap();
 
         let merge_function = Ident::new(
             &format!("merge_{}", &field.ident.as_ref().unwrap()),
             Span::call_site(),
         );
+        let merge_query_function = Ident::new(
+            &format!("merge_{}_query", &field.ident.as_ref().unwrap()),
+            Span::call_site(),
+        );
 
+        let struct_name = struct_ident.to_string();
+
+        // Restrict merged structs to the keys of the loaded structs, one query for all of them.
+        // Toql names of the other struct come from its field builder, they may be renamed
+        let merge_predicates = field.merge.iter().map(|merge| {
+            let merge_struct_key_ident = Ident::new(&merge.this, Span::call_site());
+            let merge_other_ident = Ident::new(&merge.other, Span::call_site());
+            // Merges through a link table filter the key column of the link table
+            let merge_field = if merge.through.is_some() {
+                quote!( toql::query::Field::from(format!("{}_{}", merge_path, toql::sql_mapper::THROUGH_KEY)) )
+            } else {
+                quote!( #merge_type ::fields_from_path(format!("{}_", merge_path)). #merge_other_ident () )
+            };
+            quote!(
+                merge_query = merge_query.and( #merge_field .ins(entities.iter().map(|entity| entity. #merge_struct_key_ident).collect()));
+            )
+        });
+
+        // Merges through a link table join the link table to the mapper of the merged struct
+        let load_path = match field.merge_through() {
+            Some(through) => {
+                let merge_struct_name = merge_type.to_string();
+                let (through_self, through_other) = through.through_columns(&struct_name, &merge_struct_name);
+                let through_table = through.through.as_ref().unwrap();
+                let merge_other_ident = Ident::new(&through.other, Span::call_site());
+                let key_type = _toql.field_type(&through.this);
+                quote!(
+                    let merge_type_name = <#merge_type as toql::sql_mapper::Mapped>::type_name();
+                    let mut merge_mapper = mappers.get(&merge_type_name).ok_or( toql::error::ToqlError::MapperMissing(merge_type_name.clone()))?.clone();
+                    merge_mapper.join_through(#through_table, #through_other, & #merge_type ::fields(). #merge_other_ident ().to_string(), #through_self)?;
+                    let keyed = <#merge_type as toql::mysql::load::LoadPath>::load_path_with_key_from_mysql::< #key_type >(&merge_path, &merge_mapper, &merge_query, conn)?;
+                    let (keys, mut #field_ident) : (Vec< #key_type >, Vec< #merge_type >) = keyed.into_iter().unzip();
+                    <#merge_type as toql::mysql::load::LoadPath>::load_path_dependencies_from_mysql(&mut #field_ident, &merge_path, query, mappers, conn, depth + 1)?;
+                    let #field_ident = keys.into_iter().zip(#field_ident).collect();
+                )
+            }
+            None => quote!(
+                let mut #field_ident = <#merge_type as toql::mysql::load::LoadPath>::load_path_from_mysql(&merge_path, &merge_query, mappers, conn)?;
+                <#merge_type as toql::mysql::load::LoadPath>::load_path_dependencies_from_mysql(&mut #field_ident, &merge_path, query, mappers, conn, depth + 1)?;
+            ),
+        };
+
+        // Skip merges beyond the maximum merge depth, that require other roles or are not in the query.
+        let merge_query_doc = format!("Returns the path and query to load the merge `{}` of entities, that were loaded with `path`. \
+                                       Returns `None`, if the merge is not loaded.", toql_field);
+        self.merge_query_functions.push(quote!(
+            #[doc = #merge_query_doc]
+            pub fn #merge_query_function (entities: &[Self], path: &str, query: &toql::query::Query,
+                mappers: &toql::sql_mapper::SqlMapperCache, depth: usize) -> Option<(String, toql::query::Query)> {
+                if entities.is_empty() || depth >= mappers.max_merge_depth() {
+                    return None;
+                }
+                if !mappers.get(&<Self as toql::sql_mapper::Mapped>::type_name()).is_none_or(|m| m.merge_roles_valid(#toql_field, &query.roles)) {
+                    return None;
+                }
+                let merge_path = if path.is_empty() { String::from(#toql_field) } else { format!("{}_{}", path, #toql_field) };
+                if !query.contains_path(&merge_path) {
+                    return None;
+                }
+                let mut merge_query = query.clone();
+                #(#merge_predicates)*
+                Some((merge_path, merge_query))
+            }
+        ));
+
+        // Merged structs load their own merges before they are merged, because merging clones them
+        self.path_loaders.push( quote!(
+                if let Some((merge_path, merge_query)) = Self:: #merge_query_function (entities, path, query, mappers, depth) {
+                    #load_path
+                    Self:: #merge_function (entities, #field_ident);
+                }
+         ));
+    }
+    pub(crate) fn add_tree_loader(&mut self, _toql: &Toql, field: &'a ToqlField) {
+        let field_ident = &field.ident;
+        let toql_field = crate::util::toql_field_name(_toql, field);
+        let recursive = field.recursive.as_ref().unwrap();
+        let key_ident = Ident::new(&recursive.this, Span::call_site());
+        let parent_ident = Ident::new(&recursive.other, Span::call_site());
+        let tree_suffix = format!("_{}", toql_field);
+        let key_toql_field = _toql
+            .data
+            .as_ref()
+            .take_struct()
+            .expect("Should never be enum")
+            .fields
+            .into_iter()
+            .find(|f| f.ident.as_ref().is_some_and(|i| i == &recursive.this))
+            .map(|f| crate::util::toql_field_name(_toql, f))
+            .unwrap_or_else(|| recursive.this.clone());
+
+        // Load all descendants with one query and merge them into a tree.
+        // Nodes of a tree are already loaded with their descendants, so they skip the tree.
         self.path_loaders.push( quote!(
-                let #field_ident = #merge_type ::load_path_from_mysql(#toql_field, &query, mappers, conn)?;
-                #struct_ident :: #merge_function (&mut entities, #field_ident);
+                if path != #toql_field && !path.ends_with(#tree_suffix)
+                    && mappers.get(&<Self as toql::sql_mapper::Mapped>::type_name()).map_or(true, |m| m.merge_roles_valid(#toql_field, &query.roles)) {
+                    let tree_path = if path.is_empty() { String::from(#toql_field) } else { format!("{}_{}", path, #toql_field) };
+                    if query.contains_path(&tree_path) {
+                        let tree_query = query.clone().and( toql::query::Field::from(format!("{}_{}", tree_path, #key_toql_field))
+                            .fnc("DESCENDANTS", entities.iter().map(|entity| entity. #key_ident).collect()));
+                        let mut nodes = <Self as toql::mysql::load::LoadPath>::load_path_from_mysql(&tree_path, &tree_query, mappers, conn)?;
+                        <Self as toql::mysql::load::LoadPath>::load_path_dependencies_from_mysql(&mut nodes, &tree_path, query, mappers, conn, depth + 1)?;
+                        toql::merge::merge_tree(entities, nodes,
+                            |t| Option::from(t. #key_ident),
+                            |o| Option::from(o. #parent_ident),
+                            |t, o| toql::merge::MergeTarget::merge_item(&mut t. #field_ident, (), o));
+                    }
+                }
          ));
     }
     pub(crate) fn loader_functions(&self) -> proc_macro2::TokenStream {
         let struct_ident = &self.struct_ident;
-        let struct_name = &self.struct_ident.to_string();
+        let (impl_generics, ty_generics, where_clause) = self.generics.split_for_impl();
+        let struct_name = quote!(<Self as toql::sql_mapper::Mapped>::type_name());
         let path_loaders = &self.path_loaders;
+        let merge_query_functions = &self.merge_query_functions;
         let ignored_paths = &self.ignored_paths;
-        let merge_one_predicates = &self.merge_one_predicates;
-        let merge_many_predicates = &self.merge_many_predicates;
 
-        let load_dependencies_from_mysql = if path_loaders.is_empty() {
+        let load_path_dependencies_from_mysql = if path_loaders.is_empty() {
             quote!(
-                pub fn load_dependencies_from_mysql(mut _entities: &mut Vec< #struct_ident >,
-                _query: &toql::query::Query,  _mappers: &toql::sql_mapper::SqlMapperCache, _conn: &mut toql::mysql::mysql::Conn)
+                fn load_path_dependencies_from_mysql(_entities: &mut Vec<Self>, _path: &str,
+                _query: &toql::query::Query,  _mappers: &toql::sql_mapper::SqlMapperCache, _conn: &mut toql::mysql::mysql::Conn, _depth: usize)
                 -> toql::error::Result<()> { Ok(())}
             )
         } else {
             quote!(
-                pub fn load_dependencies_from_mysql(mut entities: &mut Vec< #struct_ident >,
-                query: &toql::query::Query,  mappers: &toql::sql_mapper::SqlMapperCache, conn: &mut toql::myql::mysql::Conn)
+                fn load_path_dependencies_from_mysql(entities: &mut Vec<Self>, path: &str,
+                query: &toql::query::Query,  mappers: &toql::sql_mapper::SqlMapperCache, conn: &mut toql::mysql::mysql::Conn, depth: usize)
                 -> toql::error::Result<()>
                 {
+                    if entities.is_empty() || depth >= mappers.max_merge_depth() {
+                        return Ok(());
+                    }
                     #(#path_loaders)*
                     Ok(())
                 }
             )
         };
 
-        let load_one_call_dependencies = if path_loaders.is_empty() {
-            quote!()
-        } else {
-            quote!(
-             // Restrict dependencies to parent entity
-                // query.and( "parent_child_id eq XX" )
-                let mut query = query.clone();
-                let _entity = entities.get(0).unwrap();
-                #(#merge_one_predicates)*
-                #struct_ident ::load_dependencies_from_mysql(&mut entities, &query, mappers, conn)?;
-            )
-        };
-        let load_many_call_dependencies = if path_loaders.is_empty() {
-            quote!()
-        } else {
-            quote!(
-                let mut query = query.clone();
-                // Resolve dependencies
-                // Restrict query to keys
-                #(#merge_many_predicates)*
+        quote!(
+            impl #impl_generics #struct_ident #ty_generics #where_clause {
 
-                #struct_ident ::load_dependencies_from_mysql(&mut entities, &query, mappers, &mut conn)?;
-            )
-        };
-        
-        
+                /// Loads the merged structs of all entities and their merged structs, one query for each merge.
+                pub fn load_dependencies_from_mysql(entities: &mut Vec<Self>,
+                query: &toql::query::Query,  mappers: &toql::sql_mapper::SqlMapperCache, conn: &mut toql::mysql::mysql::Conn)
+                -> toql::error::Result<()>
+                {
+                    <Self as toql::mysql::load::LoadPath>::load_path_dependencies_from_mysql(entities, "", query, mappers, conn, 0)
+                }
 
-        quote!(
-            impl #struct_ident {
+                #(#merge_query_functions)*
+            }
+
+            impl #impl_generics toql::mysql::load::LoadPath for #struct_ident #ty_generics #where_clause {
 
-                pub fn load_path_from_mysql(path: &str, query: &toql::query::Query, mappers: &toql::sql_mapper::SqlMapperCache,  conn: &mut toql::mysql::mysql::Conn)
-                -> toql::error::Result<std::vec::Vec< #struct_ident >>
+                fn load_path_from_mysql(path: &str, query: &toql::query::Query, mappers: &toql::sql_mapper::SqlMapperCache,  conn: &mut toql::mysql::mysql::Conn)
+                -> toql::error::Result<std::vec::Vec<Self>>
                 {
-                    let mapper = mappers.get( #struct_name ).ok_or( toql::error::ToqlError::MapperMissing(String::from(#struct_name)))?;
-                    let result = toql::sql_builder::SqlBuilder::new().build_path(path, mapper, &query)?;
+                    let mapper = mappers.get( &#struct_name ).ok_or( toql::error::ToqlError::MapperMissing(#struct_name))?;
+                    // Merges of this struct are loaded with their own path
+                    let result = toql::sql_builder::SqlBuilder::new()
+                    #( .ignore_path( format!("{}_{}", path.trim_end_matches('_'), #ignored_paths) ) )*
+                    .build_path(path, mapper, &query)?;
                     toql::log::info!("SQL `{}` with params {:?}", result.to_sql(), result.params());
                     if result.is_empty() {
                         Ok(vec![])
                     } else {
                         let entities_stmt = conn.prep_exec(result.to_sql(), result.params())?;
-                        let entities = toql::mysql::row::from_query_result::< #struct_ident >(entities_stmt)?;
+                        let entities = toql::mysql::row::from_query_result::<Self>(entities_stmt)?;
                         Ok(entities)
                     }
                 }
 
+                fn load_path_with_key_from_mysql<K: toql::mysql::mysql::prelude::FromValue>(path: &str, mapper: &toql::sql_mapper::SqlMapper, query: &toql::query::Query, conn: &mut toql::mysql::mysql::Conn)
+                -> toql::error::Result<std::vec::Vec<(K, Self)>>
+                {
+                    // Merges of this struct are loaded with their own path
+                    let result = toql::sql_builder::SqlBuilder::new()
+                    #( .ignore_path( format!("{}_{}", path.trim_end_matches('_'), #ignored_paths) ) )*
+                    .build_path(path, mapper, &query)?;
+                    toql::log::info!("SQL `{}` with params {:?}", result.to_sql(), result.params());
+                    if result.is_empty() {
+                        Ok(vec![])
+                    } else {
+                        let entities_stmt = conn.prep_exec(result.to_sql(), result.params())?;
+                        let entities = toql::mysql::row::from_query_result_with_key::<K, Self>(entities_stmt)?;
+                        Ok(entities)
+                    }
+                }
 
-                #load_dependencies_from_mysql
+                #load_path_dependencies_from_mysql
             }
-            impl toql::mysql::load::Load<#struct_ident> for #struct_ident
+
+            impl #impl_generics toql::mysql::load::Load<#struct_ident #ty_generics> for #struct_ident #ty_generics #where_clause
             {
                 fn load_one(query: &toql::query::Query, mappers: &toql::sql_mapper::SqlMapperCache, conn: &mut toql::mysql::mysql::Conn )
-                    -> toql::error::Result<# struct_ident>
+                    -> toql::error::Result<Self>
                 {
-                    let mapper= mappers.get( #struct_name).ok_or( toql::error::ToqlError::MapperMissing(String::from(#struct_name)))?;
+                    let mapper= mappers.get( &#struct_name).ok_or( toql::error::ToqlError::MapperMissing(#struct_name))?;
 
                   
 
                     let result = toql::sql_builder::SqlBuilder::new()
-                    #(#ignored_paths)*
+                    #( .ignore_path( #ignored_paths ) )*
                     .build(mapper, &query)?;
 
                     toql::log::info!("SQL `{}` with params {:?}", result.to_sql_for_mysql("", 0, 2), result.params());
@@ -238,7 +338,7 @@ impl<'a> GeneratedMysqlQuery<'a> {
 
 
                     let entities_stmt = conn.prep_exec(result.to_sql_for_mysql( "", 0, 2), result.params())?;
-                    let mut entities = toql::mysql::row::from_query_result::< #struct_ident >(entities_stmt)?;
+                    let mut entities = toql::mysql::row::from_query_result::<Self>(entities_stmt)?;
 
                     if entities.len() > 1 {
                         return Err(toql::error::ToqlError::NotUnique);
@@ -246,45 +346,33 @@ impl<'a> GeneratedMysqlQuery<'a> {
                         return Err(toql::error::ToqlError::NotFound);
                     }
 
-                    #load_one_call_dependencies
+                    Self::load_dependencies_from_mysql(&mut entities, &query, mappers, conn)?;
                     Ok(entities.pop().unwrap())
                 }
 
 
-                fn load_many(query: &toql::query::Query, mappers: &toql::sql_mapper::SqlMapperCache,
-                mut conn: &mut toql::mysql::mysql::Conn, count:bool, first:u64, max:u16)
-                -> toql::error::Result<(std::vec::Vec< #struct_ident >, Option<(u32, u32)>)> {
+                fn load_many_with_count(query: &toql::query::Query, mappers: &toql::sql_mapper::SqlMapperCache,
+                mut conn: &mut toql::mysql::mysql::Conn, count_strategy: Option<&dyn toql::mysql::count::CountStrategy>, first:u64, max:u16)
+                -> toql::error::Result<toql::mysql::load::LoadedMany<Self>> {
 
-                    let mapper = mappers.get( #struct_name).ok_or( toql::error::ToqlError::MapperMissing(String::from(#struct_name)))?;
+                    let mapper = mappers.get( &#struct_name).ok_or( toql::error::ToqlError::MapperMissing(#struct_name))?;
                     // load base entities
 
-                    let hint = String::from( if count {"SQL_CALC_FOUND_ROWS" }else{""});
-
                     let result = toql::sql_builder::SqlBuilder::new()
-                    #(#ignored_paths)*
+                    #( .ignore_path( #ignored_paths ) )*
                     .build(mapper, &query)?;
 
-                    toql::log::info!("SQL `{}` with params {:?}", result.to_sql_for_mysql(&hint, first, max), result.params());
-                    let entities_stmt = conn.prep_exec(result.to_sql_for_mysql( &hint, first, max), result.params())?;
-                    let mut entities = toql::mysql::row::from_query_result::< #struct_ident >(entities_stmt)?;
-                    let mut count_result = None;
+                    toql::log::info!("SQL `{}` with params {:?}", result.to_sql_for_mysql("", first, max), result.params());
+                    let entities_stmt = conn.prep_exec(result.to_sql_for_mysql( "", first, max), result.params())?;
+                    let mut entities = toql::mysql::row::from_query_result::<Self>(entities_stmt)?;
 
                     // Get count values
-                    if count {
-                        toql::log::info!("SQL `SELECT FOUND_ROWS();`");
-                        let r = conn.query("SELECT FOUND_ROWS();")?;
-                        let total_count = r.into_iter().next().unwrap().unwrap().get(0).unwrap();
-
-                        let result = toql::sql_builder::SqlBuilder::new().build_count(mapper, &query)?;
-                        toql::log::info!("SQL `{}` with params {:?}", result.to_sql_for_mysql("SQL_CALC_FOUND_ROWS", 0, 0), result.params());
-                        conn.prep_exec(result.to_sql_for_mysql("SQL_CALC_FOUND_ROWS", 0, 0), result.params())?; // Don't select any rows
-                        toql::log::info!("SQL `SELECT FOUND_ROWS();`");
-                        let r = conn.query("SELECT FOUND_ROWS();")?;
-                        let filtered_count = r.into_iter().next().unwrap().unwrap().get(0).unwrap();
-                        count_result = Some((total_count ,filtered_count))
-                    }
+                    let count_result = match count_strategy {
+                        Some(strategy) => Some(toql::mysql::count::count(strategy, mapper, &query, &result, conn)?),
+                        None => None,
+                    };
 
-                   #load_many_call_dependencies
+                    Self::load_dependencies_from_mysql(&mut entities, &query, mappers, conn)?;
 
                     Ok((entities, count_result))
                 }
@@ -297,6 +385,7 @@ impl<'a> GeneratedMysqlQuery<'a> {
 impl<'a> quote::ToTokens for GeneratedMysqlQuery<'a> {
     fn to_tokens(&self, tokens: &mut proc_macro2::TokenStream) {
         let struct_ident = self.struct_ident;
+        let (impl_generics, ty_generics, where_clause) = self.generics.split_for_impl();
         let loader = self.loader_functions();
 
         let mysql_deserialize_fields = &self.mysql_deserialize_fields;
@@ -311,16 +400,16 @@ impl<'a> quote::ToTokens for GeneratedMysqlQuery<'a> {
             #loader
 
 
-            impl toql :: mysql :: row:: FromResultRow < #struct_ident > for #struct_ident {
+            impl #impl_generics toql :: mysql :: row:: FromResultRow < #struct_ident #ty_generics > for #struct_ident #ty_generics #where_clause {
             fn forward_row(mut i : usize) -> usize {
                 i += #regular_fields ;
                 #(#forward_joins)*
                 i
             }
 
-            fn from_row_with_index ( mut row : & mut toql::mysql::mysql :: Row , i : &mut usize) -> std::result::Result < #struct_ident , toql::mysql::mysql :: error :: Error > {
+            fn from_row_with_index ( mut row : & mut toql::mysql::mysql :: Row , i : &mut usize) -> std::result::Result <Self, toql::mysql::mysql :: error :: Error > {
 
-                Ok ( #struct_ident {
+                Ok ( Self {
                     #(#mysql_deserialize_fields),*
 
                 })
@@ -15,6 +15,7 @@ use heck::SnakeCase;
 
 pub(crate) struct GeneratedToqlIndelup<'a> {
     struct_ident: &'a Ident,
+    generics: &'a syn::Generics,
     sql_table_ident: Ident,
 
     insert_columns: Vec<String>,
@@ -24,7 +25,10 @@ pub(crate) struct GeneratedToqlIndelup<'a> {
     delup_key_params_code: Vec<proc_macro2::TokenStream>,
     
     update_set_code: Vec<proc_macro2::TokenStream>,
-    
+
+    link_functions: Vec<proc_macro2::TokenStream>, // Link rows of merges through a link table
+    link_inserts: Vec<proc_macro2::TokenStream>,
+    link_deletes: Vec<proc_macro2::TokenStream>,
 }
 
 impl<'a> GeneratedToqlIndelup<'a> {
@@ -37,6 +41,7 @@ impl<'a> GeneratedToqlIndelup<'a> {
 
         GeneratedToqlIndelup {
             struct_ident: &toql.ident,
+            generics: &toql.generics,
             sql_table_ident:sql_table_ident,
             insert_columns: Vec::new(), 
             insert_params_code: Vec::new(), 
@@ -45,13 +50,16 @@ impl<'a> GeneratedToqlIndelup<'a> {
             delup_key_params_code: Vec::new(),
           
             update_set_code: Vec::new(),
-            
+
+            link_functions: Vec::new(),
+            link_inserts: Vec::new(),
+            link_deletes: Vec::new(),
         }
     }
 
     fn add_insert_field(&mut self, toql: &Toql, field: &'a ToqlField) {
 
-            if !field.merge.is_empty() || field.skip_inup || field.sql.is_some() {
+            if field.is_merged() || field.skip_inup || field.sql.is_some() {
                 return;
             }
             
@@ -98,6 +106,16 @@ impl<'a> GeneratedToqlIndelup<'a> {
             // Join field
             else {
                  let field_ident = field.ident.as_ref().unwrap();
+                 // Keys are read from fields of the joined struct, they are unknown for type parameters
+                 if toql.is_type_param(field.first_non_generic_type().unwrap()) {
+                    let error = "Joined type parameters cannot be inserted or updated, because their fields are unknown. \
+                                 Add `#[toql(skip_inup)]` to the field or `#[toql(skip_indelup)]` to the struct.";
+                    self.insert_params_code.push(quote_spanned! {
+                        field_ident.span() =>
+                        compile_error!( #error);
+                    });
+                    return;
+                 }
                  for j in &field.sql_join {
                       let auto_self_key= crate::util::rename(&field_ident.to_string(),&toql.columns);
                       let self_column = j.this.as_ref().unwrap_or(&auto_self_key);
@@ -120,7 +138,7 @@ impl<'a> GeneratedToqlIndelup<'a> {
 
     fn add_delup_field(&mut self, toql: &Toql, field: &'a ToqlField) {
 
-         if !field.merge.is_empty() || field.sql.is_some() {
+         if field.is_merged() || field.sql.is_some() {
             return;
          }
 
@@ -204,19 +222,87 @@ impl<'a> GeneratedToqlIndelup<'a> {
     }
 
 
+    fn add_link_functions(&mut self, toql: &Toql, field: &'a ToqlField) {
+        let through = match field.merge_through() {
+            Some(through) => through,
+            None => return,
+        };
+        let field_ident = field.ident.as_ref().unwrap();
+        let merge_struct_name = field.merge_type().unwrap().to_string();
+        let (through_self, through_other) = through.through_columns(&toql.ident.to_string(), &merge_struct_name);
+        let through_table = through.through.as_ref().unwrap();
+        let key_name = &through.this;
+        let key_ident = Ident::new(&through.this, Span::call_site());
+        let other_ident = Ident::new(&through.other, Span::call_site());
+
+        // Option<key> (Toql selectable)
+        let key_param = match toql.field_type(&through.this) {
+            Some(syn::Type::Path(p)) if p.path.segments[0].ident == "Option" => quote!(
+                entity. #key_ident .as_ref()
+                .ok_or(toql::error::ToqlError::ValueMissing(String::from(#key_name)))?.to_string()
+            ),
+            _ => quote!( entity. #key_ident .to_string() ),
+        };
+        let items = if field.is_map() {
+            quote!( entity. #field_ident .values() )
+        } else {
+            quote!( entity. #field_ident .iter() )
+        };
+
+        let insert_links_ident = Ident::new(&format!("insert_{}_links_sql", field_ident), Span::call_site());
+        let delete_links_ident = Ident::new(&format!("delete_{}_links_sql", field_ident), Span::call_site());
+        let insert_statement = format!("INSERT INTO {} ({},{}) VALUES", through_table, through_self, through_other);
+        let delete_statement = format!("DELETE FROM {} WHERE {} = ?", through_table, through_self);
+
+        self.link_functions.push(quote!(
+            /// Insert the link rows of a merge through a link table, returns tuple with SQL statement and SQL params or error.
+            pub fn #insert_links_ident (entity: &Self) -> toql::error::Result<(String, Vec<String>)> {
+                let key = #key_param;
+                let mut params: Vec<String> = Vec::new();
+                let mut insert_stmt = String::from( #insert_statement );
+                for item in #items {
+                    insert_stmt.push_str(" (?,?),");
+                    params.push(key.to_owned());
+                    params.push(item. #other_ident .to_string());
+                }
+                if params.is_empty() {
+                    return Ok((String::from("-- Nothing to insert"), params));
+                }
+                insert_stmt.pop(); // Remove trailing ,
+                Ok((insert_stmt, params))
+            }
+            /// Delete the link rows of a merge through a link table, returns tuple with SQL statement and SQL params or error.
+            pub fn #delete_links_ident (entity: &Self) -> toql::error::Result<(String, Vec<String>)> {
+                Ok((String::from( #delete_statement ), vec![ #key_param ]))
+            }
+        ));
+
+        // Auto generated keys are unknown before the insert
+        if !toql.is_auto_key(&through.this) {
+            self.link_inserts.push(quote!( Self:: #insert_links_ident (entity)? ));
+        }
+        self.link_deletes.push(quote!( Self:: #delete_links_ident (entity)? ));
+    }
+
     pub(crate) fn add_indelup_field(&mut self, toql: &Toql, field: &'a ToqlField) {
         
         self.add_insert_field(toql, field);
         self.add_delup_field(toql, field);
+        self.add_link_functions(toql, field);
 
     } 
 }
 impl<'a> quote::ToTokens for GeneratedToqlIndelup<'a> {
     fn to_tokens(&self, tokens: &mut proc_macro2::TokenStream) {
         let struct_ident = self.struct_ident;
+        let (impl_generics, ty_generics, where_clause) = self.generics.split_for_impl();
 
+        // The lifetime of the trait must not clash with lifetimes of the struct
+        let mut indelup_generics = self.generics.clone();
+        indelup_generics.params.insert(0, syn::parse_quote!('toql));
+        indelup_generics.make_where_clause().predicates.push(syn::parse_quote!(#struct_ident #ty_generics : 'toql));
+        let (indelup_impl_generics, _, indelup_where_clause) = indelup_generics.split_for_impl();
 
-  
 
         let delup_key_comparison = self
             .delup_keys
@@ -232,6 +318,27 @@ impl<'a> quote::ToTokens for GeneratedToqlIndelup<'a> {
         let insert_params_code = &self.insert_params_code;
         let delup_key_params_code = &self.delup_key_params_code;
 
+        let link_inserts = &self.link_inserts;
+        let link_deletes = &self.link_deletes;
+        let insert_links = if link_inserts.is_empty() {
+            quote!()
+        } else {
+            quote!(
+                fn insert_links_sql(entity: &Self) -> toql::error::Result<Vec<(String, Vec<String>)>> {
+                    Ok(vec![ #(#link_inserts),* ])
+                }
+            )
+        };
+        let delete_links = if link_deletes.is_empty() {
+            quote!()
+        } else {
+            quote!(
+                fn delete_links_sql(entity: &Self) -> toql::error::Result<Vec<(String, Vec<String>)>> {
+                    Ok(vec![ #(#link_deletes),* ])
+                }
+            )
+        };
+
         let mods = if self.delup_keys.is_empty() {
             quote!( /* Skipped code generation, because #[toql(delup_key)] is missing */ ) 
            /*  quote_spanned! {
@@ -262,15 +369,15 @@ impl<'a> quote::ToTokens for GeneratedToqlIndelup<'a> {
             let delete_many_statement = format!("DELETE {{alias}} FROM {} {{alias}} WHERE ", self.sql_table_ident);
 
             quote! {
-                impl<'a> toql::indelup::Indelup<'a, #struct_ident> for #struct_ident {
+                impl #indelup_impl_generics toql::indelup::Indelup<'toql, #struct_ident #ty_generics> for #struct_ident #ty_generics #indelup_where_clause {
                  
 
-                     fn insert_one_sql(entity: & #struct_ident) -> toql::error::Result<(String, Vec<String>)> {
+                     fn insert_one_sql(entity: &Self) -> toql::error::Result<(String, Vec<String>)> {
                         Self::insert_many_sql(std::iter::once(entity))
                     }
 
                      fn insert_many_sql<I>(entities: I)-> toql::error::Result<(String, Vec<String>)>
-                     where I: IntoIterator<Item=&'a #struct_ident> + 'a
+                     where I: IntoIterator<Item=&'toql Self> + 'toql
                      {
                     
 
@@ -284,7 +391,7 @@ impl<'a> quote::ToTokens for GeneratedToqlIndelup<'a> {
                             Ok((insert_stmt, params))
                     }
 
-                    fn update_one_sql(  entity: & #struct_ident)  -> toql::error::Result<(String, Vec<String>)>
+                    fn update_one_sql(  entity: &Self)  -> toql::error::Result<(String, Vec<String>)>
                     {
                         let alias= "t";
                         let mut params :Vec<String> = Vec::new();
@@ -308,7 +415,7 @@ impl<'a> quote::ToTokens for GeneratedToqlIndelup<'a> {
 
                     }
                     fn update_many_sql<I>(entities:I) -> toql::error::Result<(String, Vec<String>)>
-                    where I: IntoIterator<Item=&'a #struct_ident> + 'a + Clone
+                    where I: IntoIterator<Item=&'toql Self> + 'toql + Clone
                     {
                         let mut params: Vec<String> = Vec::new();
                         let mut update_stmt = String::from("UPDATE ");
@@ -354,7 +461,7 @@ impl<'a> quote::ToTokens for GeneratedToqlIndelup<'a> {
                         Ok((update_stmt, params))
                        
                     }
-                    fn delete_one_sql(  entity: & #struct_ident) -> toql::error::Result<(String, Vec<String>)>
+                    fn delete_one_sql(  entity: &Self) -> toql::error::Result<(String, Vec<String>)>
                     {
                         let alias="t";
                         let mut params :Vec<String>= Vec::new();
@@ -366,7 +473,7 @@ impl<'a> quote::ToTokens for GeneratedToqlIndelup<'a> {
                      }
 
                         fn delete_many_sql<I>(entities: I) -> toql::error::Result<(String, Vec<String>)>
-                        where I:  IntoIterator<Item=&'a #struct_ident> +'a
+                        where I:  IntoIterator<Item=&'toql Self> +'toql
                         {
                             let alias= "t";
                             let mut delete_stmt =format!(#delete_many_statement, alias = alias);
@@ -389,11 +496,25 @@ impl<'a> quote::ToTokens for GeneratedToqlIndelup<'a> {
                             Ok((delete_stmt, params))
                      }
 
+                     #insert_links
+                     #delete_links
                 }
 
             }
         };
         
+        let link_functions = &self.link_functions;
+        let mods = if link_functions.is_empty() {
+            mods
+        } else {
+            quote!(
+                #mods
+                impl #impl_generics #struct_ident #ty_generics #where_clause {
+                    #(#link_functions)*
+                }
+            )
+        };
+
         log::debug!("Source code for `{}`:\n{}",self.struct_ident, mods.to_string());
         tokens.extend(mods);
     }   
@@ -1,29 +1,84 @@
+use crate::annot::RenameCase;
 use crate::annot::Toql;
 use crate::annot::ToqlField;
 use quote::quote;
 
 use proc_macro2::Span;
 
-use heck::MixedCase;
 use heck::SnakeCase;
 use syn::Ident;
 
 pub(crate) struct GeneratedToqlMapper<'a> {
     struct_ident: &'a Ident,
+    generics: syn::Generics, // Type parameters that are joined must be mapped
+    type_name: proc_macro2::TokenStream,
    
     sql_table_name: String,
     sql_table_alias: String,
   
     merge_functions: Vec<proc_macro2::TokenStream>,
     field_mappings: Vec<proc_macro2::TokenStream>,
+    struct_mappings: Vec<proc_macro2::TokenStream>, // Default order and selections of top mapper
+    dependencies: Vec<String>,                      // Joined and merged structs
 }
 
 impl<'a> GeneratedToqlMapper<'a> {
     pub(crate) fn from_toql(toql: &Toql) -> GeneratedToqlMapper {
        
        let renamed_table = crate::util::rename(&toql.ident.to_string(), &toql.tables);
+
+        let mut struct_mappings = Vec::new();
+        // Underscores separate paths in Toql
+        match toql.fields {
+            Some(RenameCase::SnakeCase) | Some(RenameCase::ShoutySnakeCase) => {
+                let error = "Invalid attribute `fields`: Toql names must not contain `_`, use `mixedCase` or `CamelCase`.";
+                struct_mappings.push(quote!( compile_error!( #error); ));
+            }
+            _ => {}
+        }
+        if let Some(order) = &toql.order {
+            match Self::check_order(toql, order) {
+                Ok(()) => {
+                    let error = format!("Invalid order `{}` on struct `{}`", order, toql.ident);
+                    struct_mappings.push(quote!(
+                        mapper.default_order(&toql::query_parser::QueryParser::parse(#order).expect(#error));
+                    ));
+                }
+                Err(e) => {
+                    let error = format!("Invalid attribute `order`: {}.", e);
+                    struct_mappings.push(quote!( compile_error!( #error); ));
+                }
+            }
+        }
+        for selection in &toql.selection {
+            let name = &selection.name;
+            let fields: Vec<&str> = selection
+                .fields
+                .split(|c: char| c.is_whitespace() || c == ',')
+                .filter(|f| !f.is_empty())
+                .collect();
+            let invalid = if !crate::util::is_field_path(name.trim_start_matches('$')) || name.contains('_') {
+                Some(format!("invalid name `{}`", name))
+            } else if fields.is_empty() {
+                Some(String::from("no fields"))
+            } else {
+                fields.iter().find_map(|f| crate::util::check_toql_field(toql, f).err())
+            };
+            match invalid {
+                None => struct_mappings.push(quote!(
+                    mapper.selection(#name, &[ #(#fields),* ]);
+                )),
+                Some(e) => {
+                    let error = format!("Invalid attribute `selection`: {}.", e);
+                    struct_mappings.push(quote!( compile_error!( #error); ));
+                }
+            }
+        }
+
         GeneratedToqlMapper {
             struct_ident: &toql.ident,
+            generics: toql.bounded_generics(quote!(toql::sql_mapper::Mapped)),
+            type_name: toql.type_name(),
          
             sql_table_name: toql.table.clone().unwrap_or(renamed_table), //toql.ident.to_string(),
             sql_table_alias: toql
@@ -32,9 +87,26 @@ impl<'a> GeneratedToqlMapper<'a> {
                 .unwrap_or(toql.ident.to_string().to_snake_case()), //  toql.ident.to_string().to_snake_case(),
             merge_functions: Vec::new(),
             field_mappings: Vec::new(),
+            struct_mappings,
+            dependencies: Vec::new(),
         }
     }
 
+    // Parses the default order and checks its fields
+    fn check_order(toql: &Toql, order: &str) -> Result<(), String> {
+        let query = toql_core::query_parser::QueryParser::parse(order).map_err(|e| e.to_string())?;
+        let mut mapper = toql_core::sql_mapper::SqlMapper::new("");
+        mapper.default_order(&query);
+        let fields = mapper.default_order_fields();
+        if fields.is_empty() {
+            return Err(format!("`{}` orders no field, use `+` or `-`", order));
+        }
+        for (toql_field, _) in fields {
+            crate::util::check_toql_field(toql, toql_field)?;
+        }
+        Ok(())
+    }
+
     pub(crate) fn add_field_mapping(
         &mut self,
         toql: &Toql,
@@ -42,7 +114,15 @@ impl<'a> GeneratedToqlMapper<'a> {
     ) -> Result<(), ()> {
         let field_ident = &field.ident.as_ref().unwrap();
 
-        let toql_field = format!("{}", field_ident).to_mixed_case();
+        let toql_field = crate::util::toql_field_name(toql, field);
+        if toql_field.contains('_') {
+            let error = format!("Invalid Toql name `{}`: `_` separates paths, use attribute `field` for another name.", toql_field);
+            self.field_mappings.push(quote_spanned! {
+                field_ident.span() =>
+                compile_error!( #error);
+            });
+            return Err(());
+        }
         
         let renamed_sql_column = crate::util::rename(&field_ident.to_string(),&toql.columns);
         
@@ -51,15 +131,50 @@ impl<'a> GeneratedToqlMapper<'a> {
             None => &renamed_sql_column,
         };
 
+        // Multiple role attributes must all be satisfied
+        let role_expr = match field.role.len() {
+            0 => None,
+            1 => Some(field.role[0].to_owned()),
+            _ => Some(field.role.iter().map(|r| format!("({})", r)).collect::<Vec<String>>().join(",")),
+        };
+        if let Some(expr) = &role_expr {
+            if let Err(e) = toql_core::role_expr::RoleExpr::parse(expr) {
+                let error = format!("Invalid attribute `role`: {} in `{}`.", e, expr);
+                self.field_mappings.push(quote_spanned! {
+                    field_ident.span() =>
+                    compile_error!( #error);
+                });
+                return Err(());
+            }
+        }
+        let role_expr_ident = role_expr.as_ref().map(|e| {
+            let error = format!("Invalid role expression `{}` on field `{}`", e, field_ident);
+            quote!(toql::role_expr::RoleExpr::parse(#e).expect(#error))
+        });
+
+        // Only merged maps take their key from the merged struct
+        if field.key.is_some() && (field.merge.is_empty() || !field.is_map()) {
+            let error = "Invalid attribute `key`. \
+                         Only merges into a `HashMap` or `BTreeMap` have a key, remove `key` or use a map.";
+            self.field_mappings.push(quote_spanned! {
+                field_ident.span() =>
+                compile_error!( #error);
+            });
+            return Err(());
+        }
+
         // Joined field
         if  !field.sql_join.is_empty() {
            // let renamed_join_column = crate::util::rename_sql_column(&field_ident.to_string(),&toql.columns);
             
             let joined_struct_ident = field.first_non_generic_type();
             let joined_struct_name = field.first_non_generic_type().unwrap().to_string();
-            let default_join_alias = joined_struct_name.to_snake_case();
             let renamed_join_table =
                 crate::util::rename(&joined_struct_name, &toql.tables);
+            // Type parameters are joined with the table of their mapper and aliased by field
+            let generic_join = field.table.is_none() && toql.is_type_param(joined_struct_ident.unwrap());
+            let default_join_alias = if generic_join { field_ident.to_string().to_snake_case() } else { joined_struct_name.to_snake_case() };
+            let renamed_join_table = if generic_join { String::from("{table}") } else { renamed_join_table };
             let join_table = &field.table.as_ref().unwrap_or(&renamed_join_table);
             let join_alias = &field.alias.as_ref().unwrap_or(&default_join_alias); 
 
@@ -79,17 +194,57 @@ impl<'a> GeneratedToqlMapper<'a> {
            );
 
         
-            let join_clause = quote!(&format!( #format_string, alias = sql_alias));
+            let join_clause = if generic_join {
+                quote!(&format!( #format_string, alias = sql_alias, table = <#joined_struct_ident as toql::sql_mapper::Mapped>::table_name()))
+            } else {
+                quote!(&format!( #format_string, alias = sql_alias))
+            };
+            let join = match &role_expr_ident {
+                Some(role_expr_ident) => quote!(mapper.join_with_roles( #toql_field, #join_clause, #role_expr_ident );),
+                None => quote!(mapper.join( #toql_field, #join_clause );),
+            };
             self.field_mappings.push(quote! {
                 mapper.map_join::<#joined_struct_ident>(  #toql_field, #join_alias);
-                mapper.join( #toql_field, #join_clause );
+                #join
             });
+            self.add_dependency(joined_struct_name);
         } 
+        // Tree of this struct
+        else if let Some(recursive) = &field.recursive {
+            if field.merge_type().is_none_or(|t| t != &toql.ident) || !field.merge.is_empty() {
+                let error = format!("Invalid attribute `recursive`. \
+                                     Recursive fields must be a `Vec<{}>` or another collection of this struct without `merge`.", toql.ident);
+                self.field_mappings.push(quote_spanned! {
+                    field_ident.span() =>
+                    compile_error!( #error);
+                });
+                return Err(());
+            }
+            let key_column = toql.column_name(&recursive.this);
+            let parent_column = toql.column_name(&recursive.other);
+            let max_depth = match recursive.max_depth {
+                Some(d) => quote!(Some(#d)),
+                None => quote!(None),
+            };
+            let struct_name = quote!(&Self::type_name());
+            let tree_path = quote!(&format!("{}{}{}",toql_path,if toql_path.is_empty() {"" }else {"_"}, #toql_field));
+            let merge = match &role_expr_ident {
+                Some(role_expr_ident) => quote!(mapper.merge_with_roles(#tree_path, #struct_name, #role_expr_ident);),
+                None => quote!(mapper.merge(#tree_path, #struct_name);),
+            };
+            // Tree filters belong to the mapper of this struct, not to mappers that join it
+            self.field_mappings.push(quote! {
+                if toql_path.is_empty() {
+                    mapper.map_recursive(#key_column, #parent_column, #max_depth);
+                }
+                #merge
+            });
+        }
         // Regular field
         else if field.merge.is_empty() {
             let (base, _generic, _gegeneric) = field.get_types();
 
-            if base == "Vec" {
+            if field.is_collection() {
                 let error = format!("Missing attribute `merge`. \
                                      Tell Toql which field in this struct and the other struct share the same value. \
                                      Add `#[toql( merge(self=\"id\", other=\" {}_id\") )]`", toql.ident.to_string().to_snake_case());
@@ -99,20 +254,6 @@ impl<'a> GeneratedToqlMapper<'a> {
                 });
                 return Err(());
             }
-            if base == "VecDeque"
-                || base == "LinkedList"
-                || base == "HashMap"
-                || base == "BTreeMap"
-                || base == "HashSet"
-                || base == "BTreeSet"
-            {
-                // TODO Get types as ident to highlight type and not variable name
-                self.field_mappings.push(quote_spanned! {
-                    field_ident.span() =>
-                    compile_error!("Invalid collection type. Only `std::vec::Vec` is supported.");
-                });
-                return Err(());
-            }
 
             let countfilter_ident = if field.count_filter {
                 quote!( .count_filter(true))
@@ -135,11 +276,57 @@ impl<'a> GeneratedToqlMapper<'a> {
                 quote!()
             };
 
-            let roles = &field.role;
-            let roles_ident = if roles.is_empty() {
+            let value_type_ident = match field.first_non_generic_type().map(|t| t.to_string()).as_deref() {
+                Some("bool") => quote!( .value_type(toql::sql_mapper::ValueType::Boolean)),
+                Some("i8") | Some("i16") | Some("i32") | Some("i64") | Some("i128") | Some("isize")
+                | Some("u8") | Some("u16") | Some("u32") | Some("u64") | Some("u128") | Some("usize") => {
+                    quote!( .value_type(toql::sql_mapper::ValueType::Integer))
+                }
+                Some("f32") | Some("f64") => quote!( .value_type(toql::sql_mapper::ValueType::Float)),
+                Some("String") | Some("str") | Some("char") => quote!( .value_type(toql::sql_mapper::ValueType::Text)),
+                _ => quote!(),
+            };
+
+            let filters_ident = match &field.filters {
+                Some(filters) => {
+                    let filters: Vec<String> = filters
+                        .split(|c: char| c.is_whitespace() || c == ',')
+                        .filter(|f| !f.is_empty())
+                        .map(|f| f.to_uppercase())
+                        .collect();
+                    let valid = [
+                        "EQ", "EQN", "NE", "NEN", "GT", "GE", "LT", "LE", "LK", "BW", "IN", "OUT", "RE", "FN",
+                    ];
+                    if let Some(invalid) = filters.iter().find(|f| !valid.contains(&f.as_str())) {
+                        let error = format!("Invalid filter `{}`. Valid filters are: {}.", invalid, valid.join(", "));
+                        self.field_mappings.push(quote_spanned! {
+                            field_ident.span() =>
+                            compile_error!( #error);
+                        });
+                        return Err(());
+                    }
+                    quote!( .restrict_filters( [ #(String::from(#filters)),* ].iter().cloned().collect()))
+                }
+                None => quote!(),
+            };
+            let sortable_ident = if field.unsortable {
+                quote!( .sortable(false))
+            } else {
                 quote!()
+            };
+            let aggregate_ident = if field.aggregate {
+                quote!( .aggregate(true))
             } else {
-                quote! { .restrict_roles( [ #(String::from(#roles)),* ].iter().cloned().collect())  }
+                quote!()
+            };
+            let like_prefix_ident = match field.like_prefix {
+                Some(like_prefix) => quote!( .min_like_prefix( #like_prefix )),
+                None => quote!(),
+            };
+
+            let roles_ident = match &role_expr_ident {
+                Some(role_expr_ident) => quote!( .restrict_role_expr( #role_expr_ident )),
+                None => quote!(),
             };
 
             let field_sql = &field.sql;
@@ -151,16 +338,66 @@ impl<'a> GeneratedToqlMapper<'a> {
 
             self.field_mappings.push(quote! {
                                         mapper.map_field_with_options(&format!("{}{}{}",toql_path,if toql_path.is_empty() {"" }else {"_"}, #toql_field), 
-                                        #sql_mapping,toql::sql_mapper::MapperOptions::new() #select_ident #countfilter_ident #countselect_ident #ignore_wc_ident #roles_ident);
+                                        #sql_mapping,toql::sql_mapper::MapperOptions::new() #select_ident #countfilter_ident #countselect_ident #ignore_wc_ident #value_type_ident #filters_ident #sortable_ident #like_prefix_ident #aggregate_ident #roles_ident);
                                     }
                         );
         }
+        // Merged field
+        else {
+            if field.is_map() && field.key.is_none() {
+                let error = "Missing attribute `key`. \
+                             Tell Toql which field in the merged struct is the map key. \
+                             Add `#[toql( key=\"id\")]`";
+                self.field_mappings.push(quote_spanned! {
+                    field_ident.span() =>
+                    compile_error!( #error);
+                });
+                return Err(());
+            }
+            if field.merge_through().is_some()
+                && (field.merge.len() > 1 || toql.field_type(&field.merge[0].this).is_none())
+            {
+                let error = "Invalid attribute `merge` with `through`. \
+                             Merges through a link table need exactly one `merge` with a field of this struct in `self`.";
+                self.field_mappings.push(quote_spanned! {
+                    field_ident.span() =>
+                    compile_error!( #error);
+                });
+                return Err(());
+            }
+            // Merges compare fields of the merged struct, they are unknown for type parameters
+            if toql.is_type_param(field.merge_type().unwrap()) {
+                let error = "Invalid attribute `merge`. \
+                             Merged structs cannot be type parameters, because their fields are unknown. Use a concrete type.";
+                self.field_mappings.push(quote_spanned! {
+                    field_ident.span() =>
+                    compile_error!( #error);
+                });
+                return Err(());
+            }
+            let merge_struct_name = field.merge_type().unwrap().to_string();
+            let merge_path = quote!(&format!("{}{}{}",toql_path,if toql_path.is_empty() {"" }else {"_"}, #toql_field));
+            self.field_mappings.push(match &role_expr_ident {
+                Some(role_expr_ident) => quote! {
+                    mapper.merge_with_roles(#merge_path, #merge_struct_name, #role_expr_ident);
+                },
+                None => quote! {
+                    mapper.merge(#merge_path, #merge_struct_name);
+                },
+            });
+            self.add_dependency(merge_struct_name);
+        }
         Ok(())
     }
 
+    fn add_dependency(&mut self, struct_name: String) {
+        if !self.dependencies.contains(&struct_name) {
+            self.dependencies.push(struct_name);
+        }
+    }
+
     pub(crate) fn add_merge_function(&mut self, _toql: &Toql, field: &'a ToqlField) {
-        let struct_ident = self.struct_ident;
-        let joined_struct_ident = field.first_non_generic_type();
+        let joined_struct_ident = field.merge_type();
         let field_ident = &field.ident.as_ref().unwrap();
         let function_ident = syn::Ident::new(&format!("merge_{}", field_ident), Span::call_site());
 
@@ -188,12 +425,34 @@ impl<'a> GeneratedToqlMapper<'a> {
        };
 
 
+        // Merges through a link table get the key of this struct together with the merged struct
+        let (other_type, other_fnc, item) = match field.merge_through() {
+            Some(through) => {
+                let key_type = _toql.field_type(&through.this);
+                (
+                    quote!( ( #key_type , #joined_struct_ident ) ),
+                    quote!( Option::from( o . 0 ) ),
+                    quote!( o . 1 ),
+                )
+            }
+            None => (quote!( #joined_struct_ident ), other_fnc, quote!(o)),
+        };
+
+        // Maps take their key from the merged struct
+        let assign_fnc = match &field.key {
+            Some(key) if field.is_map() => {
+                let key_ident = Ident::new(key, Span::call_site());
+                quote!( { let o = #item ; toql :: merge :: MergeTarget :: merge_item ( & mut t . #field_ident , o . #key_ident . clone ( ) , o ) } )
+            }
+            _ => quote!( toql :: merge :: MergeTarget :: merge_item ( & mut t . #field_ident , ( ) , #item ) ),
+        };
+
         self.merge_functions.push(quote!(
-            pub fn #function_ident ( t : & mut Vec < #struct_ident > , o : Vec < #joined_struct_ident > ) {
+            pub fn #function_ident ( t : & mut Vec < Self > , o : Vec < #other_type > ) {
                     toql :: merge :: merge ( t , o ,
                     | t | #self_fnc ,
                     | o | #other_fnc ,
-                    | t , o | t . #field_ident . push ( o )
+                    | t , o | #assign_fnc
                     ) ;
             }
          ));
@@ -205,34 +464,66 @@ impl<'a> quote::ToTokens for GeneratedToqlMapper<'a> {
     fn to_tokens(&self, tokens: &mut proc_macro2::TokenStream) {
         let struct_ident = self.struct_ident;
         let struct_name= format!("{}", struct_ident);
+        let type_name = &self.type_name;
+        let (impl_generics, ty_generics, where_clause) = self.generics.split_for_impl();
         let sql_table_name =  &self.sql_table_name;
         let sql_table_alias = &self.sql_table_alias;
                
         let merge_functions = &self.merge_functions;
 
         let field_mappings = &self.field_mappings;
+        let struct_mappings = &self.struct_mappings;
+        let dependency_idents = self
+            .dependencies
+            .iter()
+            .map(|d| Ident::new(d, Span::call_site()))
+            .collect::<Vec<_>>();
+        // Default implementation registers nothing
+        let register_dependencies = if dependency_idents.is_empty() {
+            quote!()
+        } else {
+            quote!(
+                fn register_dependencies(cache: &mut toql::sql_mapper::SqlMapperCache) {
+                    #( cache.register::<#dependency_idents>(); )*
+                }
+            )
+        };
+
+        // Catch mapping errors early in debug builds
+        let debug_validation = quote!(
+            #[cfg(debug_assertions)]
+            {
+                if let Err(errors) = m.validate() {
+                    let messages : Vec<String> = errors.iter().map(|e| e.to_string()).collect();
+                    panic!("Invalid mapper for `{}`: {}", #struct_name, messages.join(", "));
+                }
+            }
+        );
 
         let builder = quote!(
 
-            impl toql::sql_mapper::Mapped for #struct_ident {
+            impl #impl_generics toql::sql_mapper::Mapped for #struct_ident #ty_generics #where_clause {
                 fn insert_new_mapper(cache: &mut toql::sql_mapper::SqlMapperCache) ->  &mut toql::sql_mapper::SqlMapper {
                     let m = Self::new_mapper( #sql_table_alias);
-                    cache.insert( String::from( #struct_name ), m);
-                    cache.get_mut( #struct_name ).unwrap()
+                    cache.insert( Self::type_name(), m);
+                    Self::register_dependencies(cache);
+                    cache.get_mut( &Self::type_name() ).unwrap()
                 }
                 
                  fn insert_new_mapper_with_handler<H>(cache: &mut toql::sql_mapper::SqlMapperCache,  handler: H) -> &mut toql::sql_mapper::SqlMapper   // Create new SQL Mapper and insert into mapper cache
                   where  H: 'static + toql::sql_mapper::FieldHandler + Send + Sync 
                  {
                     let m = Self::new_mapper_with_handler( #sql_table_alias, handler);
-                    cache.insert( String::from( #struct_name ), m);
-                    cache.get_mut( #struct_name ).unwrap()
+                    cache.insert( Self::type_name(), m);
+                    Self::register_dependencies(cache);
+                    cache.get_mut( &Self::type_name() ).unwrap()
                   }
 
                 fn new_mapper(table_alias: &str) -> toql::sql_mapper::SqlMapper {
                     let s = format!("{} {}",#sql_table_name, table_alias );
                     let mut m = toql::sql_mapper::SqlMapper::new( if table_alias.is_empty() { #sql_table_name } else { &s });
                     Self::map(&mut m, "", table_alias);
+                    #debug_validation
                     m
                 }
                 fn new_mapper_with_handler<H>(table_alias: &str,  handler: H) -> toql::sql_mapper::SqlMapper 
@@ -241,15 +532,29 @@ impl<'a> quote::ToTokens for GeneratedToqlMapper<'a> {
                     let s = format!("{} {}",#sql_table_name, table_alias );
                     let mut m = toql::sql_mapper::SqlMapper::new_with_handler( if table_alias.is_empty() { #sql_table_name } else { &s }, handler);
                     Self::map(&mut m, "", table_alias);
+                    #debug_validation
                     m
                 }
 
                 fn map(mapper: &mut toql::sql_mapper::SqlMapper, toql_path: &str, sql_alias: &str) {
                     #(#field_mappings)*
+                    if toql_path.is_empty() {
+                        #(#struct_mappings)*
+                    }
+                }
+
+                fn type_name() -> String {
+                    #type_name
                 }
+
+                fn table_name() -> String {
+                    String::from( #sql_table_name )
+                }
+
+                #register_dependencies
             }
 
-            impl #struct_ident {
+            impl #impl_generics #struct_ident #ty_generics #where_clause {
 
                 #(#merge_functions)*
 
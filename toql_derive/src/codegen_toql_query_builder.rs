@@ -4,11 +4,12 @@ use crate::annot::Toql;
 use crate::annot::ToqlField;
 use quote::quote;
 use proc_macro2::Span;
-use heck::MixedCase;
 use syn::Ident;
 
 pub(crate) struct GeneratedToqlQueryBuilder<'a> {
     struct_ident: &'a Ident,
+    generics: syn::Generics,
+    joined_type_params: Vec<&'a Ident>, // Generics of the fields struct
     vis: &'a syn::Visibility,
    // sql_table_name: String,
    // sql_table_alias: String,
@@ -21,8 +22,17 @@ pub(crate) struct GeneratedToqlQueryBuilder<'a> {
 impl<'a> GeneratedToqlQueryBuilder<'a> {
     pub(crate) fn from_toql(toql: &Toql) -> GeneratedToqlQueryBuilder {
        
+        let mut joined_type_params: Vec<&Ident> = Vec::new();
+        for t in toql.joined_type_params() {
+            if !joined_type_params.contains(&t) {
+                joined_type_params.push(t);
+            }
+        }
+
         GeneratedToqlQueryBuilder {
             struct_ident: &toql.ident,
+            generics: toql.bounded_generics(quote!(toql::fields_type::FieldsType)),
+            joined_type_params,
             vis: &toql.vis,
          
             builder_fields_struct: syn::Ident::new(
@@ -45,8 +55,8 @@ impl<'a> GeneratedToqlQueryBuilder<'a> {
             self.build_wildcard = false;
         }
 
-        if field.sql_join.is_empty() && field.merge.is_empty() {
-            let toql_field = format!("{}", field_ident.as_ref().unwrap()).to_mixed_case();
+        if field.sql_join.is_empty() && !field.is_merged() {
+            let toql_field = crate::util::toql_field_name(_toql, field);
             self.builder_fields.push(quote!(
                 #vis fn #field_ident (mut self) -> toql :: query :: Field {
                     self . 0 . push_str ( #toql_field ) ;
@@ -54,12 +64,9 @@ impl<'a> GeneratedToqlQueryBuilder<'a> {
                 }
             ));
         } else {
-            let toql_field = format!(
-                "{}_",
-                format!("{}", field_ident.as_ref().unwrap()).to_mixed_case()
-            );
+            let toql_field = format!("{}_", crate::util::toql_field_name(_toql, field));
 
-            let field_type = field.first_non_generic_type().unwrap();
+            let field_type = field.merge_type().unwrap();
 
         
             let type_ident: &Ident = field_type;
@@ -68,7 +75,7 @@ impl<'a> GeneratedToqlQueryBuilder<'a> {
             self.builder_fields.push(quote!(
                         #vis fn #field_ident (mut self) -> #path_fields_struct {
                             self.0.push_str(#toql_field);
-                            #path_fields_struct ::from_path(self.0)
+                            < #type_ident as toql::fields_type::FieldsType>::fields_from_path(self.0)
                         }
             ));
         }
@@ -82,6 +89,20 @@ impl<'a> quote::ToTokens for GeneratedToqlQueryBuilder<'a> {
         let builder_fields_struct = &self.builder_fields_struct;
         let builder_fields = &self.builder_fields;
         let struct_ident = &self.struct_ident;
+        let (impl_generics, ty_generics, where_clause) = self.generics.split_for_impl();
+
+        // Fields of joined type parameters are only known through their fields type
+        let joined_type_params = &self.joined_type_params;
+        let (fields_generics, fields_where, phantom, phantom_init) = if joined_type_params.is_empty() {
+            (quote!(), quote!(), quote!(), quote!())
+        } else {
+            (
+                quote!( < #(#joined_type_params),* > ),
+                quote!( where #(#joined_type_params : toql::fields_type::FieldsType),* ),
+                quote!( , std::marker::PhantomData<( #(#joined_type_params,)* )> ),
+                quote!( , std::marker::PhantomData ),
+            )
+        };
 
         let wildcard = if self.build_wildcard {
             quote!(
@@ -95,20 +116,21 @@ impl<'a> quote::ToTokens for GeneratedToqlQueryBuilder<'a> {
 
         let builder = quote!(
 
-            impl toql::fields_type::FieldsType for #struct_ident {
-                type FieldsType = #builder_fields_struct ;
+            impl #impl_generics toql::fields_type::FieldsType for #struct_ident #ty_generics #where_clause {
+                type FieldsType = #builder_fields_struct #fields_generics ;
+                fn fields_from_path ( path : String ) -> Self::FieldsType { #builder_fields_struct :: from_path ( path ) }
             }
 
-            impl #struct_ident {
-                #vis fn fields ( ) -> #builder_fields_struct { #builder_fields_struct :: new ( ) }
-                #vis fn fields_from_path ( path : String ) -> #builder_fields_struct { #builder_fields_struct :: from_path ( path ) }
+            impl #impl_generics #struct_ident #ty_generics #where_clause {
+                #vis fn fields ( ) -> #builder_fields_struct #fields_generics { #builder_fields_struct :: new ( ) }
+                #vis fn fields_from_path ( path : String ) -> #builder_fields_struct #fields_generics { #builder_fields_struct :: from_path ( path ) }
             }
 
 
-            #vis struct #builder_fields_struct ( String ) ;
-            impl #builder_fields_struct {
+            #vis struct #builder_fields_struct #fields_generics ( String #phantom ) ;
+            impl #fields_generics #builder_fields_struct #fields_generics #fields_where {
                 #vis fn new ( ) -> Self { Self :: from_path ( String :: from ( "" ) ) }
-                #vis fn from_path ( path : String ) -> Self { Self ( path ) }
+                #vis fn from_path ( path : String ) -> Self { Self ( path #phantom_init ) }
                 #(#builder_fields)*
 
                 #wildcard
@@ -58,6 +58,80 @@ mod codegen_mysql_query;
 mod util;
 
 /// Derive to add Toql functionality to your struct.
+///
+/// Attributes are checked at compile time. An invalid role expression does not compile:
+/// ```compile_fail
+/// use toql::derive::Toql;
+///
+/// #[derive(Toql)]
+/// struct User {
+///   id : u64,
+///   #[toql(role = "admin;;owner")]
+///   email : Option<String>
+/// }
+/// ```
+///
+/// Likewise the default order and the fields of selections are checked:
+/// ```compile_fail
+/// use toql::derive::Toql;
+///
+/// #[derive(Toql)]
+/// #[toql(order = "+1 usrname")]
+/// struct User {
+///   id : u64,
+///   username : Option<String>
+/// }
+/// ```
+/// ```compile_fail
+/// use toql::derive::Toql;
+///
+/// #[derive(Toql)]
+/// #[toql(selection(name = "std", fields = "id, usrname"))]
+/// struct User {
+///   id : u64,
+///   username : Option<String>
+/// }
+/// ```
+///
+/// Toql names must not contain `_`, because it separates paths:
+/// ```compile_fail
+/// use toql::derive::Toql;
+///
+/// #[derive(Toql)]
+/// struct User {
+///   id : u64,
+///   #[toql(field = "user_name")]
+///   username : Option<String>
+/// }
+/// ```
+/// ```compile_fail
+/// use toql::derive::Toql;
+///
+/// #[derive(Toql)]
+/// #[toql(fields = "snake_case")]
+/// struct User {
+///   id : u64,
+///   username : Option<String>
+/// }
+/// ```
+///
+/// Only merges into maps have a `key`:
+/// ```compile_fail
+/// use toql::derive::Toql;
+///
+/// #[derive(Toql)]
+/// struct User {
+///   id : u64,
+///   #[toql(merge(self = "id", other = "user_id"), key = "id")]
+///   phones : Vec<Phone>
+/// }
+///
+/// #[derive(Toql)]
+/// struct Phone {
+///   id : u64,
+///   user_id : u64
+/// }
+/// ```
 #[proc_macro_derive(Toql, attributes(toql))]
 pub fn toql_derive(input: TokenStream) -> TokenStream {
 
@@ -6,6 +6,8 @@ use heck::MixedCase;
 use heck::SnakeCase;
 
 use crate::annot::RenameCase;
+use crate::annot::Toql;
+use crate::annot::ToqlField;
 
 pub(crate) fn rename(string: &str, renaming: &Option<RenameCase>) -> String{
         
@@ -17,3 +19,52 @@ pub(crate) fn rename(string: &str, renaming: &Option<RenameCase>) -> String{
                 None => string.to_owned()
            }
     }
+
+// Toql name of a field: the `field` attribute or the renamed Rust identifier, mixedCase by default
+pub(crate) fn toql_field_name(toql: &Toql, field: &ToqlField) -> String {
+    if let Some(name) = &field.field {
+        return name.to_owned();
+    }
+    let field_name = field.ident.as_ref().unwrap().to_string();
+    match toql.fields {
+        None => field_name.to_mixed_case(),
+        _ => rename(&field_name, &toql.fields),
+    }
+}
+
+// Toql name or path as in the query language: `name` or `author_name`
+pub(crate) fn is_field_path(path: &str) -> bool {
+    path.split('_').all(|name| {
+        let mut chars = name.chars();
+        chars.next().is_some_and(|c| c.is_ascii_alphabetic()) && chars.all(|c| c.is_ascii_alphanumeric())
+    })
+}
+
+// Checks a Toql field from a struct attribute. Paths must start with a joined or merged field,
+// the fields of the joined and merged structs are unknown here.
+pub(crate) fn check_toql_field(toql: &Toql, toql_field: &str) -> Result<(), String> {
+    if !is_field_path(toql_field) {
+        return Err(format!("invalid field `{}`", toql_field));
+    }
+    let mut names = toql_field.splitn(2, '_');
+    let name = names.next().unwrap_or_default();
+    let is_path = names.next().is_some();
+    let field = toql
+        .data
+        .as_ref()
+        .take_struct()
+        .expect("Should never be enum")
+        .fields
+        .into_iter()
+        .find(|f| toql_field_name(toql, f) == name);
+    match field {
+        None => Err(format!("unknown field `{}`", name)),
+        Some(f) if is_path && f.sql_join.is_empty() && !f.is_merged() => {
+            Err(format!("field `{}` has no fields", name))
+        }
+        Some(f) if !is_path && (!f.sql_join.is_empty() || f.is_merged()) => {
+            Err(format!("`{}` is a path, use a field of it, e.g. `{}_id`", name, name))
+        }
+        _ => Ok(()),
+    }
+}
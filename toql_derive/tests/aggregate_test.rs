@@ -0,0 +1,28 @@
+#![allow(dead_code)] // Structs are only mapped
+
+use toql::query_parser::QueryParser;
+use toql::sql_builder::SqlBuilder;
+use toql::sql_mapper::SqlMapper;
+use toql_derive::Toql;
+
+#[derive(Debug, Clone, Toql)]
+#[toql(table = "Order", skip_indelup)]
+struct UserTotal {
+    user_id: u64,
+
+    #[toql(sql = "SUM(..amount)", aggregate)]
+    total: Option<u64>,
+}
+
+#[test]
+fn aggregate_filter() {
+    let mapper = SqlMapper::map::<UserTotal>("o");
+    let query = QueryParser::parse("total gt 100").unwrap();
+    let result = SqlBuilder::new().build(&mapper, &query).unwrap();
+
+    assert_eq!(
+        "SELECT o.user_id, SUM(o.amount) FROM Order o GROUP BY o.user_id HAVING SUM(o.amount) > ?",
+        result.to_sql()
+    );
+    assert_eq!(*result.params(), ["100"]);
+}
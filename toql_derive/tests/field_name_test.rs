@@ -0,0 +1,73 @@
+#![allow(dead_code)] // Structs are only mapped
+
+use toql::query::Query;
+use toql::sql_builder::SqlBuilder;
+use toql::sql_mapper::SqlMapper;
+use toql_derive::Toql;
+
+#[derive(Debug, Clone, Toql)]
+#[toql(fields = "CamelCase", skip_indelup)]
+struct NamedBook {
+    id: u8,
+
+    #[toql(field = "title")]
+    long_title: Option<String>,
+
+    page_count: Option<u8>,
+
+    #[toql(sql_join(self = "author_id", other = "id"))]
+    book_author: Option<NamedUser>,
+
+    #[toql(merge(self = "id", other = "book_id"))]
+    book_chapters: Vec<NamedChapter>,
+}
+
+#[derive(Debug, Clone, Toql)]
+#[toql(skip_indelup)]
+struct NamedUser {
+    id: u8,
+
+    #[toql(field = "login")]
+    user_name: Option<String>,
+}
+
+#[derive(Debug, Clone, Toql)]
+#[toql(fields = "CamelCase", skip_indelup)]
+struct NamedChapter {
+    id: u8,
+    book_id: u8,
+}
+
+#[test]
+fn mapper_keys() {
+    let mapper = SqlMapper::map::<NamedBook>("b");
+    assert_eq!(
+        vec!["Id", "title", "PageCount", "BookAuthor_id", "BookAuthor_login"],
+        mapper.field_names()
+    );
+    assert_eq!(vec!["BookAuthor"], mapper.join_paths());
+    assert_eq!(vec!["BookChapters"], mapper.merge_paths());
+}
+
+#[test]
+fn field_methods() {
+    assert_eq!("title", NamedBook::fields().long_title().to_string());
+    assert_eq!("PageCount", NamedBook::fields().page_count().to_string());
+    assert_eq!("BookAuthor_login", NamedBook::fields().book_author().user_name().to_string());
+    assert_eq!("BookChapters_BookId", NamedBook::fields().book_chapters().book_id().to_string());
+}
+
+#[test]
+fn merge_predicate() {
+    // Predicate as built by the loader of `book_chapters`
+    let query: Query = NamedChapter::fields_from_path(String::from("BookChapters_"))
+        .book_id()
+        .ins(vec![1, 2])
+        .into();
+    let mapper = SqlMapper::map::<NamedChapter>("c");
+    let result = SqlBuilder::new().build_path("BookChapters", &mapper, &query).unwrap();
+    assert_eq!(
+        "SELECT c.id, c.book_id FROM NamedChapter c WHERE c.book_id IN (?,?)",
+        result.to_sql()
+    );
+}
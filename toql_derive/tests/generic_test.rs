@@ -0,0 +1,98 @@
+#![allow(dead_code)] // Structs are only mapped
+
+use std::marker::PhantomData;
+use toql::mapper_registry::MapperRegistry;
+use toql::query_parser::QueryParser;
+use toql::sql_builder::SqlBuilder;
+use toql::sql_mapper::{Mapped, SqlMapper};
+use toql_derive::Toql;
+
+#[derive(Debug, Clone, Toql)]
+#[toql(skip_indelup)]
+struct GenericUser {
+    id: u8,
+    login: Option<String>,
+}
+
+#[derive(Debug, Clone, Toql)]
+#[toql(skip_indelup)]
+struct GenericPhone {
+    id: u8,
+    number: Option<String>,
+}
+
+#[derive(Debug, Clone, Toql)]
+#[toql(skip_indelup)]
+struct Page<T> {
+    id: u8,
+    #[toql(sql_join(self = "content_id", other = "id"))]
+    content: T,
+}
+
+#[derive(Debug, Clone, Toql)]
+#[toql(skip_indelup)]
+struct Marked<T: Default + Clone> {
+    id: u8,
+    #[toql(skip)]
+    marker: PhantomData<T>,
+}
+
+#[derive(Debug, Clone, Toql)]
+#[toql(skip_indelup)]
+struct Label<'a> {
+    id: u8,
+    #[toql(skip)]
+    text: Option<&'a str>,
+}
+
+#[test]
+fn type_names() {
+    assert_eq!("Page<GenericUser>", Page::<GenericUser>::type_name());
+    assert_eq!("Page<GenericPhone>", Page::<GenericPhone>::type_name());
+    assert_eq!("Page<Page<GenericUser>>", Page::<Page<GenericUser>>::type_name());
+
+    // Type parameters that are not joined do not change the mapper
+    assert_eq!("Marked", Marked::<u8>::type_name());
+    assert_eq!("Marked", Marked::<String>::type_name());
+    assert_eq!("Label", Label::type_name());
+}
+
+#[test]
+fn register_per_type_argument() {
+    let mut registry = MapperRegistry::new();
+    registry.register::<Page<GenericUser>>();
+    registry.register::<Page<GenericPhone>>();
+    assert!(registry.get("Page<GenericUser>").is_some());
+    assert!(registry.get("Page<GenericPhone>").is_some());
+    assert!(registry.get("GenericUser").is_some());
+    assert!(registry.get("GenericPhone").is_some());
+}
+
+#[test]
+fn join_type_argument() {
+    let mapper = SqlMapper::map::<Page<GenericPhone>>("p");
+    let query = QueryParser::parse("id, content_number").unwrap();
+    let result = SqlBuilder::new().build(&mapper, &query).unwrap();
+    assert_eq!(
+        "SELECT p.id, content.id, content.number FROM Page p \
+         INNER JOIN GenericPhone content ON (p.content_id = content.id)",
+        result.to_sql()
+    );
+}
+
+#[test]
+fn fields_of_type_argument() {
+    assert_eq!("content_login", Page::<GenericUser>::fields().content().login().to_string());
+    assert_eq!(
+        "content_content_number",
+        Page::<Page<GenericPhone>>::fields().content().content().number().to_string()
+    );
+}
+
+#[test]
+fn lifetime() {
+    let mapper = SqlMapper::map::<Label>("l");
+    let query = QueryParser::parse("*").unwrap();
+    let result = SqlBuilder::new().build(&mapper, &query).unwrap();
+    assert_eq!("SELECT l.id FROM Label l", result.to_sql());
+}
@@ -0,0 +1,113 @@
+#![allow(dead_code)] // Structs are only mapped
+
+use std::collections::BTreeMap;
+use std::collections::HashMap;
+use std::collections::HashSet;
+use toql::sql_mapper::SqlMapper;
+use toql_derive::Toql;
+
+#[derive(Debug, Clone, Toql)]
+#[toql(skip_indelup)]
+struct MergeBook {
+    id: u8,
+
+    #[toql(merge(self = "id", other = "book_id"))]
+    chapters: Vec<MergeChapter>,
+
+    #[toql(merge(self = "id", other = "book_id"), key = "lang")]
+    titles: HashMap<String, MergeTitle>,
+
+    #[toql(merge(self = "id", other = "book_id"), key = "lang")]
+    sorted_titles: BTreeMap<String, MergeTitle>,
+
+    #[toql(merge(self = "id", other = "book_id"))]
+    tags: HashSet<MergeTag>,
+}
+
+#[derive(Debug, Clone, PartialEq, Toql)]
+#[toql(skip_indelup)]
+struct MergeChapter {
+    id: u8,
+    book_id: u8,
+}
+
+#[derive(Debug, Clone, PartialEq, Toql)]
+#[toql(skip_indelup)]
+struct MergeTitle {
+    id: u8,
+    book_id: u8,
+    lang: String,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Hash, Toql)]
+#[toql(skip_indelup)]
+struct MergeTag {
+    id: u8,
+    book_id: u8,
+}
+
+fn books() -> Vec<MergeBook> {
+    (1..=2)
+        .map(|id| MergeBook {
+            id,
+            chapters: Vec::new(),
+            titles: HashMap::new(),
+            sorted_titles: BTreeMap::new(),
+            tags: HashSet::new(),
+        })
+        .collect()
+}
+
+fn title(id: u8, book_id: u8, lang: &str) -> MergeTitle {
+    MergeTitle {
+        id,
+        book_id,
+        lang: String::from(lang),
+    }
+}
+
+#[test]
+fn merge_paths() {
+    let mapper = SqlMapper::map::<MergeBook>("b");
+    assert_eq!(vec!["chapters", "sortedTitles", "tags", "titles"], mapper.merge_paths());
+    assert_eq!(Some("MergeTitle"), mapper.merge_mapper("sortedTitles"));
+}
+
+#[test]
+fn merge_vec() {
+    let mut books = books();
+    let chapters = vec![
+        MergeChapter { id: 1, book_id: 2 },
+        MergeChapter { id: 2, book_id: 2 },
+        MergeChapter { id: 3, book_id: 3 }, // No book
+    ];
+    MergeBook::merge_chapters(&mut books, chapters);
+    assert!(books[0].chapters.is_empty());
+    assert_eq!(vec![1, 2], books[1].chapters.iter().map(|c| c.id).collect::<Vec<u8>>());
+}
+
+#[test]
+fn merge_maps() {
+    let mut books = books();
+    let titles = vec![title(1, 1, "en"), title(2, 1, "de"), title(3, 2, "en")];
+    MergeBook::merge_titles(&mut books, titles.clone());
+    MergeBook::merge_sorted_titles(&mut books, titles);
+
+    assert_eq!(2, books[0].titles.len());
+    assert_eq!(2, books[0].titles.get("de").unwrap().id);
+    assert_eq!(3, books[1].titles.get("en").unwrap().id);
+    assert_eq!(
+        vec!["de", "en"],
+        books[0].sorted_titles.keys().map(|k| k.as_str()).collect::<Vec<&str>>()
+    );
+}
+
+#[test]
+fn merge_set() {
+    let mut books = books();
+    let tags = vec![MergeTag { id: 1, book_id: 1 }, MergeTag { id: 2, book_id: 1 }];
+    MergeBook::merge_tags(&mut books, tags);
+    assert!(books[0].tags.contains(&MergeTag { id: 2, book_id: 1 }));
+    assert_eq!(2, books[0].tags.len());
+    assert!(books[1].tags.is_empty());
+}
@@ -0,0 +1,106 @@
+#![allow(dead_code)] // Structs are only mapped
+
+use toql::mapper_registry::MapperRegistry;
+use toql::mapper_registry::DEFAULT_MAX_MERGE_DEPTH;
+use toql::query_parser::QueryParser;
+use toql::sql_builder::SqlBuilder;
+use toql_derive::Toql;
+
+#[derive(Debug, Clone, Toql)]
+#[toql(skip_indelup)]
+struct NestUser {
+    id: u8,
+
+    #[toql(merge(self = "id", other = "user_id"))]
+    orders: Vec<NestOrder>,
+}
+
+#[derive(Debug, Clone, Toql)]
+#[toql(skip_indelup)]
+struct NestOrder {
+    id: u8,
+    user_id: u8,
+
+    #[toql(merge(self = "id", other = "order_id"))]
+    items: Vec<NestItem>,
+}
+
+#[derive(Debug, Clone, Toql)]
+#[toql(skip_indelup)]
+struct NestItem {
+    id: u8,
+    order_id: u8,
+}
+
+fn user(id: u8) -> NestUser {
+    NestUser { id, orders: Vec::new() }
+}
+
+fn order(id: u8) -> NestOrder {
+    NestOrder { id, user_id: 1, items: Vec::new() }
+}
+
+#[test]
+fn register_merge_tree() {
+    let mut registry = MapperRegistry::new();
+    registry.register::<NestUser>();
+    assert!(registry.contains_key("NestOrder"));
+    assert!(registry.contains_key("NestItem"));
+    assert_eq!(Ok(()), registry.validate());
+
+    assert_eq!(DEFAULT_MAX_MERGE_DEPTH, registry.max_merge_depth());
+    registry.set_max_merge_depth(1);
+    assert_eq!(1, registry.max_merge_depth());
+}
+
+#[test]
+fn query_per_level() {
+    let mut registry = MapperRegistry::new();
+    registry.register::<NestUser>();
+    let query = QueryParser::parse("*, orders_*, orders_items_*").unwrap();
+
+    // Orders of all loaded users, items are loaded with the next level
+    let (path, orders_query) = NestUser::merge_orders_query(&[user(1), user(2)], "", &query, &registry, 0).unwrap();
+    assert_eq!("orders", path);
+    let result = SqlBuilder::new()
+        .ignore_path("orders_items")
+        .build_path(&path, registry.get("NestOrder").unwrap(), &orders_query)
+        .unwrap();
+    assert_eq!(
+        "SELECT nest_order.id, nest_order.user_id FROM NestOrder nest_order WHERE nest_order.user_id IN (?,?)",
+        result.to_sql()
+    );
+    assert_eq!(*result.params(), ["1", "2"]);
+
+    // Items of all loaded orders, their path is prefixed with the path of the orders
+    let (path, items_query) =
+        NestOrder::merge_items_query(&[order(3), order(4), order(5)], "orders", &query, &registry, 1).unwrap();
+    assert_eq!("orders_items", path);
+    let result = SqlBuilder::new()
+        .build_path(&path, registry.get("NestItem").unwrap(), &items_query)
+        .unwrap();
+    assert_eq!(
+        "SELECT nest_item.id, nest_item.order_id FROM NestItem nest_item WHERE nest_item.order_id IN (?,?,?)",
+        result.to_sql()
+    );
+    assert_eq!(*result.params(), ["3", "4", "5"]);
+}
+
+#[test]
+fn skip_merge_query() {
+    let mut registry = MapperRegistry::new();
+    registry.register::<NestUser>();
+    let query = QueryParser::parse("*, orders_*, orders_items_*").unwrap();
+
+    // No entities
+    assert!(NestUser::merge_orders_query(&[], "", &query, &registry, 0).is_none());
+
+    // Merge is not in the query
+    let users_only = QueryParser::parse("*").unwrap();
+    assert!(NestUser::merge_orders_query(&[user(1)], "", &users_only, &registry, 0).is_none());
+
+    // Maximum merge depth
+    registry.set_max_merge_depth(1);
+    assert!(NestUser::merge_orders_query(&[user(1)], "", &query, &registry, 0).is_some());
+    assert!(NestOrder::merge_items_query(&[order(3)], "orders", &query, &registry, 1).is_none());
+}
@@ -0,0 +1,57 @@
+#![allow(dead_code)] // Structs are only mapped
+
+use toql::query_parser::QueryParser;
+use toql::sql_builder::SqlBuilder;
+use toql::sql_mapper::SqlMapper;
+use toql_derive::Toql;
+
+#[derive(Debug, Clone, Toql)]
+#[toql(
+    skip_indelup,
+    order = "+1 title, -2 id",
+    selection(name = "summary", fields = "id, title"),
+    selection(name = "detail", fields = "id, title, author_username")
+)]
+struct OrderedBook {
+    id: u8,
+    title: Option<String>,
+
+    #[toql(sql_join(self = "author_id", other = "id"))]
+    author: Option<OrderedUser>,
+}
+
+#[derive(Debug, Clone, Toql)]
+#[toql(skip_indelup)]
+struct OrderedUser {
+    id: u8,
+    username: Option<String>,
+}
+
+#[test]
+fn default_order() {
+    let mapper = SqlMapper::map::<OrderedBook>("b");
+    assert_eq!(mapper.default_order_fields(), [("title", true), ("id", false)]);
+
+    let query = QueryParser::parse("id").unwrap();
+    let result = SqlBuilder::new().build(&mapper, &query).unwrap();
+    assert_eq!(
+        "SELECT b.id, null, ordered_user.id, null FROM OrderedBook b \
+         LEFT JOIN OrderedUser ordered_user ON (b.author_id = ordered_user.id) ORDER BY b.title ASC, b.id DESC",
+        result.to_sql()
+    );
+}
+
+#[test]
+fn selections() {
+    let mapper = SqlMapper::map::<OrderedBook>("b");
+    assert_eq!(mapper.selection_names(), ["detail", "summary"]);
+    assert_eq!(mapper.selection_fields("detail").unwrap(), ["id", "title", "author_username"]);
+
+    let query = QueryParser::parse("$summary, -id").unwrap();
+    let result = SqlBuilder::new().build(&mapper, &query).unwrap();
+    assert_eq!(
+        "SELECT b.id, b.title, ordered_user.id, null FROM OrderedBook b \
+         LEFT JOIN OrderedUser ordered_user ON (b.author_id = ordered_user.id) ORDER BY b.id DESC",
+        result.to_sql()
+    );
+}
@@ -0,0 +1,86 @@
+#![allow(dead_code)] // Structs are only mapped
+
+use toql::query::{Field, Query};
+use toql::query_parser::QueryParser;
+use toql::sql_builder::SqlBuilder;
+use toql::sql_mapper::SqlMapper;
+use toql_derive::Toql;
+
+#[derive(Debug, Clone, Toql)]
+#[toql(skip_indelup)]
+struct Category {
+    id: u8,
+    parent_id: Option<u8>,
+    name: Option<String>,
+
+    #[toql(recursive(self = "id", other = "parent_id", max_depth = 2))]
+    children: Vec<Category>,
+}
+
+#[derive(Debug, Clone, Toql)]
+#[toql(skip_indelup)]
+struct Comment {
+    id: u8,
+    reply_to: Option<u8>,
+
+    #[toql(recursive(self = "id", other = "reply_to"))]
+    replies: Vec<Comment>,
+}
+
+#[test]
+fn mapper_keys() {
+    let mapper = SqlMapper::map::<Category>("c");
+    assert_eq!(vec!["id", "parentId", "name"], mapper.field_names());
+    assert_eq!(vec!["children"], mapper.merge_paths());
+    assert_eq!(mapper.function_names(), ["ANCESTORS", "DESCENDANTS"]);
+}
+
+#[test]
+fn depth_predicate() {
+    let mapper = SqlMapper::map::<Category>("c");
+    let query = QueryParser::parse("id FN DESCENDANTS 1").unwrap();
+    let result = SqlBuilder::new().build(&mapper, &query).unwrap();
+    assert_eq!(
+        "SELECT c.id, null, null FROM Category c WHERE c.id IN (WITH RECURSIVE toql_tree (toql_key, toql_depth) AS \
+         (SELECT id, 1 FROM Category WHERE parent_id IN (?) \
+         UNION ALL SELECT toql_node.id, toql_tree.toql_depth + 1 FROM Category toql_node \
+         JOIN toql_tree ON (toql_node.parent_id = toql_tree.toql_key) WHERE toql_tree.toql_depth < 2) \
+         SELECT toql_key FROM toql_tree)",
+        result.to_sql()
+    );
+}
+
+#[test]
+fn cycle_stop() {
+    let mapper = SqlMapper::map::<Comment>("c");
+    let query = QueryParser::parse("id FN ANCESTORS 7").unwrap();
+    let result = SqlBuilder::new().build(&mapper, &query).unwrap();
+    assert_eq!(
+        "SELECT c.id, null FROM Comment c WHERE c.id IN (WITH RECURSIVE toql_tree (toql_key, toql_depth, toql_path) AS \
+         (SELECT reply_to, 1, CAST(reply_to AS CHAR(4000)) FROM Comment WHERE id IN (?) AND reply_to IS NOT NULL \
+         UNION ALL SELECT toql_node.reply_to, toql_tree.toql_depth + 1, CONCAT(toql_tree.toql_path, ',', toql_node.reply_to) FROM Comment toql_node \
+         JOIN toql_tree ON (toql_node.id = toql_tree.toql_key) WHERE toql_node.reply_to IS NOT NULL \
+         AND FIND_IN_SET(toql_node.reply_to, toql_tree.toql_path) = 0) \
+         SELECT toql_key FROM toql_tree)",
+        result.to_sql()
+    );
+}
+
+#[test]
+fn tree_query() {
+    // Query as built by the loader of `children`
+    let query: Query = QueryParser::parse("children_parentId, children_name")
+        .unwrap()
+        .and(Field::from("children_id").fnc("DESCENDANTS", vec![1, 2]));
+    let mapper = SqlMapper::map::<Category>("c");
+    let result = SqlBuilder::new().build_path("children", &mapper, &query).unwrap();
+    assert_eq!(
+        "SELECT c.id, c.parent_id, c.name FROM Category c WHERE c.id IN (WITH RECURSIVE toql_tree (toql_key, toql_depth) AS \
+         (SELECT id, 1 FROM Category WHERE parent_id IN (?,?) \
+         UNION ALL SELECT toql_node.id, toql_tree.toql_depth + 1 FROM Category toql_node \
+         JOIN toql_tree ON (toql_node.parent_id = toql_tree.toql_key) WHERE toql_tree.toql_depth < 2) \
+         SELECT toql_key FROM toql_tree)",
+        result.to_sql()
+    );
+    assert_eq!(*result.params(), ["1", "2"]);
+}
@@ -0,0 +1,73 @@
+#![allow(dead_code)] // Structs are only mapped
+
+use std::sync::Arc;
+use toql::mapper_registry::MapperRegistry;
+use toql::query_parser::QueryParser;
+use toql::sql_builder::SqlBuilder;
+use toql::sql_mapper::SqlMapper;
+use toql::sql_mapper::SqlMapperCache;
+use toql_derive::Toql;
+
+#[derive(Debug, Clone, Toql)]
+#[toql(skip_indelup)]
+struct RegisteredBook {
+    id: u8,
+
+    #[toql(sql_join(self = "author_id", other = "id"))]
+    author: Option<RegisteredUser>,
+
+    #[toql(merge(self = "id", other = "book_id"))]
+    chapters: Vec<RegisteredChapter>,
+}
+
+#[derive(Debug, Clone, Toql)]
+#[toql(skip_indelup)]
+struct RegisteredUser {
+    id: u8,
+    username: Option<String>,
+}
+
+#[derive(Debug, Clone, Toql)]
+#[toql(skip_indelup)]
+struct RegisteredChapter {
+    id: u8,
+    book_id: u8,
+
+    #[toql(sql_join(self = "editor_id", other = "id"))]
+    editor: Option<RegisteredUser>,
+}
+
+#[test]
+fn register_dependencies() {
+    let mut registry = MapperRegistry::new();
+    registry.register::<RegisteredBook>();
+
+    let mut names: Vec<&String> = registry.keys();
+    names.sort();
+    assert_eq!(names, ["RegisteredBook", "RegisteredChapter", "RegisteredUser"]);
+    assert_eq!(Ok(()), registry.validate());
+}
+
+#[test]
+fn insert_new_mapper() {
+    let mut cache = SqlMapperCache::new();
+    SqlMapper::insert_new_mapper::<RegisteredChapter>(&mut cache);
+
+    assert!(cache.get_for::<RegisteredChapter>().is_some());
+    assert!(cache.contains_key("RegisteredUser"));
+    assert!(!cache.contains_key("RegisteredBook"));
+}
+
+#[test]
+fn overlay() {
+    let mut registry = MapperRegistry::new();
+    registry.register::<RegisteredBook>();
+    let base = Arc::new(registry);
+
+    let mut tenant = MapperRegistry::with_base(Arc::clone(&base));
+    tenant.get_mut_for::<RegisteredUser>().unwrap().unmap_field("username").unwrap();
+
+    let query = QueryParser::parse("username").unwrap();
+    assert!(SqlBuilder::new().build(tenant.get("RegisteredUser").unwrap(), &query).is_err());
+    assert!(SqlBuilder::new().build(base.get("RegisteredUser").unwrap(), &query).is_ok());
+}
@@ -0,0 +1,58 @@
+#![allow(dead_code)] // Structs are only mapped
+
+use toql::query_parser::QueryParser;
+use toql::sql_builder::SqlBuilder;
+use toql::sql_builder::SqlBuilderError;
+use toql::sql_mapper::SqlMapper;
+use toql_derive::Toql;
+
+#[derive(Debug, Clone, Toql)]
+#[toql(skip_indelup)]
+struct RestrictedBook {
+    #[toql(filters = "eq IN")]
+    id: u8,
+
+    #[toql(unsortable, like_prefix = 3)]
+    title: Option<String>,
+
+    #[toql(filters = "")] // No filters
+    isbn: Option<String>,
+}
+
+fn assert_invalid(toql: &str, expected: &str) {
+    let mapper = SqlMapper::map::<RestrictedBook>("b");
+    let query = QueryParser::parse(toql).unwrap();
+    match SqlBuilder::new().build(&mapper, &query) {
+        Err(SqlBuilderError::FilterInvalid(s)) => assert_eq!(expected, s),
+        _ => panic!("Filter `{}` must be invalid", toql),
+    }
+}
+
+#[test]
+fn restrict_filters() {
+    assert_invalid("id re '^1'", "field `id` does not allow filter `RE`");
+    assert_invalid("isbn eq '123'", "field `isbn` does not allow filter `EQ`");
+
+    let mapper = SqlMapper::map::<RestrictedBook>("b");
+    let query = QueryParser::parse("id in 1 2").unwrap();
+    let result = SqlBuilder::new().build(&mapper, &query).unwrap();
+    assert_eq!(
+        "SELECT b.id, null, null FROM RestrictedBook b WHERE b.id IN (?,?)",
+        result.to_sql()
+    );
+}
+
+#[test]
+fn restrict_order_and_like_prefix() {
+    let mapper = SqlMapper::map::<RestrictedBook>("b");
+    let query = QueryParser::parse("+title").unwrap();
+    match SqlBuilder::new().build(&mapper, &query) {
+        Err(SqlBuilderError::OrderInvalid(f)) => assert_eq!("title", f),
+        _ => panic!("Field `title` must not be sortable"),
+    }
+
+    assert_invalid(
+        "title lk 'Fo%'",
+        "field `title` requires at least 3 characters before a LIKE wildcard",
+    );
+}
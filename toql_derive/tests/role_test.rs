@@ -0,0 +1,90 @@
+#![allow(dead_code)] // Structs are only mapped
+
+use std::collections::BTreeSet;
+use toql::query_parser::QueryParser;
+use toql::sql_builder::SqlBuilder;
+use toql::sql_builder::SqlBuilderError;
+use toql::sql_mapper::SqlMapper;
+use toql_derive::Toql;
+
+#[derive(Debug, Clone, Toql)]
+#[toql(skip_indelup)]
+struct RoleBook {
+    id: u8,
+
+    #[toql(role = "admin;owner")]
+    price: Option<u8>,
+
+    #[toql(role = "editor", role = "!guest")] // Both must be satisfied
+    notes: Option<String>,
+
+    #[toql(sql_join(self = "author_id", other = "id"), role = "!guest")]
+    author: Option<RoleUser>,
+
+    #[toql(merge(self = "id", other = "book_id"), role = "editor")]
+    chapters: Vec<RoleChapter>,
+}
+
+#[derive(Debug, Clone, Toql)]
+#[toql(skip_indelup)]
+struct RoleUser {
+    id: u8,
+    username: Option<String>,
+}
+
+#[derive(Debug, Clone, Toql)]
+#[toql(skip_indelup)]
+struct RoleChapter {
+    id: u8,
+    book_id: u8,
+}
+
+fn roles(roles: &[&str]) -> BTreeSet<String> {
+    roles.iter().map(|r| r.to_string()).collect()
+}
+
+#[test]
+fn field_role() {
+    let mapper = SqlMapper::map::<RoleBook>("b");
+    let mut query = QueryParser::parse("price").unwrap();
+
+    match SqlBuilder::new().build(&mapper, &query) {
+        Err(SqlBuilderError::RoleRequired(_)) => {}
+        _ => panic!("Field `price` must require a role"),
+    }
+
+    query.roles = roles(&["owner"]);
+    let result = SqlBuilder::new().build(&mapper, &query).unwrap();
+    assert_eq!("SELECT b.id, b.price, null, role_user.id, null FROM RoleBook b LEFT JOIN RoleUser role_user ON (b.author_id = role_user.id)", result.to_sql());
+}
+
+#[test]
+fn multiple_roles() {
+    let mapper = SqlMapper::map::<RoleBook>("b");
+    let mut query = QueryParser::parse("notes").unwrap();
+
+    query.roles = roles(&["editor", "guest"]);
+    match SqlBuilder::new().build(&mapper, &query) {
+        Err(SqlBuilderError::RoleRequired(_)) => {}
+        _ => panic!("Field `notes` must deny guests"),
+    }
+
+    query.roles = roles(&["editor"]);
+    let result = SqlBuilder::new().build(&mapper, &query).unwrap();
+    assert_eq!("SELECT b.id, null, b.notes, role_user.id, null FROM RoleBook b LEFT JOIN RoleUser role_user ON (b.author_id = role_user.id)", result.to_sql());
+}
+
+#[test]
+fn join_and_merge_role() {
+    let mapper = SqlMapper::map::<RoleBook>("b");
+    let mut query = QueryParser::parse("author_username").unwrap();
+
+    query.roles = roles(&["guest"]);
+    match SqlBuilder::new().build(&mapper, &query) {
+        Err(SqlBuilderError::RoleRequired(_)) => {}
+        _ => panic!("Path `author` must deny guests"),
+    }
+
+    assert!(!mapper.merge_roles_valid("chapters", &roles(&["guest"])));
+    assert!(mapper.merge_roles_valid("chapters", &roles(&["editor"])));
+}
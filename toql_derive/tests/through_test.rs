@@ -0,0 +1,131 @@
+#![allow(dead_code)] // Structs are only mapped
+
+use toql::indelup::Indelup;
+use toql::sql_mapper::SqlMapper;
+use toql_derive::Toql;
+
+#[derive(Debug, Clone, Toql)]
+struct LinkUser {
+    #[toql(delup_key)]
+    id: u8,
+
+    #[toql(merge(self = "id", other = "id", through = "UserRole"))]
+    roles: Vec<LinkRole>,
+
+    #[toql(merge(
+        self = "id",
+        other = "id",
+        through = "Watch",
+        through_self = "watcher",
+        through_other = "watched"
+    ))]
+    watched: Vec<LinkRole>,
+}
+
+#[derive(Debug, Clone, Toql)]
+struct AutoLinkUser {
+    #[toql(delup_key, skip_inup)]
+    id: u8,
+
+    #[toql(merge(self = "id", other = "id", through = "UserRole"))]
+    roles: Vec<LinkRole>,
+}
+
+#[derive(Debug, Clone, Toql)]
+struct LinkRole {
+    #[toql(delup_key)]
+    id: u8,
+    name: Option<String>,
+}
+
+fn role(id: u8) -> LinkRole {
+    LinkRole { id, name: None }
+}
+
+#[test]
+fn merge_paths() {
+    let mapper = SqlMapper::map::<LinkUser>("u");
+    assert_eq!(vec!["roles", "watched"], mapper.merge_paths());
+    assert_eq!(Some("LinkRole"), mapper.merge_mapper("roles"));
+}
+
+#[test]
+fn merge_by_link_key() {
+    let mut users = vec![
+        LinkUser { id: 1, roles: Vec::new(), watched: Vec::new() },
+        LinkUser { id: 2, roles: Vec::new(), watched: Vec::new() },
+    ];
+    // Merged structs come with the key of the link table, a role can belong to many users
+    LinkUser::merge_roles(&mut users, vec![(1, role(5)), (2, role(5)), (2, role(6))]);
+    assert_eq!(vec![5], users[0].roles.iter().map(|r| r.id).collect::<Vec<u8>>());
+    assert_eq!(vec![5, 6], users[1].roles.iter().map(|r| r.id).collect::<Vec<u8>>());
+}
+
+#[test]
+fn link_sql() {
+    let user = LinkUser {
+        id: 1,
+        roles: vec![role(5), role(6)],
+        watched: vec![role(7)],
+    };
+
+    let (sql, params) = LinkUser::insert_roles_links_sql(&user).unwrap();
+    assert_eq!("INSERT INTO UserRole (link_user_id,link_role_id) VALUES (?,?), (?,?)", sql);
+    assert_eq!(["1", "5", "1", "6"], *params);
+
+    let (sql, params) = LinkUser::delete_roles_links_sql(&user).unwrap();
+    assert_eq!("DELETE FROM UserRole WHERE link_user_id = ?", sql);
+    assert_eq!(["1"], *params);
+
+    // Link columns from attributes
+    let (sql, params) = LinkUser::insert_watched_links_sql(&user).unwrap();
+    assert_eq!("INSERT INTO Watch (watcher,watched) VALUES (?,?)", sql);
+    assert_eq!(["1", "7"], *params);
+}
+
+#[test]
+fn link_sql_without_links() {
+    let user = LinkUser { id: 1, roles: Vec::new(), watched: Vec::new() };
+    let (sql, params) = LinkUser::insert_roles_links_sql(&user).unwrap();
+    assert_eq!("-- Nothing to insert", sql);
+    assert!(params.is_empty());
+
+    let (sql, params) = LinkUser::delete_roles_links_sql(&user).unwrap();
+    assert_eq!("DELETE FROM UserRole WHERE link_user_id = ?", sql);
+    assert_eq!(["1"], *params);
+}
+
+#[test]
+fn indelup_link_statements() {
+    let user = LinkUser {
+        id: 1,
+        roles: vec![role(5)],
+        watched: Vec::new(),
+    };
+
+    // Links are deleted before the user and inserted after it
+    let statements = LinkUser::delete_links_sql(&user).unwrap();
+    assert_eq!(
+        vec![
+            (String::from("DELETE FROM UserRole WHERE link_user_id = ?"), vec![String::from("1")]),
+            (String::from("DELETE FROM Watch WHERE watcher = ?"), vec![String::from("1")]),
+        ],
+        statements
+    );
+    let statements = LinkUser::insert_links_sql(&user).unwrap();
+    assert_eq!(
+        vec![
+            (
+                String::from("INSERT INTO UserRole (link_user_id,link_role_id) VALUES (?,?)"),
+                vec![String::from("1"), String::from("5")]
+            ),
+            (String::from("-- Nothing to insert"), Vec::new()),
+        ],
+        statements
+    );
+
+    // Auto generated keys are unknown before the insert
+    let user = AutoLinkUser { id: 1, roles: vec![role(5)] };
+    assert!(AutoLinkUser::insert_links_sql(&user).unwrap().is_empty());
+    assert_eq!(1, AutoLinkUser::delete_links_sql(&user).unwrap().len());
+}
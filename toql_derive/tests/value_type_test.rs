@@ -0,0 +1,41 @@
+#![allow(dead_code)] // Structs are only mapped
+
+use toql::query_parser::QueryParser;
+use toql::sql_builder::SqlBuilder;
+use toql::sql_builder::SqlBuilderError;
+use toql::sql_mapper::SqlMapper;
+use toql_derive::Toql;
+
+#[derive(Debug, Clone, Toql)]
+#[toql(skip_indelup)]
+struct TypedUser {
+    id: u64,
+    score: Option<f32>,
+    active: Option<bool>,
+    name: Option<String>,
+}
+
+fn assert_invalid(toql: &str, expected: &str) {
+    let mapper = SqlMapper::map::<TypedUser>("u");
+    let query = QueryParser::parse(toql).unwrap();
+    match SqlBuilder::new().build(&mapper, &query) {
+        Err(SqlBuilderError::FilterInvalid(s)) => assert_eq!(expected, s),
+        _ => panic!("Filter `{}` must be invalid", toql),
+    }
+}
+
+#[test]
+fn value_types_from_rust_types() {
+    assert_invalid("id eq 'abc'", "field `id` expects integer, got 'abc'");
+    assert_invalid("score gt 'x'", "field `score` expects float, got 'x'");
+    assert_invalid("active eq 2", "field `active` expects boolean, got 2");
+
+    let mapper = SqlMapper::map::<TypedUser>("u");
+    let query = QueryParser::parse("id eq '5', name eq 5").unwrap();
+    let result = SqlBuilder::new().build(&mapper, &query).unwrap();
+    assert_eq!(
+        "SELECT u.id, null, null, u.name FROM TypedUser u WHERE u.id = ? AND u.name = ?",
+        result.to_sql()
+    );
+    assert_eq!(*result.params(), ["5", "5"]);
+}
@@ -0,0 +1,78 @@
+//!
+//! Count strategies to get the total and filtered count of a query.
+//!
+//! The total count are all rows a user can access, the filtered count all rows that match the query.
+//! See [count queries](../../toql_core/sql_builder/index.html#count-queries) for details.
+//!
+//! The default strategy [SubqueryCount](struct.SubqueryCount.html) runs `SELECT COUNT(*)` statements.
+//! Implement [CountStrategy](trait.CountStrategy.html) for other ways to count, for example an estimate.
+//! A window function count (`COUNT(*) OVER ()`) is out of scope, it needs the select statement of the
+//! loaded rows and cannot count the total rows, because their filter differs.
+//!
+
+use mysql::Conn;
+use mysql::Value;
+use toql_core::error::ToqlError;
+use toql_core::query::Query;
+use toql_core::sql_builder::SqlBuilder;
+use toql_core::sql_builder_result::SqlBuilderResult;
+use toql_core::sql_mapper::SqlMapper;
+
+/// Trait to count the rows of a built query.
+pub trait CountStrategy {
+    /// Returns the number of rows of the query.
+    fn count(&self, result: &SqlBuilderResult, conn: &mut Conn) -> Result<u64, ToqlError>;
+}
+
+/// Counts rows with `SELECT COUNT(*)`, see [to_count_sql()](../../toql_core/sql_builder_result/struct.SqlBuilderResult.html#method.to_count_sql).
+#[derive(Debug, Clone, Copy, Default)]
+pub struct SubqueryCount;
+
+impl CountStrategy for SubqueryCount {
+    fn count(&self, result: &SqlBuilderResult, conn: &mut Conn) -> Result<u64, ToqlError> {
+        let sql = result.to_count_sql();
+        log::info!("SQL `{}` with params {:?}", sql, result.params());
+        let mut query_result = conn.prep_exec(sql, result.params())?;
+        let value = match query_result.next() {
+            Some(row) => row?.take::<Value, usize>(0),
+            None => None,
+        };
+        count_from_value(value)
+    }
+}
+
+/// Returns the count from the first column of a count row.
+/// A missing row or column and `NULL` fail with [ValueMissing](../../toql_core/error/enum.ToqlError.html#variant.ValueMissing).
+pub fn count_from_value(value: Option<Value>) -> Result<u64, ToqlError> {
+    match value {
+        None | Some(Value::NULL) => Err(ToqlError::ValueMissing(String::from("COUNT(*)"))),
+        Some(value) => mysql::from_value_opt::<u64>(value)
+            .map_err(|_| ToqlError::ValueMissing(String::from("COUNT(*)"))),
+    }
+}
+
+/// Returns true, if the filtered count must be counted.
+/// It is skipped, if its SQL and parameters are equal to the total count.
+pub fn filtered_count_needed(total_result: &SqlBuilderResult, result: &SqlBuilderResult) -> bool {
+    total_result.to_count_sql() != result.to_count_sql() || total_result.params() != result.params()
+}
+
+/// Returns the total and filtered count for a query and its built result.
+///
+/// The total count is built from the count filters of the mapper.
+/// The filtered count is skipped, if its SQL and parameters are equal to the total count, see [filtered_count_needed()](fn.filtered_count_needed.html).
+pub fn count(
+    strategy: &dyn CountStrategy,
+    mapper: &SqlMapper,
+    query: &Query,
+    result: &SqlBuilderResult,
+    conn: &mut Conn,
+) -> Result<(u64, u64), ToqlError> {
+    let total_result = SqlBuilder::new().build_count(mapper, query)?;
+    let total_count = strategy.count(&total_result, conn)?;
+    if !filtered_count_needed(&total_result, result) {
+        return Ok((total_count, total_count));
+    }
+    let filtered_count = strategy.count(result, conn)?;
+    Ok((total_count, filtered_count))
+}
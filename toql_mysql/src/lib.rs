@@ -9,16 +9,32 @@ use toql_core::sql_mapper::SqlMapperCache;
 use toql_core::query::Query;
 use toql_core::error::ToqlError;
 use toql_core::indelup::Indelup;
+use toql_core::sql_builder_result::SqlBuilderResult;
+use toql_core::sql_builder::SqlBuilder;
+use toql_core::sql_mapper::SqlMapper;
 
 
+pub mod count;
 pub mod load;
 pub mod row;
 pub use mysql;  // Reexport for derive produced code
 
 
+    // Execute statements for link rows, statements without params have nothing to do
+    fn execute_links(statements: Vec<(String, Vec<String>)>, conn: &mut mysql::Conn) -> Result<(), ToqlError> {
+        for (link_stmt, params) in statements {
+            if params.is_empty() {continue;}
+            log::info!("Sql `{}` with params {:?}", link_stmt, params);
+            let mut stmt = conn.prepare(link_stmt)?;
+            stmt.execute(params)?;
+        }
+        Ok(())
+    }
+
     /// Insert one struct. 
     /// 
     /// Skip fields in struct that are auto generated with `#[toql(skip_inup)]`.
+    /// Rows in link tables of merges are inserted too, unless the key is auto generated.
     /// Returns the last generated id.
  pub fn insert_one<'a, T>( entity: &T, conn: &mut mysql::Conn) -> Result<u64, ToqlError> 
     where T:'a + Indelup<'a, T>
@@ -28,32 +44,46 @@ pub use mysql;  // Reexport for derive produced code
     log::info!("Sql `{}` with params {:?}", insert_stmt, params);
     let mut stmt = conn.prepare(insert_stmt)?;
     let res= stmt.execute(params)?;
-    Ok(res.last_insert_id())
+    let id = res.last_insert_id();
+    drop(res);
+    drop(stmt);
+    execute_links(T::insert_links_sql(entity)?, conn)?;
+    Ok(id)
      
  }
      
     /// Insert a collection of structs. 
     /// 
     /// Skip fields in struct that are auto generated with `#[toql(skip_inup)]`.
+    /// Rows in link tables of merges are inserted too, unless the key is auto generated.
     /// Returns the last generated id
   pub fn insert_many<'a, I, T > (entities: I, conn: &mut mysql::Conn) -> Result<u64, ToqlError> 
     where I: Iterator<Item = &'a T> + 'a, T:'a + Indelup<'a, T>
      {
-        let (insert_stmt, params) = T::insert_many_sql(entities)?;
+        let entities: Vec<&'a T> = entities.collect();
+        let (insert_stmt, params) = T::insert_many_sql(entities.clone())?;
         if params.is_empty() {return Ok(0);}
         log::info!("Sql `{}` with params {:?}", insert_stmt, params);
         let mut stmt = conn.prepare(insert_stmt)?;
         let res= stmt.execute(params)?;
-        Ok(res.last_insert_id())
+        let id = res.last_insert_id();
+        drop(res);
+        drop(stmt);
+        for entity in entities {
+            execute_links(T::insert_links_sql(entity)?, conn)?;
+        }
+        Ok(id)
     }
 
     /// Delete a struct. 
     /// 
     /// The field that is used as key must be attributed with `#[toql(delup_key)]`.
+    /// Rows in link tables of merges are deleted first.
     /// Returns the number of deleted rows.
     pub fn delete_one<'a, T >(entity: &T, conn: &mut mysql::Conn) -> Result<u64, ToqlError> 
     where T:'a + Indelup<'a, T>
     {
+        execute_links(T::delete_links_sql(entity)?, conn)?;
         let (delete_stmt, params) = T::delete_one_sql(&entity)?;
         log::info!("Sql `{}` with params {:?}", delete_stmt, params);
 
@@ -65,10 +95,15 @@ pub use mysql;  // Reexport for derive produced code
     /// Delete a collection of structs. 
     /// 
     /// The field that is used as key must be attributed with `#[toql(delup_key)]`.
+    /// Rows in link tables of merges are deleted first.
     /// Returns the number of deleted rows.
     pub fn delete_many<'a, I, T> (entities: I, conn: &mut mysql::Conn) -> Result<u64, ToqlError> 
     where I: Iterator<Item = &'a T> + 'a ,  T:'a + Indelup<'a, T>
     {
+        let entities: Vec<&'a T> = entities.collect();
+        for entity in &entities {
+            execute_links(T::delete_links_sql(entity)?, conn)?;
+        }
         let (delete_stmt, params)= T::delete_many_sql(entities)?;
         if params.is_empty() {return Ok(0);}
         log::info!("Sql `{}` with params {:?}", delete_stmt, params);
@@ -81,16 +116,24 @@ pub use mysql;  // Reexport for derive produced code
     /// 
     /// Optional fields with value `None` are not updated. See guide for details.
     /// The field that is used as key must be attributed with `#[toql(delup_key)]`.
+    /// Rows in link tables of merges are replaced with the merged structs, so merges must be loaded.
     /// Returns the number of updated rows.
     pub fn update_many<'a, I, T> (entities: I, conn: &mut mysql::Conn) -> Result<u64, ToqlError> 
         where I: Iterator<Item = &'a T> + Clone +'a,  T:'a + Indelup<'a, T>
          {
-            let (update_stmt, params) = T::update_many_sql(entities)?;
+            let (update_stmt, params) = T::update_many_sql(entities.clone())?;
             log::info!("Sql `{}` with params {:?}", update_stmt, params);
             let mut stmt = conn.prepare(&update_stmt)?;
             let res = stmt.execute(params)?;
+            let affected_rows = res.affected_rows();
+            drop(res);
+            drop(stmt);
+            for entity in entities {
+                execute_links(T::delete_links_sql(entity)?, conn)?;
+                execute_links(T::insert_links_sql(entity)?, conn)?;
+            }
 
-            Ok(res.affected_rows())
+            Ok(affected_rows)
        /*   let mut x = 0;
 
         for entity in entities{
@@ -103,6 +146,7 @@ pub use mysql;  // Reexport for derive produced code
     /// 
     /// Optional fields with value `None` are not updated. See guide for details.
     /// The field that is used as key must be attributed with `#[toql(delup_key)]`.
+    /// Rows in link tables of merges are replaced with the merged structs, so merges must be loaded.
     /// Returns the number of updated rows.
     pub fn update_one<'a, T >(entity: &T, conn: &mut mysql::Conn) -> Result<u64, ToqlError> 
     where T:'a + Indelup<'a, T>
@@ -111,10 +155,45 @@ pub use mysql;  // Reexport for derive produced code
         log::info!("Sql `{}` with params {:?}", update_stmt, params);
         let mut stmt = conn.prepare(&update_stmt)?;
         let res = stmt.execute(params)?;
+        let affected_rows = res.affected_rows();
+        drop(res);
+        drop(stmt);
+        execute_links(T::delete_links_sql(entity)?, conn)?;
+        execute_links(T::insert_links_sql(entity)?, conn)?;
 
-        Ok(res.affected_rows())
+        Ok(affected_rows)
     }
    
+    /// Delete all rows that match the filter of a Toql query.
+    ///
+    /// Roles and row filters of the mapper apply. Without query filter the delete is refused, unless `allow_unfiltered` is true.
+    /// Returns the number of deleted rows.
+    pub fn delete_where(mapper: &SqlMapper, query: &Query, allow_unfiltered: bool, conn: &mut mysql::Conn) -> Result<u64, ToqlError>
+    {
+        let (delete_stmt, params) = SqlBuilder::new()
+            .allow_unfiltered(allow_unfiltered)
+            .build_delete(mapper, query)?;
+        log::info!("Sql `{}` with params {:?}", delete_stmt, params);
+        let mut stmt = conn.prepare(delete_stmt)?;
+        let res = stmt.execute(params)?;
+        Ok(res.affected_rows())
+    }
+
+    /// Update fields of all rows that match the filter of a Toql query.
+    ///
+    /// Changes contain fields and their new values. See [delete_where()](fn.delete_where.html).
+    /// Returns the number of updated rows.
+    pub fn update_where(mapper: &SqlMapper, query: &Query, changes: &[(&str, &str)], allow_unfiltered: bool, conn: &mut mysql::Conn) -> Result<u64, ToqlError>
+    {
+        let (update_stmt, params) = SqlBuilder::new()
+            .allow_unfiltered(allow_unfiltered)
+            .build_update(mapper, query, changes)?;
+        log::info!("Sql `{}` with params {:?}", update_stmt, params);
+        let mut stmt = conn.prepare(&update_stmt)?;
+        let res = stmt.execute(params)?;
+        Ok(res.affected_rows())
+    }
+
 /// Load a struct with dependencies for a given Toql query.
 /// 
 /// Returns a struct or a [ToqlError](../toql_core/error/enum.ToqlError.html) if no struct was found _NotFound_ or more than one _NotUnique_.
@@ -126,14 +205,41 @@ pub use mysql;  // Reexport for derive produced code
 /// Load a vector of structs with dependencies for a given Toql query.
 /// 
 /// Returns a tuple with the structs and an optional tuple of count values. 
-/// If `count` argument is `false`, no count queries are run and the resulting `Option<(u32,u32)>` will be `None`
+/// If `count` argument is `false`, no count queries are run and the resulting `Option<(u64,u64)>` will be `None`
 /// otherwise the count queries are run and it will be `Some((total count, filtered count))`.
  pub fn load_many<T: load::Load<T>>(query: &Query, mappers: &SqlMapperCache, conn: &mut Conn, count: bool, first:u64, max:u16)
--> Result<(Vec<T>, Option<(u32,u32)>), ToqlError>
+-> Result<load::LoadedMany<T>, ToqlError>
  {
     T::load_many(query, mappers, conn,  count, first, max)
  }
 
+/// Load a vector of structs with dependencies and count them with a [count strategy](count/trait.CountStrategy.html).
+/// 
+/// Returns a tuple with the structs and the count values, if a count strategy is given.
+ pub fn load_many_with_count<T: load::Load<T>>(query: &Query, mappers: &SqlMapperCache, conn: &mut Conn, count_strategy: Option<&dyn count::CountStrategy>, first:u64, max:u16)
+-> Result<load::LoadedMany<T>, ToqlError>
+ {
+    T::load_many_with_count(query, mappers, conn, count_strategy, first, max)
+ }
+
+/// Load rows for a SQL Builder result without a derived struct.
+///
+/// Use it for mappers that are built by hand or from configuration.
+/// Every row is a JSON object with the selected Toql fields as keys and nested objects for paths.
+/// See [from_row_dynamic()](row/fn.from_row_dynamic.html).
+ pub fn load_dynamic(result: &SqlBuilderResult, conn: &mut Conn, first: u64, max: u16)
+ -> Result<Vec<serde_json::Value>, ToqlError>
+ {
+    let sql = result.to_sql_for_mysql("", first, max);
+    log::info!("SQL `{}` with params {:?}", sql, result.params());
+    let query_result = conn.prep_exec(sql, result.params())?;
+    let mut rows = Vec::new();
+    for row in query_result {
+        rows.push(row::from_row_dynamic(&row?, result.columns())?);
+    }
+    Ok(rows)
+ }
+
 /* 
  pub fn is_null(row: &mysql::Row, id: usize) -> bool {
     let v : mysql::Value;
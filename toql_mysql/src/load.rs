@@ -2,10 +2,17 @@
 
 use toql_core::error::ToqlError;
 use toql_core::query::Query;
+use toql_core::sql_mapper::SqlMapper;
 use toql_core::sql_mapper::SqlMapperCache;
 use mysql::Conn;
+use mysql::prelude::FromValue;
+use crate::count::CountStrategy;
+use crate::count::SubqueryCount;
 
 
+/// Loaded structs with the total and filtered count, if they were counted.
+pub type LoadedMany<T> = (Vec<T>, Option<(u64, u64)>);
+
 /// Trait to load entities from MySQL database.
 pub trait Load<T> {
     /// Load a struct with dependencies for a given Toql query.
@@ -17,8 +24,35 @@ pub trait Load<T> {
     /// Load a vector of structs with dependencies for a given Toql query.
     /// 
     /// Returns a tuple with the structs and an optional tuple of count values. 
-    /// If `count` argument is `false`, no count queries are run and the resulting `Option<(u32,u32)>` will be `None`
+    /// If `count` argument is `false`, no count queries are run and the resulting `Option<(u64,u64)>` will be `None`
     /// otherwise the count queries are run and it will be `Some((total count, filtered count))`.
+    /// Counts are taken with [SubqueryCount](../count/struct.SubqueryCount.html).
     fn load_many(query: &Query, mappers: &SqlMapperCache, conn: &mut Conn, count: bool, first:u64, max:u16) 
-        -> Result<(Vec<T>, Option<(u32,u32)>),ToqlError>;
- } 
\ No newline at end of file
+        -> Result<LoadedMany<T>, ToqlError> {
+        let strategy = SubqueryCount;
+        let count_strategy: Option<&dyn CountStrategy> = if count { Some(&strategy) } else { None };
+        Self::load_many_with_count(query, mappers, conn, count_strategy, first, max)
+    }
+
+    /// Load a vector of structs with dependencies for a given Toql query and count them with a [count strategy](../count/trait.CountStrategy.html).
+    /// 
+    /// Returns a tuple with the structs and the count values, if a count strategy is given. See [load_many()](trait.Load.html#method.load_many).
+    fn load_many_with_count(query: &Query, mappers: &SqlMapperCache, conn: &mut Conn, count_strategy: Option<&dyn CountStrategy>, first:u64, max:u16) 
+        -> Result<LoadedMany<T>, ToqlError>;
+ }
+
+/// Trait to load merged structs by path.
+/// This is implemented by Toql Derive for all derived structs, use [Load](trait.Load.html) to load structs.
+pub trait LoadPath: Sized {
+    /// Load the structs of a path, e.g. the `Vec<Phone>` of a user with path `phones`.
+    fn load_path_from_mysql(path: &str, query: &Query, mappers: &SqlMapperCache, conn: &mut Conn)
+        -> Result<Vec<Self>, ToqlError>;
+
+    /// Load the structs of a path together with a key, that is selected after the struct, e.g. the key of a link table.
+    fn load_path_with_key_from_mysql<K: FromValue>(path: &str, mapper: &SqlMapper, query: &Query, conn: &mut Conn)
+        -> Result<Vec<(K, Self)>, ToqlError>;
+
+    /// Load the merged structs of structs that were loaded with a path, up to the maximum merge depth of the mappers.
+    fn load_path_dependencies_from_mysql(entities: &mut Vec<Self>, path: &str, query: &Query, mappers: &SqlMapperCache, conn: &mut Conn, depth: usize)
+        -> Result<(), ToqlError>;
+}
@@ -1,5 +1,7 @@
 
 use mysql;
+use toql_core::error::ToqlError;
+use toql_core::sql_builder_result::ColumnDescriptor;
 
 
 /// Trait to convert MySQL result row into Toql structs.
@@ -20,10 +22,108 @@ pub fn from_query_result<T: FromResultRow<T>>(result: mysql::QueryResult) -> Res
         .collect()
 }
 
+/// Function to convert MySQL query result into Toql structs together with a key.
+/// The key is read from the column after the struct, e.g. the key of a link table for many-to-many merges.
+pub fn from_query_result_with_key<K, T>(result: mysql::QueryResult) -> Result<Vec<(K, T)>, mysql::error::Error>
+where
+    K: mysql::prelude::FromValue,
+    T: FromResultRow<T>,
+{
+    let mut i: usize = 0;
+    result
+        .map(|row| {
+            i = 0;
+            let mut row = row?;
+            let entity = T::from_row_with_index(&mut row, &mut i)?;
+            let key = match row.take_opt(i + 1) {
+                Some(key) => key?,
+                None => return Err(mysql::error::Error::FromValueError(mysql::Value::NULL)),
+            };
+            Ok((key, entity))
+        })
+        .collect()
+}
+
 /// Function to convert MySQL query result row into Rust struct.
 pub fn from_row<T: FromResultRow<T>>(mut row: mysql::Row) -> Result<T, mysql::error::Error> {
     let mut i: usize = 0;
     T::from_row_with_index(&mut row, &mut i)
 }
 
+/// Function to convert MySQL query result row into a JSON object.
+/// The keys are the selected Toql fields, see [columns()](../../toql_core/sql_builder_result/struct.SqlBuilderResult.html#method.columns).
+/// Fields with a path, such as `author_name`, are put into nested objects, e.g. `{"author": {"name": ..}}`.
+/// See [from_values_dynamic()](fn.from_values_dynamic.html) for details.
+pub fn from_row_dynamic(row: &mysql::Row, columns: &[ColumnDescriptor]) -> Result<serde_json::Value, ToqlError> {
+    from_values_dynamic((0..columns.len()).map(|i| row.as_ref(i).unwrap_or(&mysql::Value::NULL)), columns)
+}
 
+/// Function to convert the values of a MySQL row into a JSON object, see [from_row_dynamic()](fn.from_row_dynamic.html).
+/// Bytes that are not UTF-8, such as BLOB columns, become Base64 strings.
+/// Fails with [ValueClash](../../toql_core/error/enum.ToqlError.html#variant.ValueClash), if a field has the name of a path, e.g. `author` and `author_name`.
+pub fn from_values_dynamic<'a, I>(values: I, columns: &[ColumnDescriptor]) -> Result<serde_json::Value, ToqlError>
+where
+    I: IntoIterator<Item = &'a mysql::Value>,
+{
+    let mut object = serde_json::Map::new();
+    for (value, column) in values.into_iter().zip(columns) {
+        if column.selected {
+            insert_path(&mut object, &column.toql_field, &column.toql_field, json_value(value))?;
+        }
+    }
+    Ok(serde_json::Value::Object(object))
+}
+
+fn insert_path(
+    object: &mut serde_json::Map<String, serde_json::Value>,
+    toql_field: &str,
+    name: &str,
+    value: serde_json::Value,
+) -> Result<(), ToqlError> {
+    match name.find('_') {
+        Some(i) => {
+            let nested = object
+                .entry(name[..i].to_string())
+                .or_insert_with(|| serde_json::Value::Object(serde_json::Map::new()));
+            match nested {
+                serde_json::Value::Object(nested) => insert_path(nested, toql_field, &name[i + 1..], value),
+                _ => Err(ToqlError::ValueClash(toql_field.to_string())),
+            }
+        }
+        None => {
+            if object.contains_key(name) {
+                return Err(ToqlError::ValueClash(toql_field.to_string()));
+            }
+            object.insert(name.to_string(), value);
+            Ok(())
+        }
+    }
+}
+
+// Dates and times become strings in MySQL format, bytes that are not UTF-8 Base64 strings
+fn json_value(value: &mysql::Value) -> serde_json::Value {
+    match value {
+        mysql::Value::NULL => serde_json::Value::Null,
+        mysql::Value::Bytes(b) => serde_json::Value::String(match std::str::from_utf8(b) {
+            Ok(s) => s.to_string(),
+            Err(_) => base64::encode(b),
+        }),
+        mysql::Value::Int(i) => serde_json::Value::from(*i),
+        mysql::Value::UInt(u) => serde_json::Value::from(*u),
+        mysql::Value::Float(f) => serde_json::Number::from_f64(*f)
+            .map_or(serde_json::Value::Null, serde_json::Value::Number),
+        mysql::Value::Date(y, m, d, h, mi, s, us) => serde_json::Value::String(if *us > 0 {
+            format!("{:04}-{:02}-{:02} {:02}:{:02}:{:02}.{:06}", y, m, d, h, mi, s, us)
+        } else {
+            format!("{:04}-{:02}-{:02} {:02}:{:02}:{:02}", y, m, d, h, mi, s)
+        }),
+        mysql::Value::Time(neg, d, h, mi, s, us) => {
+            let hours = *d * 24 + u32::from(*h);
+            serde_json::Value::String(if *us > 0 {
+                format!("{}{:02}:{:02}:{:02}.{:06}", if *neg { "-" } else { "" }, hours, mi, s, us)
+            } else {
+                format!("{}{:02}:{:02}:{:02}", if *neg { "-" } else { "" }, hours, mi, s)
+            })
+        }
+    }
+}
@@ -0,0 +1,68 @@
+use mysql::Value;
+use toql_core::error::ToqlError;
+use toql_core::query_parser::QueryParser;
+use toql_core::sql_builder::SqlBuilder;
+use toql_core::sql_mapper::MapperOptions;
+use toql_core::sql_mapper::SqlMapper;
+use toql_mysql::count::count_from_value;
+use toql_mysql::count::filtered_count_needed;
+
+fn setup_mapper() -> SqlMapper {
+    let mut mapper = SqlMapper::new("User");
+    mapper
+        .join("book", "JOIN Book b ON (id = b.id)")
+        .map_field("id", "id")
+        .map_field("username", "username")
+        .map_field_with_options("book_id", "b.id", MapperOptions::new().count_filter(true));
+    mapper
+}
+
+#[test]
+fn skip_filtered_count() {
+    let mapper = setup_mapper();
+
+    // Without filter both counts are equal
+    let query = QueryParser::parse("*").unwrap();
+    let total_result = SqlBuilder::new().build_count(&mapper, &query).unwrap();
+    let result = SqlBuilder::new().build(&mapper, &query).unwrap();
+    assert!(!filtered_count_needed(&total_result, &result));
+
+    // Count filters belong to both counts
+    let query = QueryParser::parse("*, book_id eq 1").unwrap();
+    let total_result = SqlBuilder::new().build_count(&mapper, &query).unwrap();
+    let result = SqlBuilder::new().build(&mapper, &query).unwrap();
+    assert!(!filtered_count_needed(&total_result, &result));
+}
+
+#[test]
+fn run_filtered_count() {
+    let mapper = setup_mapper();
+    let query = QueryParser::parse("*, username eq 'Ann'").unwrap();
+    let total_result = SqlBuilder::new().build_count(&mapper, &query).unwrap();
+    let result = SqlBuilder::new().build(&mapper, &query).unwrap();
+    assert!(filtered_count_needed(&total_result, &result));
+
+    // Same SQL with other parameters
+    let query = QueryParser::parse("*, book_id eq 1").unwrap();
+    let total_result = SqlBuilder::new().build_count(&mapper, &query).unwrap();
+    let query = QueryParser::parse("*, book_id eq 2").unwrap();
+    let result = SqlBuilder::new().build(&mapper, &query).unwrap();
+    assert!(filtered_count_needed(&total_result, &result));
+}
+
+#[test]
+fn count_value() {
+    assert_eq!(5, count_from_value(Some(Value::UInt(5))).unwrap());
+    assert_eq!(5, count_from_value(Some(Value::Int(5))).unwrap());
+    assert_eq!(5, count_from_value(Some(Value::Bytes(b"5".to_vec()))).unwrap());
+
+    // Missing row and NULL
+    match count_from_value(None) {
+        Err(ToqlError::ValueMissing(c)) => assert_eq!("COUNT(*)", c),
+        _ => panic!("Missing row must fail"),
+    }
+    match count_from_value(Some(Value::NULL)) {
+        Err(ToqlError::ValueMissing(c)) => assert_eq!("COUNT(*)", c),
+        _ => panic!("NULL must fail"),
+    }
+}
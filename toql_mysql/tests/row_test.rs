@@ -0,0 +1,62 @@
+use mysql::Value;
+use toql_core::error::ToqlError;
+use toql_core::query_parser::QueryParser;
+use toql_core::sql_builder::SqlBuilder;
+use toql_core::sql_builder_result::ColumnDescriptor;
+use toql_core::sql_mapper::SqlMapper;
+use toql_mysql::row::from_values_dynamic;
+
+fn column(toql_field: &str) -> ColumnDescriptor {
+    ColumnDescriptor { toql_field: toql_field.to_string(), selected: true }
+}
+
+#[test]
+fn values_into_object() {
+    let mut mapper = SqlMapper::new("Book b");
+    mapper
+        .join("author", "JOIN User a ON (b.author_id = a.id)")
+        .map_field("id", "b.id")
+        .map_field("title", "b.title")
+        .map_field("author_name", "a.name");
+    let query = QueryParser::parse("id, author_name").unwrap();
+    let result = SqlBuilder::new().build(&mapper, &query).unwrap();
+
+    let values = vec![Value::UInt(1), Value::NULL, Value::Bytes(b"Ann".to_vec())];
+    let object = from_values_dynamic(&values, result.columns()).unwrap();
+    assert_eq!(r#"{"author":{"name":"Ann"},"id":1}"#, object.to_string());
+}
+
+#[test]
+fn convert_values() {
+    let columns = vec![column("bytes"), column("blob"), column("created"), column("price"), column("empty")];
+    let values = vec![
+        Value::Bytes("Zürich".as_bytes().to_vec()),
+        Value::Bytes(vec![0xff, 0x00, 0x10]),
+        Value::Date(2019, 3, 1, 12, 30, 0, 0),
+        Value::Float(2.5),
+        Value::NULL,
+    ];
+    let object = from_values_dynamic(&values, &columns).unwrap();
+    assert_eq!("Zürich", object["bytes"]);
+    assert_eq!("/wAQ", object["blob"]);
+    assert_eq!("2019-03-01 12:30:00", object["created"]);
+    assert_eq!(2.5, object["price"]);
+    assert!(object["empty"].is_null());
+}
+
+#[test]
+fn refuse_clash() {
+    let values = vec![Value::UInt(1), Value::Bytes(b"Ann".to_vec())];
+
+    let columns = vec![column("author"), column("author_name")];
+    match from_values_dynamic(&values, &columns) {
+        Err(ToqlError::ValueClash(f)) => assert_eq!("author_name", f),
+        _ => panic!("Path `author` must clash with field `author`"),
+    }
+
+    let columns = vec![column("author_name"), column("author")];
+    match from_values_dynamic(&values, &columns) {
+        Err(ToqlError::ValueClash(f)) => assert_eq!("author", f),
+        _ => panic!("Field `author` must clash with path `author`"),
+    }
+}